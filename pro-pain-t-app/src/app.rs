@@ -1,11 +1,16 @@
 use crate::components::canvas_area::CanvasArea;
 use crate::components::canvas_size_window::CanvasSizeWindow;
+use crate::components::export_window::ExportWindow;
+use crate::components::import_image_window::ImportImageWindow;
 use crate::components::layer_panel::LayerPanel;
 use crate::components::new_project_window::NewProjectWindow;
+use crate::components::shortcuts_window::ShortcutsWindow;
 use crate::components::status_bar::StatusBar;
 use crate::components::tool_palette::ToolPalette;
+use crate::events::document_state::{restore_recovered_project, sync_document_state};
+use crate::events::shortcuts::{load_shortcuts, register_shortcuts};
 use crate::events::listeners::{
-    canvas_size_listener, create_new_project_listener, export_project_listener, import_as_layer_listener, open_project_listener, redo_listener, save_project_listener, undo_listener
+    autosave_listener, canvas_size_listener, copy_listener, create_new_project_listener, cut_listener, export_project_listener, fill_window_listener, fit_to_window_listener, import_as_layer_listener, open_image_listener, open_project_listener, paste_as_layer_listener, paste_listener, redo_listener, save_project_listener, shortcuts_listener, undo_listener
 };
 use crate::state::workspace_state::WorkspaceState;
 use crate::structs::project::Project;
@@ -27,15 +32,34 @@ pub fn App() -> impl IntoView {
     let is_new_project_window_open = RwSignal::new(false);
 
     let is_canvas_size_open = RwSignal::new(false);
+    let pending_import = RwSignal::new(None);
+    let is_export_open = RwSignal::new(false);
+    let is_shortcuts_open = RwSignal::new(false);
 
     create_new_project_listener(is_new_project_window_open);
     open_project_listener(project);
+    open_image_listener(project);
     save_project_listener(project);
-    import_as_layer_listener(project);
-    export_project_listener(project);
+    import_as_layer_listener(project, pending_import);
+    export_project_listener(is_export_open);
     canvas_size_listener(is_canvas_size_open);
-    undo_listener(project);
-    redo_listener(project);
+    shortcuts_listener(is_shortcuts_open);
+    fit_to_window_listener(project, view_state);
+    fill_window_listener(project, view_state);
+    undo_listener(project, workspace_state);
+    redo_listener(project, workspace_state);
+    copy_listener(project, workspace_state);
+    cut_listener(project, workspace_state);
+    paste_listener(project, workspace_state);
+    paste_as_layer_listener(project, workspace_state);
+
+    autosave_listener(project);
+
+    load_shortcuts(workspace_state);
+    register_shortcuts(project, view_state, workspace_state);
+
+    sync_document_state(project);
+    restore_recovered_project(project);
 
     view! {
         <div class="app-root">
@@ -52,6 +76,15 @@ pub fn App() -> impl IntoView {
             <CanvasSizeWindow
                 is_open = is_canvas_size_open
             />
+            <ExportWindow
+                is_open = is_export_open
+            />
+            <ShortcutsWindow
+                is_open = is_shortcuts_open
+            />
+            <ImportImageWindow
+                pending_import = pending_import
+            />
             <NewProjectWindow
                 dialog_ref = new_project_window_ref
                 is_open = is_new_project_window_open
@@ -0,0 +1,39 @@
+use crate::structs::blend_mode::BlendMode;
+use leptos::prelude::*;
+
+#[component]
+pub fn BlendModeSelect(
+    #[prop(into)] value: Signal<BlendMode>,
+    on_change: Callback<BlendMode>,
+    #[prop(optional, into)] disabled: Signal<bool>,
+) -> impl IntoView {
+    view! {
+        <select
+            title="Blend mode"
+            prop:disabled=move || disabled.get()
+            on:change=move |ev| {
+                let mode = event_target_value(&ev)
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(BlendMode::from_id);
+                if let Some(mode) = mode {
+                    on_change.run(mode);
+                }
+            }
+        >
+            {BlendMode::ALL
+                .into_iter()
+                .map(|mode| {
+                    view! {
+                        <option
+                            value=mode.id().to_string()
+                            prop:selected=move || value.get() == mode
+                        >
+                            {mode.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+    }
+}
@@ -0,0 +1,59 @@
+use crate::tools::brush_engine::BrushSettings;
+use leptos::prelude::*;
+
+/// Tip and pressure settings of the brush engine.
+#[component]
+pub fn BrushOptions(
+    settings: RwSignal<BrushSettings>,
+    /// What the opacity slider is called, e.g. "Strength" for the eraser.
+    #[prop(default = "Opacity")]
+    opacity_label: &'static str,
+) -> impl IntoView {
+    // Percent slider bound to one field of the settings
+    let slider = move |label: &'static str,
+                       title: &'static str,
+                       min: u32,
+                       get: fn(&BrushSettings) -> f32,
+                       set: fn(&mut BrushSettings, f32)| {
+        view! {
+            <div style="display:flex; align-items:center; gap:8px; width:100%; font-size:0.8rem; color:white;" title=title>
+                <span style="width:56px;">{label}</span>
+                <input
+                    type="range"
+                    min=min.to_string()
+                    max="100"
+                    step="1"
+                    prop:value=move || ((settings.with(get) * 100.0).round() as u32).to_string()
+                    style="width:60px; flex:1;"
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev).parse::<f32>().unwrap_or(100.0) / 100.0;
+                        settings.update(|s| set(s, value.clamp(0.0, 1.0)));
+                    }
+                />
+            </div>
+        }
+    };
+
+    view! {
+        {slider("Hardness", "Part of the tip painted at full strength", 0, |s| s.hardness, |s, v| s.hardness = v)}
+        {slider("Spacing", "Distance between dabs, relative to the tip size", 1, |s| s.spacing, |s, v| s.spacing = v)}
+        {slider("Flow", "Strength of each dab, overlapping dabs build up", 1, |s| s.flow, |s, v| s.flow = v)}
+        {slider(opacity_label, "Most one stroke can cover", 1, |s| s.opacity, |s, v| s.opacity = v)}
+        <label style="width:100%; font-size:0.8rem; color:white;">
+            <input
+                type="checkbox"
+                prop:checked=move || settings.with(|s| s.pressure_size)
+                on:change=move |ev| settings.update(|s| s.pressure_size = event_target_checked(&ev))
+            />
+            "Pressure → size"
+        </label>
+        <label style="width:100%; font-size:0.8rem; color:white;">
+            <input
+                type="checkbox"
+                prop:checked=move || settings.with(|s| s.pressure_opacity)
+                on:change=move |ev| settings.update(|s| s.pressure_opacity = event_target_checked(&ev))
+            />
+            "Pressure → opacity"
+        </label>
+    }
+}
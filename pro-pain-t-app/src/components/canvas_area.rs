@@ -1,14 +1,26 @@
-use crate::view_state::ProjectViewState;
+use crate::view_state::{PinchGesture, ProjectViewState};
 use crate::{state::workspace_state::WorkspaceState, structs::project::Project};
 use leptos::prelude::*;
 use leptos::*;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, PointerEvent, wasm_bindgen::JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, PointerEvent, WheelEvent, wasm_bindgen::JsCast};
 
-use crate::render::canvas_renderer::{ViewTransform, composite_layers, draw_project_viewport};
+use crate::render::canvas_renderer::{ViewTransform, draw_project_viewport};
+use crate::render::tile_compositor::TileCompositor;
+use crate::events::shortcuts::is_typing_target;
 use crate::tools::context::ToolContext;
 use crate::tools::select::{commit_selection, SelectionBuffer, SelectionState};
+use crate::tools::selection_mask::SelectionMask;
+use crate::tools::symmetry::Symmetry;
+use crate::tools::tools::Tool;
 use wasm_bindgen::prelude::*;
 
+/// How often the marching ants advance, in milliseconds.
+const ANTS_INTERVAL_MS: i32 = 150;
+/// Dash length of the marching ants in screen pixels.
+const ANTS_DASH_PX: f64 = 4.0;
+/// Dash length of the symmetry axes in screen pixels.
+const SYMMETRY_DASH_PX: f64 = 6.0;
+
 
 #[component]
 pub fn CanvasArea() -> impl IntoView {
@@ -27,6 +39,16 @@ pub fn CanvasArea() -> impl IntoView {
 
     let canvas_size_trigger = RwSignal::new(0u32);
 
+    let compositor = StoredValue::new_local(TileCompositor::new());
+    let composite_version = RwSignal::new(0u32);
+
+    let pinch = StoredValue::new(PinchGesture::default());
+
+    // Outlines of an unfinished lasso, line or shape, only change when the paths do
+    let preview_paths = Memo::new(move |_| current_tool.with(|tool| tool.preview_paths()));
+    // Advances the dash offset of the marching ants
+    let ants_phase = RwSignal::new(0u32);
+
     let on_pointer_down = move |ev: PointerEvent| {
         if ev.button() != 0 {
             return;
@@ -44,6 +66,19 @@ pub fn CanvasArea() -> impl IntoView {
             None => return,
         };
 
+        // A second finger turns the touch into a pinch and takes back the stroke
+        // the first one started
+        if ev.pointer_type() == "touch" {
+            let (x, y) = client_to_viewport(&canvas, &ev);
+            let mut is_pinch = false;
+            pinch.update_value(|pinch| is_pinch = pinch.pointer_down(ev.pointer_id(), x, y));
+            if is_pinch {
+                current_tool.update(|t| t.on_pointer_cancel(&project, &workspace_state_for_down));
+                ev.prevent_default();
+                return;
+            }
+        }
+
         let zoom = view_state.zoom_factor.get();
         let pan_x = view_state.pan_x.get();
         let pan_y = view_state.pan_y.get();
@@ -70,6 +105,18 @@ pub fn CanvasArea() -> impl IntoView {
             None => return,
         };
 
+        if ev.pointer_type() == "touch" {
+            let (x, y) = client_to_viewport(&canvas, &ev);
+            let mut is_pinch = false;
+            pinch.update_value(|pinch| {
+                is_pinch = pinch.pointer_move(ev.pointer_id(), x, y, &view_state)
+            });
+            if is_pinch {
+                ev.prevent_default();
+                return;
+            }
+        }
+
         let zoom = view_state.zoom_factor.get();
         let pan_x = view_state.pan_x.get();
         let pan_y = view_state.pan_y.get();
@@ -96,6 +143,18 @@ pub fn CanvasArea() -> impl IntoView {
             None => return,
         };
 
+        if ev.pointer_type() == "touch" {
+            let mut was_pinch = false;
+            pinch.update_value(|pinch| {
+                was_pinch = pinch.is_active();
+                pinch.pointer_up(ev.pointer_id());
+            });
+            if was_pinch {
+                ev.prevent_default();
+                return;
+            }
+        }
+
         let zoom = view_state.zoom_factor.get();
         let pan_x = view_state.pan_x.get();
         let pan_y = view_state.pan_y.get();
@@ -115,6 +174,36 @@ pub fn CanvasArea() -> impl IntoView {
         ev.prevent_default();
     };
 
+    // Ctrl/Cmd + wheel zooms around the cursor (trackpad pinches arrive the same
+    // way), any other scrolling pans
+    let on_wheel = move |ev: WheelEvent| {
+        let canvas: HtmlCanvasElement = match canvas_ref.get() {
+            Some(c) => c,
+            None => return,
+        };
+        let rect = canvas.get_bounding_client_rect();
+
+        let unit = match ev.delta_mode() {
+            WheelEvent::DOM_DELTA_LINE => 16.0,
+            WheelEvent::DOM_DELTA_PAGE => rect.height(),
+            _ => 1.0,
+        };
+        let dx = (ev.delta_x() * unit) as f32;
+        let dy = (ev.delta_y() * unit) as f32;
+
+        if ev.ctrl_key() || ev.meta_key() {
+            let factor = (-dy * ProjectViewState::WHEEL_ZOOM_SENSITIVITY).exp();
+            let anchor_x = (ev.client_x() as f64 - rect.left()) as f32;
+            let anchor_y = (ev.client_y() as f64 - rect.top()) as f32;
+            view_state.zoom_at(factor, anchor_x, anchor_y);
+        } else if ev.shift_key() && dx == 0.0 {
+            view_state.pan_by(-dy, 0.0);
+        } else {
+            view_state.pan_by(-dx, -dy);
+        }
+        ev.prevent_default();
+    };
+
     Effect::new(move || {
         if let Some(window) = web_sys::window() {
             let trigger = canvas_size_trigger;
@@ -143,7 +232,28 @@ pub fn CanvasArea() -> impl IntoView {
                         }
                     });
                     ws.selection.set(None);
-                    tool.update(|t| t.on_pointer_cancel());
+                    tool.update(|t| t.on_pointer_cancel(&project, &ws));
+                    ev.prevent_default();
+                    return;
+                }
+
+                if is_typing_target(&ev) {
+                    return;
+                }
+                let Some(canvas) = canvas_ref.get_untracked() else { return };
+                let ctx = ToolContext {
+                    canvas: &canvas,
+                    project: &project,
+                    view_state: &view_state,
+                    workspace_state: &ws,
+                    zoom: view_state.zoom_factor.get_untracked(),
+                    pan_x: view_state.pan_x.get_untracked(),
+                    pan_y: view_state.pan_y.get_untracked(),
+                    selected_layer: ws.selected_layer_id.get_untracked(),
+                };
+                let mut handled = false;
+                tool.update(|t| handled = t.on_key_down(&ev, &ctx));
+                if handled {
                     ev.prevent_default();
                 }
             }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
@@ -154,6 +264,23 @@ pub fn CanvasArea() -> impl IntoView {
         }
     });
 
+    Effect::new(move || {
+        if let Some(window) = web_sys::window() {
+            let closure = Closure::wrap(Box::new(move || {
+                if workspace_state.selection.with_untracked(|selection| selection.is_some()) {
+                    ants_phase.update(|phase| *phase = phase.wrapping_add(1));
+                }
+            }) as Box<dyn FnMut()>);
+
+            let _ = window.set_interval_with_callback_and_timeout_and_arguments_0(
+                closure.as_ref().unchecked_ref(),
+                ANTS_INTERVAL_MS,
+            );
+
+            closure.forget();
+        }
+    });
+
     Effect::new(move |_| {
         let canvas: HtmlCanvasElement = match canvas_ref.get() {
             Some(c) => c,
@@ -186,6 +313,30 @@ pub fn CanvasArea() -> impl IntoView {
         });
     });
 
+    let background_color = Memo::new(move |_| project.with(|project| project.background_color));
+
+    // Keeps the tile cache in sync with the layers. Tools report the areas they
+    // touched through `WorkspaceState::mark_dirty`; anything else re-blends everything.
+    // Only what is drawn is tracked, so recording history or flagging unsaved
+    // changes through the project signal doesn't recomposite.
+    Effect::new(move |_| {
+        let background_color = background_color.get();
+        let (layers, width, height) = project.with_untracked(|project| (project.layers, project.width, project.height));
+        let (width, height) = (width.get(), height.get());
+        let dirty = workspace_state.take_dirty();
+
+        layers.with(|layers| {
+            let proj_w = layers.first().map_or(width, |layer| layer.canvas.width);
+            let proj_h = layers.first().map_or(height, |layer| layer.canvas.height);
+
+            compositor.update_value(|compositor| {
+                compositor.update(layers, background_color, proj_w, proj_h, dirty);
+            });
+        });
+
+        composite_version.update(|v| *v = v.wrapping_add(1));
+    });
+
     Effect::new(move |_| {
         let canvas: HtmlCanvasElement = match canvas_ref.get() {
             Some(c) => c,
@@ -208,6 +359,7 @@ pub fn CanvasArea() -> impl IntoView {
         let device_pixel_ratio = window.device_pixel_ratio();
 
         let rect = canvas.get_bounding_client_rect();
+        view_state.viewport_size.set_value((rect.width() as f32, rect.height() as f32));
         let cw = (rect.width() * device_pixel_ratio).max(1.0).round() as u32;
         let ch = (rect.height() * device_pixel_ratio).max(1.0).round() as u32;
         if canvas.width() != cw {
@@ -217,46 +369,13 @@ pub fn CanvasArea() -> impl IntoView {
             canvas.set_height(ch);
         }
 
-        project.with(|project| {
-            let layers = project.layers.get();
-            let proj_w = if layers.is_empty() {
-                project.width.get()
-            } else {
-                layers[0].canvas.width
-            };
-            let proj_h = if layers.is_empty() {
-                project.height.get()
-            } else {
-                layers[0].canvas.height
-            };
-
-            if layers.is_empty() {
-                let pixels = vec![0u8; (proj_w * proj_h * 4) as usize];
-                draw_project_viewport(
-                    &ctx,
-                    cw,
-                    ch,
-                    &pixels,
-                    proj_w,
-                    proj_h,
-                    ViewTransform {
-                        zoom,
-                        pan_x,
-                        pan_y,
-                        device_pixel_ratio,
-                    },
-                );
-                return;
-            }
-
-            let (pixels, proj_w, proj_h) = composite_layers(&layers);
+        let _ = composite_version.get();
+        compositor.with_value(|compositor| {
             draw_project_viewport(
                 &ctx,
                 cw,
                 ch,
-                &pixels,
-                proj_w,
-                proj_h,
+                compositor,
                 ViewTransform {
                     zoom,
                     pan_x,
@@ -266,6 +385,28 @@ pub fn CanvasArea() -> impl IntoView {
             );
         });
 
+        // The axes only matter to the tools that paint with symmetry
+        let symmetry = workspace_state_for_render.symmetry.get();
+        let paints = current_tool.with(|tool| matches!(tool, Tool::Pen(_) | Tool::Brush(_) | Tool::Eraser(_)));
+        if symmetry.is_active() && paints {
+            let (width, height) = project.with(|project| (project.width.get(), project.height.get()));
+            draw_symmetry_axes(
+                &ctx,
+                &symmetry,
+                width,
+                height,
+                ViewTransform {
+                    zoom,
+                    pan_x,
+                    pan_y,
+                    device_pixel_ratio,
+                },
+            );
+        }
+
+        let ants_offset = ants_phase.get() as f64;
+        // Other tools keep the selection but can't resize it
+        let show_handles = current_tool.with(|tool| matches!(tool, Tool::Select(_)));
         let active_layer = workspace_state_for_render.selected_layer_id.get();
         workspace_state_for_render.selection.with(|selection| {
             if let Some(selection) = selection {
@@ -275,6 +416,24 @@ pub fn CanvasArea() -> impl IntoView {
                 draw_selection_overlay(
                     &ctx,
                     selection,
+                    ants_offset,
+                    show_handles,
+                    ViewTransform {
+                        zoom,
+                        pan_x,
+                        pan_y,
+                        device_pixel_ratio,
+                    },
+                );
+            }
+        });
+
+        preview_paths.with(|paths| {
+            for path in paths {
+                draw_preview_path(
+                    &ctx,
+                    path,
+                    ants_offset,
                     ViewTransform {
                         zoom,
                         pan_x,
@@ -292,7 +451,11 @@ pub fn CanvasArea() -> impl IntoView {
             on:pointerdown=on_pointer_down
             on:pointermove=on_pointer_move
             on:pointerup=on_pointer_up
-            on:pointercancel=move |_| current_tool.update(|t| t.on_pointer_cancel())
+            on:pointercancel=move |ev: PointerEvent| {
+                pinch.update_value(|pinch| pinch.pointer_up(ev.pointer_id()));
+                current_tool.update(|t| t.on_pointer_cancel(&project, &workspace_state));
+            }
+            on:wheel=on_wheel
             style=move || {
                 let cursor = current_tool.get().cursor();
                 let _ = view_state.zoom_factor.get();
@@ -315,7 +478,22 @@ pub fn CanvasArea() -> impl IntoView {
     }
 }
 
-fn draw_selection_overlay(ctx: &CanvasRenderingContext2d, selection: &SelectionState, t: ViewTransform) {
+/// Pointer position in CSS pixels relative to the canvas element.
+fn client_to_viewport(canvas: &HtmlCanvasElement, ev: &PointerEvent) -> (f32, f32) {
+    let rect = canvas.get_bounding_client_rect();
+    (
+        (ev.client_x() as f64 - rect.left()) as f32,
+        (ev.client_y() as f64 - rect.top()) as f32,
+    )
+}
+
+fn draw_selection_overlay(
+    ctx: &CanvasRenderingContext2d,
+    selection: &SelectionState,
+    ants_offset: f64,
+    show_handles: bool,
+    t: ViewTransform,
+) {
     let scale = (t.zoom as f64) * t.device_pixel_ratio;
     let tx = (t.pan_x as f64) * t.device_pixel_ratio;
     let ty = (t.pan_y as f64) * t.device_pixel_ratio;
@@ -330,13 +508,22 @@ fn draw_selection_overlay(ctx: &CanvasRenderingContext2d, selection: &SelectionS
     if rect.is_empty() {
         return;
     }
-    ctx.set_stroke_style_str("#4a7cff");
-    ctx.stroke_rect(rect.x as f64, rect.y as f64, rect.w as f64, rect.h as f64);
 
     if let Some(buffer) = selection.buffer.as_ref() {
         draw_selection_pixels(ctx, rect, buffer);
     }
 
+    ctx.begin_path();
+    match selection.mask.as_ref() {
+        Some(mask) => trace_mask_outline(ctx, rect, mask),
+        None => ctx.rect(rect.x as f64, rect.y as f64, rect.w as f64, rect.h as f64),
+    }
+    stroke_marching_ants(ctx, scale, ants_offset);
+
+    if !show_handles {
+        return;
+    }
+
     let handle_size = 6.0 / scale.max(0.0001);
     let hs = handle_size / 2.0;
     let x0 = rect.x as f64;
@@ -366,6 +553,77 @@ fn draw_selection_overlay(ctx: &CanvasRenderingContext2d, selection: &SelectionS
     }
 }
 
+/// Adds the mask outline to the current path, stretched over `rect`.
+fn trace_mask_outline(ctx: &CanvasRenderingContext2d, rect: &crate::tools::select::SelectionRect, mask: &SelectionMask) {
+    let sx = rect.w as f64 / mask.width.max(1) as f64;
+    let sy = rect.h as f64 / mask.height.max(1) as f64;
+    for [x0, y0, x1, y1] in mask.outline() {
+        ctx.move_to(rect.x as f64 + *x0 as f64 * sx, rect.y as f64 + *y0 as f64 * sy);
+        ctx.line_to(rect.x as f64 + *x1 as f64 * sx, rect.y as f64 + *y1 as f64 * sy);
+    }
+}
+
+/// Strokes the current path white with black dashes on top, shifted by
+/// `offset` so the dashes crawl along it.
+fn stroke_marching_ants(ctx: &CanvasRenderingContext2d, scale: f64, offset: f64) {
+    let dash = ANTS_DASH_PX / scale.max(0.0001);
+    let dashes = web_sys::js_sys::Array::of2(&JsValue::from_f64(dash), &JsValue::from_f64(dash));
+
+    ctx.set_stroke_style_str("#ffffff");
+    ctx.stroke();
+
+    let _ = ctx.set_line_dash(&dashes);
+    ctx.set_line_dash_offset(-offset * dash / 2.0);
+    ctx.set_stroke_style_str("#000000");
+    ctx.stroke();
+
+    let _ = ctx.set_line_dash(&web_sys::js_sys::Array::new());
+    ctx.set_line_dash_offset(0.0);
+}
+
+/// Outline of a selection that is still being drawn, e.g. an open lasso.
+fn draw_preview_path(ctx: &CanvasRenderingContext2d, path: &[(f32, f32)], ants_offset: f64, t: ViewTransform) {
+    let Some((first, rest)) = path.split_first() else { return };
+
+    let scale = (t.zoom as f64) * t.device_pixel_ratio;
+    let tx = (t.pan_x as f64) * t.device_pixel_ratio;
+    let ty = (t.pan_y as f64) * t.device_pixel_ratio;
+    let _ = ctx.set_transform(scale, 0.0, 0.0, scale, tx, ty);
+    ctx.set_line_width((1.0 / scale.max(0.0001)).max(0.5));
+
+    ctx.begin_path();
+    ctx.move_to(first.0 as f64, first.1 as f64);
+    for (x, y) in rest {
+        ctx.line_to(*x as f64, *y as f64);
+    }
+    stroke_marching_ants(ctx, scale, ants_offset);
+}
+
+/// Dashed symmetry axes with a ring marking their center.
+fn draw_symmetry_axes(ctx: &CanvasRenderingContext2d, symmetry: &Symmetry, width: u32, height: u32, t: ViewTransform) {
+    let scale = (t.zoom as f64) * t.device_pixel_ratio;
+    let tx = (t.pan_x as f64) * t.device_pixel_ratio;
+    let ty = (t.pan_y as f64) * t.device_pixel_ratio;
+    let _ = ctx.set_transform(scale, 0.0, 0.0, scale, tx, ty);
+    ctx.set_line_width((1.0 / scale.max(0.0001)).max(0.5));
+
+    ctx.begin_path();
+    for (start, end) in symmetry.axes(width, height) {
+        ctx.move_to(start.0 as f64, start.1 as f64);
+        ctx.line_to(end.0 as f64, end.1 as f64);
+    }
+    let dash = SYMMETRY_DASH_PX / scale.max(0.0001);
+    let _ = ctx.set_line_dash(&web_sys::js_sys::Array::of2(&JsValue::from_f64(dash), &JsValue::from_f64(dash)));
+    ctx.set_stroke_style_str("rgba(0, 170, 255, 0.85)");
+    ctx.stroke();
+    let _ = ctx.set_line_dash(&web_sys::js_sys::Array::new());
+
+    let (cx, cy) = symmetry.center_in(width, height);
+    ctx.begin_path();
+    let _ = ctx.arc(cx as f64, cy as f64, 4.0 / scale.max(0.0001), 0.0, std::f64::consts::TAU);
+    ctx.stroke();
+}
+
 fn draw_selection_pixels(ctx: &CanvasRenderingContext2d, rect: &crate::tools::select::SelectionRect, buffer: &SelectionBuffer) {
     if buffer.width == 0 || buffer.height == 0 || rect.w <= 0 || rect.h <= 0 {
         return;
@@ -379,7 +637,7 @@ fn draw_selection_pixels(ctx: &CanvasRenderingContext2d, rect: &crate::tools::se
         .dyn_into::<CanvasRenderingContext2d>()
         .unwrap();
 
-    let rgba = buffer_to_rgba(buffer);
+    let rgba = buffer.to_rgba();
     let image_data = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
         wasm_bindgen::Clamped(&rgba),
         buffer.width,
@@ -398,17 +656,6 @@ fn draw_selection_pixels(ctx: &CanvasRenderingContext2d, rect: &crate::tools::se
     );
 }
 
-fn buffer_to_rgba(buffer: &SelectionBuffer) -> Vec<u8> {
-    let mut out = Vec::with_capacity((buffer.width * buffer.height * 4) as usize);
-    for c in &buffer.pixels {
-        out.push(c.r);
-        out.push(c.g);
-        out.push(c.b);
-        out.push((c.alpha * 255.0) as u8);
-    }
-    out
-}
-
 fn create_offscreen_canvas(width: u32, height: u32) -> HtmlCanvasElement {
     let document = web_sys::window()
         .and_then(|w| w.document())
@@ -31,15 +31,7 @@ pub fn CanvasSizeWindow(is_open: RwSignal<bool>) -> impl IntoView {
     }
 
     let on_resize_canvas = move |new_w: u32, new_h: u32| {
-        project.update(|project| {
-            project.layers.update(|layers_vec| {
-                for layer in layers_vec.iter_mut() {
-                    layer.resize_canvas(new_w, new_h);
-                }
-            });
-            project.width.set(new_w);
-            project.height.set(new_h);
-        });
+        project.update(|project| project.resize_canvas(new_w, new_h));
     };
 
     let on_width_input = move |ev: leptos::ev::Event| {
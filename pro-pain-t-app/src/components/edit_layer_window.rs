@@ -1,3 +1,4 @@
+use crate::components::blend_mode_select::BlendModeSelect;
 use crate::components::color_picker::ColorPicker;
 use crate::structs::project::Project;
 use leptos::{html::Dialog, logging, prelude::*};
@@ -8,23 +9,31 @@ pub fn EditLayerWindow(
     is_open: RwSignal<bool>,
     id: usize,
 ) -> impl IntoView {
-    let project = use_context::<RwSignal<Project>>().expect("Project context missing")
-        .get();
+    let project = use_context::<RwSignal<Project>>().expect("Project context missing");
 
-    let layers = project.layers.get();
+    let layers = project.get().layers.get();
     let layer = layers.iter().find(|l| l.id == id).expect("Layer with specified ID couldn't be found");
 
     let title = RwSignal::new(layer.title.clone());
     let color = RwSignal::new(layer.canvas.background_color);
+    let opacity = RwSignal::new(layer.opacity);
+    let blend_mode = RwSignal::new(layer.blend_mode);
 
     let edit_layer = move || {
-        project.layers.update(|layers| {
-            if let Some(index) = layers.iter_mut().position(|l| l.id == id) {
-                layers[index].canvas.background_color = color.get();
-                layers[index].title = title.get();
-                logging::log!("Layer {} edited", id);
-            }
-        });
+        let Some(mut properties) = project
+            .get()
+            .layers
+            .with(|layers| layers.iter().find(|l| l.id == id).map(|l| l.properties()))
+        else {
+            return;
+        };
+
+        properties.background_color = color.get();
+        properties.title = title.get();
+        properties.opacity = opacity.get();
+        properties.blend_mode = blend_mode.get();
+        project.update(|project| project.set_layer_properties(id, properties));
+        logging::log!("Layer {} edited", id);
     };
 
     view! {
@@ -48,6 +57,27 @@ pub fn EditLayerWindow(
                         />
                         Background color:
                         <ColorPicker color=color style:color = "#ffffff" style:margin="2px" style:padding="2px"/>
+                        "Opacity:"
+                        <div style="display:flex; align-items:center; gap:8px;">
+                            <input
+                                type="range"
+                                min="0"
+                                max="100"
+                                step="1"
+                                prop:value = move || (opacity.get() * 100.0).round().to_string()
+                                on:input = move |ev| {
+                                    let percent = event_target_value(&ev).parse::<f32>().unwrap_or(100.0);
+                                    opacity.set((percent / 100.0).clamp(0.0, 1.0));
+                                }
+                                id="edit-layer-opacity"
+                            />
+                            <span style="width:40px;">{move || format!("{:.0}%", opacity.get() * 100.0)}</span>
+                        </div>
+                        "Blend mode:"
+                        <BlendModeSelect
+                            value = blend_mode
+                            on_change = Callback::new(move |mode| blend_mode.set(mode))
+                        />
                 <div
                 style="display:flex; justify-content:space-between;">
                     <button
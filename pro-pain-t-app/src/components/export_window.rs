@@ -0,0 +1,234 @@
+use crate::components::color_picker::ColorPicker;
+use crate::render::canvas_renderer::composite_layers;
+use crate::structs::color::Color;
+use crate::structs::project::Project;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use pro_pain_t_shared::dtos::export_object::ExportObjectDto;
+use pro_pain_t_shared::dtos::export_options::{ExportFormat, ExportOptionsDto, PngCompression};
+use pro_pain_t_shared::dtos::image::ImageDto;
+use tauri_sys::core::invoke;
+
+/// Upper bound of the scale input, the backend still rejects images too large to encode.
+const MAX_SCALE_PERCENT: u32 = 1000;
+
+#[component]
+pub fn ExportWindow(is_open: RwSignal<bool>) -> impl IntoView {
+    let project = use_context::<RwSignal<Project>>().expect("Project context missing");
+
+    let defaults = ExportOptionsDto::default();
+    let format = RwSignal::new(defaults.format);
+    let jpeg_quality = RwSignal::new(defaults.jpeg_quality);
+    let png_compression = RwSignal::new(defaults.png_compression);
+    let flatten = RwSignal::new(true);
+    let background = RwSignal::new(Color::default_white());
+    let scale_percent = RwSignal::new(100u32);
+
+    // The flatten color starts out as the document background every time the window opens
+    Effect::new(move |_| {
+        if is_open.get() {
+            background.set(project.with_untracked(|project| project.background_color));
+        }
+    });
+
+    let must_flatten = move || !format.get().supports_alpha();
+
+    let on_export = move || {
+        let (raw_image, width, height) = project.with_untracked(|project| {
+            // Composited onto transparency, the backend flattens if asked to
+            project
+                .layers
+                .with_untracked(|layers| composite_layers(layers, Color::transparent()))
+        });
+        if raw_image.is_empty() {
+            return;
+        }
+
+        let flatten_background = (flatten.get_untracked() || must_flatten()).then(|| {
+            let [r, g, b, _] = background.get_untracked().to_rgba8();
+            [r, g, b]
+        });
+        let options = ExportOptionsDto {
+            format: format.get_untracked(),
+            jpeg_quality: jpeg_quality.get_untracked(),
+            png_compression: png_compression.get_untracked(),
+            flatten_background,
+            scale: scale_percent.get_untracked() as f32 / 100.0,
+        };
+
+        spawn_local(async move {
+            invoke::<()>(
+                "export_project_command",
+                ExportObjectDto::new(ImageDto::new(width, height, raw_image), options),
+            )
+            .await;
+        });
+    };
+
+    let on_key_down = move |ev: leptos::ev::KeyboardEvent| {
+        if ev.key() == "Escape" {
+            is_open.set(false);
+            ev.prevent_default();
+            ev.stop_propagation();
+        }
+    };
+
+    view! {
+        <div
+            style=move || format!(
+                "position:fixed; inset:0; background:rgba(0,0,0,0.4); display:{}; align-items:center; justify-content:center; z-index:1000;",
+                if is_open.get() { "flex" } else { "none" }
+            )
+        >
+            <div
+                style="
+                    background:#2b2b2b;
+                    padding:1rem 1.25rem;
+                    border-radius:4px;
+                    color:#f5f5f5;
+                    min-width:260px;
+                    font-family:system-ui, sans-serif;
+                    box-shadow:0 12px 30px rgba(0,0,0,0.7);
+                "
+                tabindex="-1"
+                on:keydown=on_key_down
+            >
+                <h2 style="margin:0 0 0.75rem 0; font-size:0.95rem;">"Export"</h2>
+                <table style="width:100%; font-size:0.8rem;">
+                    <tr>
+                        <td style="padding:0.15rem 0.5rem 0.15rem 0;">"Format"</td>
+                        <td style="padding:0.15rem 0;">
+                            <select
+                                style="width:100%;"
+                                on:change=move |ev| {
+                                    let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                                    if let Some(selected) = ExportFormat::ALL.get(index) {
+                                        format.set(*selected);
+                                    }
+                                }
+                            >
+                                {ExportFormat::ALL
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, option)| {
+                                        view! {
+                                            <option
+                                                value=index.to_string()
+                                                prop:selected=move || format.get() == option
+                                            >
+                                                {option.label()}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </td>
+                    </tr>
+                    <Show when=move || format.get() == ExportFormat::Jpeg fallback=|| ()>
+                        <tr>
+                            <td style="padding:0.15rem 0.5rem 0.15rem 0;">"Quality"</td>
+                            <td style="padding:0.15rem 0; display:flex; align-items:center; gap:0.4rem;">
+                                <input
+                                    type="range"
+                                    min="1"
+                                    max="100"
+                                    prop:value=move || jpeg_quality.get().to_string()
+                                    on:input=move |ev| {
+                                        let value = event_target_value(&ev).parse::<u8>().unwrap_or(90);
+                                        jpeg_quality.set(value.clamp(1, 100));
+                                    }
+                                />
+                                <span>{move || jpeg_quality.get()}</span>
+                            </td>
+                        </tr>
+                    </Show>
+                    <Show when=move || format.get() == ExportFormat::Png fallback=|| ()>
+                        <tr>
+                            <td style="padding:0.15rem 0.5rem 0.15rem 0;">"Compression"</td>
+                            <td style="padding:0.15rem 0;">
+                                <select
+                                    style="width:100%;"
+                                    on:change=move |ev| {
+                                        let index = event_target_value(&ev).parse::<usize>().unwrap_or(1);
+                                        if let Some(selected) = PngCompression::ALL.get(index) {
+                                            png_compression.set(*selected);
+                                        }
+                                    }
+                                >
+                                    {PngCompression::ALL
+                                        .into_iter()
+                                        .enumerate()
+                                        .map(|(index, option)| {
+                                            view! {
+                                                <option
+                                                    value=index.to_string()
+                                                    prop:selected=move || png_compression.get() == option
+                                                >
+                                                    {option.label()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                            </td>
+                        </tr>
+                    </Show>
+                    <tr>
+                        <td style="padding:0.15rem 0.5rem 0.15rem 0;">"Scale (%)"</td>
+                        <td style="padding:0.15rem 0;">
+                            <input
+                                type="number"
+                                min="1"
+                                max=MAX_SCALE_PERCENT.to_string()
+                                prop:value=move || scale_percent.get().to_string()
+                                on:input=move |ev| {
+                                    let value = event_target_value(&ev).parse::<u32>().ok().filter(|v| *v > 0).unwrap_or(100);
+                                    scale_percent.set(value.min(MAX_SCALE_PERCENT));
+                                }
+                                style="width:100%; box-sizing:border-box;"
+                            />
+                        </td>
+                    </tr>
+                    <tr>
+                        <td style="padding:0.15rem 0.5rem 0.15rem 0;">"Flatten"</td>
+                        <td style="padding:0.15rem 0;">
+                            <input
+                                type="checkbox"
+                                title="Formats without transparency are always flattened"
+                                prop:checked=move || flatten.get() || must_flatten()
+                                prop:disabled=must_flatten
+                                on:change=move |ev| flatten.set(event_target_checked(&ev))
+                            />
+                        </td>
+                    </tr>
+                </table>
+                <Show when=move || flatten.get() || must_flatten() fallback=|| ()>
+                    <div style="font-size:0.8rem; margin-top:0.5rem;">"Background color:"</div>
+                    <ColorPicker color=background />
+                </Show>
+                <div
+                    style="
+                        margin-top:0.75rem;
+                        display:flex;
+                        justify-content:flex-end;
+                        gap:0.5rem;
+                    "
+                >
+                    <button
+                        class="dialog-button"
+                        on:click=move |_| {
+                            is_open.set(false);
+                        }
+                        >"Cancel"</button>
+                    <button
+                        class="dialog-button-ok"
+                        on:click=move |_| {
+                            is_open.set(false);
+                            on_export();
+                        }
+                        >"Export"</button>
+                </div>
+            </div>
+        </div>
+    }
+}
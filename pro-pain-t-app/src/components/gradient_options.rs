@@ -0,0 +1,53 @@
+use crate::tools::gradient::{GradientPreset, GradientShape};
+use leptos::prelude::*;
+
+#[component]
+pub fn GradientOptions(shape: RwSignal<GradientShape>, preset: RwSignal<GradientPreset>) -> impl IntoView {
+    view! {
+        <div style="width:100%; text-align:center; font-size:0.85rem; letter-spacing:0.06em; color:white;">
+            "Gradient"
+        </div>
+        <select
+            style="width:100%;"
+            on:change=move |ev| {
+                let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                if let Some(selected) = GradientShape::ALL.get(index) {
+                    shape.set(*selected);
+                }
+            }
+        >
+            {GradientShape::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    view! {
+                        <option value=index.to_string() prop:selected=move || shape.get() == option>
+                            {option.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+        <select
+            style="width:100%;"
+            on:change=move |ev| {
+                let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                if let Some(selected) = GradientPreset::ALL.get(index) {
+                    preset.set(*selected);
+                }
+            }
+        >
+            {GradientPreset::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    view! {
+                        <option value=index.to_string() prop:selected=move || preset.get() == option>
+                            {option.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+    }
+}
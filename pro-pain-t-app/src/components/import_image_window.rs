@@ -0,0 +1,100 @@
+use crate::structs::image_placement::ImagePlacement;
+use crate::structs::project::Project;
+use image::RgbaImage;
+use leptos::prelude::*;
+
+/// Asks where to put an imported image whose size differs from the document.
+/// Open while `pending_import` holds an image.
+#[component]
+pub fn ImportImageWindow(pending_import: RwSignal<Option<RgbaImage>>) -> impl IntoView {
+    let project = use_context::<RwSignal<Project>>().expect("Project context missing");
+    let placement = RwSignal::new(ImagePlacement::default());
+
+    let image_size = move || {
+        pending_import.with(|image| image.as_ref().map(|image| image.dimensions()))
+    };
+
+    let on_import = move || {
+        let Some(image) = pending_import.try_update(|image| image.take()).flatten() else {
+            return;
+        };
+        project.update(|project| project.import_image(&image, placement.get_untracked()));
+    };
+
+    let on_key_down = move |ev: leptos::ev::KeyboardEvent| {
+        if ev.key() == "Escape" {
+            pending_import.set(None);
+            ev.prevent_default();
+            ev.stop_propagation();
+        }
+    };
+
+    view! {
+        <div
+            style=move || format!(
+                "position:fixed; inset:0; background:rgba(0,0,0,0.4); display:{}; align-items:center; justify-content:center; z-index:1000;",
+                if image_size().is_some() { "flex" } else { "none" }
+            )
+        >
+            <div
+                style="
+                    background:#2b2b2b;
+                    padding:1rem 1.25rem;
+                    border-radius:4px;
+                    color:#f5f5f5;
+                    min-width:260px;
+                    font-family:system-ui, sans-serif;
+                    box-shadow:0 12px 30px rgba(0,0,0,0.7);
+                "
+                tabindex="-1"
+                on:keydown=on_key_down
+            >
+                <h2 style="margin:0 0 0.75rem 0; font-size:0.95rem;">"Import Image"</h2>
+                <p style="margin:0 0 0.5rem 0; font-size:0.8rem;">
+                    {move || {
+                        let (image_w, image_h) = image_size().unwrap_or_default();
+                        let (project_w, project_h) = project.with(|project| (project.width.get(), project.height.get()));
+                        format!("The image is {}x{} px, the canvas is {}x{} px.", image_w, image_h, project_w, project_h)
+                    }}
+                </p>
+                <div style="display:flex; flex-direction:column; gap:0.25rem; font-size:0.8rem;">
+                    {ImagePlacement::ALL
+                        .into_iter()
+                        .map(|option| {
+                            view! {
+                                <label style="display:flex; align-items:center; gap:0.4rem;">
+                                    <input
+                                        type="radio"
+                                        name="import-placement"
+                                        prop:checked=move || placement.get() == option
+                                        on:change=move |_| placement.set(option)
+                                    />
+                                    {option.label()}
+                                </label>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+                <div
+                    style="
+                        margin-top:0.75rem;
+                        display:flex;
+                        justify-content:flex-end;
+                        gap:0.5rem;
+                    "
+                >
+                    <button
+                        class="dialog-button"
+                        on:click=move |_| {
+                            pending_import.set(None);
+                        }
+                        >"Cancel"</button>
+                    <button
+                        class="dialog-button-ok"
+                        on:click=move |_| on_import()
+                        >"Import"</button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,11 +1,30 @@
+use crate::components::blend_mode_select::BlendModeSelect;
 use crate::components::edit_layer_window::EditLayerWindow;
 use crate::components::layer_preview::LayerPreview;
 use crate::components::new_layer_window::NewLayerWindow;
 use crate::state::workspace_state::WorkspaceState;
-use crate::structs::layer::Layer;
+use crate::structs::layer::{Layer, LayerProperties};
 use crate::structs::project::Project;
 use leptos::{html::Dialog, logging, prelude::*};
 
+/// Changes properties of a layer through the project so the change can be undone.
+fn update_layer_properties(
+    project: RwSignal<Project>,
+    layer_id: usize,
+    change: impl FnOnce(&mut LayerProperties),
+) {
+    let Some(mut properties) = project
+        .get()
+        .layers
+        .with(|layers| layers.iter().find(|l| l.id == layer_id).map(|l| l.properties()))
+    else {
+        return;
+    };
+
+    change(&mut properties);
+    project.update(|project| project.set_layer_properties(layer_id, properties));
+}
+
 #[component]
 pub fn LayerPanel() -> impl IntoView {
     let project = use_context::<RwSignal<Project>>().expect("Project context missing");
@@ -61,6 +80,9 @@ pub fn LayerPanel() -> impl IntoView {
                     each=move || project.get().layers.get().into_iter().rev()
                     key=|layer| layer.id
                     children=move |layer: Layer| {
+                        // Properties before the opacity slider started moving, recorded once it's released
+                        let opacity_drag_start = StoredValue::new(None::<LayerProperties>);
+
                         view! {
                             <div
                                 style= format!("
@@ -112,11 +134,9 @@ pub fn LayerPanel() -> impl IntoView {
                                         }
                                     }
                                     on:click = move |_| {
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                layers[index].is_visible = !layers[index].is_visible;
-                                                logging::log!("Layer {} visibility toggle: {}", layers[index].id, layers[index].is_visible);
-                                            }
+                                        update_layer_properties(project, layer.id, |properties| {
+                                            properties.is_visible = !properties.is_visible;
+                                            logging::log!("Layer {} visibility toggle: {}", layer.id, properties.is_visible);
                                         });
                                     }>
                                     "👀"
@@ -137,11 +157,9 @@ pub fn LayerPanel() -> impl IntoView {
                                         }
                                     }
                                     on:click = move |_| {
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                layers[index].is_locked = !layers[index].is_locked;
-                                                logging::log!("Layer {} locked toggle: {}", layers[index].id, layers[index].is_locked);
-                                            }
+                                        update_layer_properties(project, layer.id, |properties| {
+                                            properties.is_locked = !properties.is_locked;
+                                            logging::log!("Layer {} locked toggle: {}", layer.id, properties.is_locked);
                                         });
                                     }>
                                     "🔒"
@@ -176,12 +194,8 @@ pub fn LayerPanel() -> impl IntoView {
                                             }
                                         }
 
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                layers.remove(index);
-                                                logging::log!("Layer {} delete pressed", layer.id);
-                                            }
-                                        });
+                                        project.update(|project| project.remove_layer(layer.id));
+                                        logging::log!("Layer {} delete pressed", layer.id);
 
                                         workspace_state.selected_layer_id.set(new_selected);
                                     }>
@@ -223,6 +237,79 @@ pub fn LayerPanel() -> impl IntoView {
                                         let l = binding.iter().find(|l| l.id == layer.id).expect("Layer with specified ID couldn't be found");
                                         l.title.clone()
                                     }}</span>
+                                    <div
+                                        style="
+                                            display:flex;
+                                            align-items:center;
+                                            gap:0.2rem;
+                                            font-size:0.7rem;
+                                        "
+                                        on:click = move |ev| ev.stop_propagation()
+                                    >
+                                        <BlendModeSelect
+                                            value = Signal::derive(move || {
+                                                project.get().layers.get().iter()
+                                                    .find(|l| l.id == layer.id)
+                                                    .map(|l| l.blend_mode)
+                                                    .unwrap_or_default()
+                                            })
+                                            on_change = Callback::new(move |mode| {
+                                                update_layer_properties(project, layer.id, |properties| {
+                                                    properties.blend_mode = mode;
+                                                });
+                                                logging::log!("Layer {} blend mode: {:?}", layer.id, mode);
+                                            })
+                                            disabled = Signal::derive(move || {
+                                                project.get().layers.get().iter()
+                                                    .find(|l| l.id == layer.id)
+                                                    .map(|l| l.is_locked)
+                                                    .unwrap_or(true)
+                                            })
+                                        />
+                                        <input
+                                            type="range"
+                                            min="0"
+                                            max="100"
+                                            step="1"
+                                            title="Opacity"
+                                            style="width:50px;"
+                                            prop:value = move || {
+                                                project.get().layers.get().iter()
+                                                    .find(|l| l.id == layer.id)
+                                                    .map(|l| (l.opacity * 100.0).round())
+                                                    .unwrap_or(100.0)
+                                                    .to_string()
+                                            }
+                                            prop:disabled = move || {
+                                                project.get().layers.get().iter()
+                                                    .find(|l| l.id == layer.id)
+                                                    .map(|l| l.is_locked)
+                                                    .unwrap_or(true)
+                                            }
+                                            on:input = move |ev| {
+                                                let percent = event_target_value(&ev).parse::<f32>().unwrap_or(100.0);
+                                                project.get().layers.update(|layers| {
+                                                    if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
+                                                        if opacity_drag_start.with_value(|start| start.is_none()) {
+                                                            opacity_drag_start.set_value(Some(layers[index].properties()));
+                                                        }
+                                                        layers[index].opacity = (percent / 100.0).clamp(0.0, 1.0);
+                                                    }
+                                                });
+                                            }
+                                            on:change = move |_| {
+                                                let Some(before) = opacity_drag_start.try_update_value(|start| start.take()).flatten() else {
+                                                    return;
+                                                };
+                                                let after = project.get().layers.with(|layers| {
+                                                    layers.iter().find(|l| l.id == layer.id).map(|l| l.properties())
+                                                });
+                                                if let Some(after) = after {
+                                                    project.update(|project| project.record_layer_properties(layer.id, before, after));
+                                                }
+                                            }
+                                        />
+                                    </div>
                                 </div>
                                 <div
                                     style="
@@ -243,34 +330,16 @@ pub fn LayerPanel() -> impl IntoView {
                                         }
                                     }
                                     on:click = move |_| {
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                if index >= layers.len() - 1 {
-                                                    return;
-                                                }
-                                                layers.swap(index, index + 1);
-                                                logging::log!("Layer {} moved up", layer.id);
-                                            }
-                                        });
+                                        project.update(|project| project.move_layer(layer.id, 1));
+                                        logging::log!("Layer {} moved up", layer.id);
                                     }>
                                     "▲"
                                     </button>
 
                                     <button
                                     on:click = move |_| {
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                let original_layer = layers.get(index).unwrap();
-                                                let mut layer_cloned = original_layer.clone();
-                                                layer_cloned.title = (layer_cloned.title + " (Copy)").to_string();
-                                                let layer_id = project.get().next_layer_id.get();
-                                                layer_cloned.id = layer_id;
-                                                project.get().next_layer_id.set(layer_id + 1);
-
-                                                layers.insert(index + 1, layer_cloned);
-                                                logging::log!("Layer {} cloned", layer.id);
-                                            }
-                                        });
+                                        project.update(|project| project.duplicate_layer(layer.id));
+                                        logging::log!("Layer {} cloned", layer.id);
                                     }>
                                     "📄"
                                     </button>
@@ -285,12 +354,8 @@ pub fn LayerPanel() -> impl IntoView {
                                         }
                                     }
                                     on:click = move |_| {
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                layers[index].canvas.clear();
-                                                logging::log!("Layer {} cleared", layers[index].id);
-                                            }
-                                        });
+                                        project.update(|project| project.clear_layer(layer.id));
+                                        logging::log!("Layer {} cleared", layer.id);
                                     }>
                                     "🧽"
                                     </button>
@@ -305,15 +370,8 @@ pub fn LayerPanel() -> impl IntoView {
                                         }
                                     }
                                     on:click = move |_| {
-                                        project.get().layers.update(|layers| {
-                                            if let Some(index) = layers.iter_mut().position(|l| l.id == layer.id) {
-                                                if index == 0 {
-                                                    return;
-                                                }
-                                                layers.swap(index, index - 1);
-                                                logging::log!("Layer {} moved down", layer.id);
-                                            }
-                                        });
+                                        project.update(|project| project.move_layer(layer.id, -1));
+                                        logging::log!("Layer {} moved down", layer.id);
                                     }>
                                     "▼"
                                     </button>
@@ -0,0 +1,64 @@
+use crate::tools::line::{LineArrows, LineDash, LineOptions};
+use leptos::prelude::*;
+
+#[component]
+pub fn LineOptions(options: RwSignal<LineOptions>) -> impl IntoView {
+    view! {
+        <div style="width:100%; text-align:center; font-size:0.85rem; letter-spacing:0.06em; color:white;">
+            "Line"
+        </div>
+        <label
+            style="width:100%; font-size:0.8rem; color:white;"
+            title="Click to add points, double-click or Enter to finish, Backspace removes the last point"
+        >
+            <input
+                type="checkbox"
+                prop:checked=move || options.with(|o| o.polyline)
+                on:change=move |ev| options.update(|o| o.polyline = event_target_checked(&ev))
+            />
+            "Polyline"
+        </label>
+        <select
+            style="width:100%;"
+            on:change=move |ev| {
+                let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                if let Some(selected) = LineArrows::ALL.get(index) {
+                    options.update(|o| o.arrows = *selected);
+                }
+            }
+        >
+            {LineArrows::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    view! {
+                        <option value=index.to_string() prop:selected=move || options.with(|o| o.arrows == option)>
+                            {option.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+        <select
+            style="width:100%;"
+            on:change=move |ev| {
+                let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                if let Some(selected) = LineDash::ALL.get(index) {
+                    options.update(|o| o.dash = *selected);
+                }
+            }
+        >
+            {LineDash::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    view! {
+                        <option value=index.to_string() prop:selected=move || options.with(|o| o.dash == option)>
+                            {option.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+    }
+}
@@ -1,13 +1,24 @@
 pub mod alpha_slider;
+pub mod blend_mode_select;
 pub mod canvas_area;
 pub mod canvas_size_window;
 pub mod color_picker;
 pub mod edit_layer_window;
+pub mod export_window;
+pub mod import_image_window;
 pub mod layer_panel;
 pub mod layer_preview;
 pub mod new_layer_window;
 pub mod new_project_window;
 pub mod rgb_slider;
+pub mod shortcuts_window;
 pub mod status_bar;
 pub mod tool_palette;
 pub mod brush_size_slider;
+pub mod region_options;
+pub mod gradient_options;
+pub mod shape_options;
+pub mod brush_options;
+pub mod line_options;
+pub mod symmetry_options;
+pub mod retouch_options;
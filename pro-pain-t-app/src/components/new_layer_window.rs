@@ -6,7 +6,7 @@ use leptos::{html::Dialog, logging, prelude::*};
 #[component]
 pub fn NewLayerWindow(dialog_ref: NodeRef<Dialog>, is_open: RwSignal<bool>) -> impl IntoView {
     let title = RwSignal::new(String::from("New layer"));
-    let color = RwSignal::new(Color::default_white());
+    let color = RwSignal::new(Color::transparent());
 
     let project = use_context::<RwSignal<Project>>().expect("Project context missing");
 
@@ -19,7 +19,7 @@ pub fn NewLayerWindow(dialog_ref: NodeRef<Dialog>, is_open: RwSignal<bool>) -> i
             project.get().height.get(),
             color.get(),
         );
-        project.get().add_new_layer(layer);
+        project.update(|project| project.add_new_layer(layer));
 
         logging::log!(
             "new_layer: {}, {}, {}, {}, count: {}",
@@ -33,7 +33,7 @@ pub fn NewLayerWindow(dialog_ref: NodeRef<Dialog>, is_open: RwSignal<bool>) -> i
 
     let reset = move || {
         title.set(String::from("New layer"));
-        color.set(Color::new(255, 255, 255, 1.0f32));
+        color.set(Color::transparent());
     };
 
     view! {
@@ -58,7 +58,7 @@ pub fn NewLayerWindow(dialog_ref: NodeRef<Dialog>, is_open: RwSignal<bool>) -> i
                         style:text="Title"
                         id="new-layer-title"
                     />
-                    Background color:
+                    "Fill color:"
                     <ColorPicker color=color style:color = "#ffffff" style:margin="2px" style:padding="2px" />
                 <div
                 style="display:flex; justify-content:space-between;">
@@ -0,0 +1,66 @@
+use leptos::prelude::*;
+
+/// Options of the tools that pick a region by color.
+#[component]
+pub fn RegionOptions(
+    tolerance: RwSignal<u8>,
+    contiguous: RwSignal<bool>,
+    eight_connected: RwSignal<bool>,
+    sample_merged: RwSignal<bool>,
+) -> impl IntoView {
+    let on_input = move |ev| {
+        let value = event_target_value(&ev).parse().unwrap_or(32u8);
+        tolerance.set(value);
+    };
+
+    view! {
+        <div style="width:100%; text-align:center; font-size:0.85rem; letter-spacing:0.06em; color:white;">
+            "Tolerance"
+        </div>
+        <div style="display:flex; align-items:center; gap:8px; width:100%;">
+            <input
+                type="number"
+                min="0"
+                max="255"
+                step="1"
+                prop:value=move || tolerance.get().to_string()
+                style="width:50px;"
+                on:input=on_input
+            />
+            <input
+                type="range"
+                min="0"
+                max="255"
+                step="1"
+                prop:value=tolerance.get()
+                style="width:60px; flex:1;"
+                on:input=on_input
+            />
+        </div>
+        <label style="width:100%; font-size:0.8rem; color:white;" title="Only pixels connected to the clicked one">
+            <input
+                type="checkbox"
+                prop:checked=move || contiguous.get()
+                on:change=move |ev| contiguous.set(event_target_checked(&ev))
+            />
+            "Contiguous"
+        </label>
+        <label style="width:100%; font-size:0.8rem; color:white;" title="Pixels touching by a corner are connected too">
+            <input
+                type="checkbox"
+                prop:checked=move || eight_connected.get()
+                prop:disabled=move || !contiguous.get()
+                on:change=move |ev| eight_connected.set(event_target_checked(&ev))
+            />
+            "Diagonal neighbours"
+        </label>
+        <label style="width:100%; font-size:0.8rem; color:white;" title="Compare colors of all visible layers">
+            <input
+                type="checkbox"
+                prop:checked=move || sample_merged.get()
+                on:change=move |ev| sample_merged.set(event_target_checked(&ev))
+            />
+            "Sample all layers"
+        </label>
+    }
+}
@@ -0,0 +1,26 @@
+use leptos::prelude::*;
+
+/// Strength of the smudge, blur and sharpen tools.
+#[component]
+pub fn RetouchOptions(strength: RwSignal<f32>) -> impl IntoView {
+    view! {
+        <div
+            style="display:flex; align-items:center; gap:8px; width:100%; font-size:0.8rem; color:white;"
+            title="How much one stroke changes the pixels"
+        >
+            <span style="width:56px;">"Strength"</span>
+            <input
+                type="range"
+                min="1"
+                max="100"
+                step="1"
+                prop:value=move || ((strength.get() * 100.0).round() as u32).to_string()
+                style="width:60px; flex:1;"
+                on:input=move |ev| {
+                    let value = event_target_value(&ev).parse::<f32>().unwrap_or(50.0) / 100.0;
+                    strength.set(value.clamp(0.0, 1.0));
+                }
+            />
+        </div>
+    }
+}
@@ -0,0 +1,50 @@
+use crate::tools::shape::ShapeStyle;
+use leptos::prelude::*;
+
+#[component]
+pub fn ShapeOptions(style: RwSignal<ShapeStyle>, sides: RwSignal<u32>, show_sides: Signal<bool>) -> impl IntoView {
+    view! {
+        <div style="width:100%; text-align:center; font-size:0.85rem; letter-spacing:0.06em; color:white;">
+            "Shape"
+        </div>
+        <select
+            style="width:100%;"
+            title="Outline and fill paints the inside with the secondary color"
+            on:change=move |ev| {
+                let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                if let Some(selected) = ShapeStyle::ALL.get(index) {
+                    style.set(*selected);
+                }
+            }
+        >
+            {ShapeStyle::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    view! {
+                        <option value=index.to_string() prop:selected=move || style.get() == option>
+                            {option.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+        <Show when=move || show_sides.get() fallback=|| ()>
+            <div style="display:flex; align-items:center; gap:8px; width:100%; font-size:0.8rem; color:white;">
+                "Sides"
+                <input
+                    type="number"
+                    min="3"
+                    max="32"
+                    step="1"
+                    prop:value=move || sides.get().to_string()
+                    style="width:50px;"
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev).parse().unwrap_or(5u32);
+                        sides.set(value.clamp(3, 32));
+                    }
+                />
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,141 @@
+use crate::events::error::show_error_dialog;
+use crate::events::shortcuts::chord_from_event;
+use crate::state::workspace_state::WorkspaceState;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use pro_pain_t_shared::dtos::shortcuts::{SetShortcutDto, ShortcutsDto};
+use pro_pain_t_shared::shortcuts::ShortcutAction;
+use tauri_sys::core::invoke_result;
+
+/// Lists every shortcut. Clicking a chord waits for the next key press and
+/// binds it, the backend stores the change in the settings file.
+#[component]
+pub fn ShortcutsWindow(is_open: RwSignal<bool>) -> impl IntoView {
+    let workspace_state = use_context::<WorkspaceState>().expect("WorkspaceState context missing");
+    let shortcuts = workspace_state.shortcuts;
+
+    // Action waiting for its new chord
+    let recording = RwSignal::new(None::<ShortcutAction>);
+
+    let set_shortcut = move |action: ShortcutAction, chord: Option<String>| {
+        spawn_local(async move {
+            let result = invoke_result::<ShortcutsDto, String>(
+                "set_shortcut_command",
+                SetShortcutDto::new(action, chord),
+            )
+            .await;
+            match result {
+                Ok(updated) => shortcuts.set(updated),
+                Err(err) => show_error_dialog(err),
+            }
+        });
+    };
+
+    // Stopping propagation keeps the keys from the window's shortcut handler
+    let on_key_down = move |ev: leptos::ev::KeyboardEvent| {
+        ev.stop_propagation();
+
+        let Some(action) = recording.get_untracked() else {
+            if ev.key() == "Escape" {
+                is_open.set(false);
+                ev.prevent_default();
+            }
+            return;
+        };
+
+        ev.prevent_default();
+        if ev.key() == "Escape" {
+            recording.set(None);
+            return;
+        }
+        // Wait for the key that comes with the modifiers
+        let Some(chord) = chord_from_event(&ev) else { return };
+        recording.set(None);
+        set_shortcut(action, Some(chord.to_string()));
+    };
+
+    view! {
+        <div
+            style=move || format!(
+                "position:fixed; inset:0; background:rgba(0,0,0,0.4); display:{}; align-items:center; justify-content:center; z-index:1000;",
+                if is_open.get() { "flex" } else { "none" }
+            )
+        >
+            <div
+                style="
+                    background:#2b2b2b;
+                    padding:1rem 1.25rem;
+                    border-radius:4px;
+                    color:#f5f5f5;
+                    min-width:320px;
+                    max-height:80vh;
+                    overflow-y:auto;
+                    font-family:system-ui, sans-serif;
+                    box-shadow:0 12px 30px rgba(0,0,0,0.7);
+                "
+                tabindex="-1"
+                on:keydown=on_key_down
+            >
+                <h2 style="margin:0 0 0.75rem 0; font-size:0.95rem;">"Keyboard shortcuts"</h2>
+                <table style="width:100%; font-size:0.8rem;">
+                    {ShortcutAction::ALL
+                        .into_iter()
+                        .map(|action| {
+                            view! {
+                                <tr>
+                                    <td style="padding:0.15rem 0.5rem 0.15rem 0;">{action.label()}</td>
+                                    <td style="padding:0.15rem 0;">
+                                        <button
+                                            class="dialog-button"
+                                            style="width:100%;"
+                                            title="Click, then press the new shortcut"
+                                            on:click=move |_| recording.set(Some(action))
+                                        >
+                                            {move || {
+                                                if recording.get() == Some(action) {
+                                                    "Press keys...".to_string()
+                                                } else {
+                                                    shortcuts.with(|s| s.bindings.get(&action).cloned().unwrap_or_default())
+                                                }
+                                            }}
+                                        </button>
+                                    </td>
+                                    <td style="padding:0.15rem 0 0.15rem 0.5rem;">
+                                        <button
+                                            class="dialog-button"
+                                            title="Restore the default shortcut"
+                                            prop:disabled=move || {
+                                                shortcuts.with(|s| {
+                                                    s.bindings.get(&action).map(String::as_str) == Some(action.default_chord())
+                                                })
+                                            }
+                                            on:click=move |_| set_shortcut(action, None)
+                                        >
+                                            "Reset"
+                                        </button>
+                                    </td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </table>
+                <div
+                    style="
+                        margin-top:0.75rem;
+                        display:flex;
+                        justify-content:flex-end;
+                        gap:0.5rem;
+                    "
+                >
+                    <button
+                        class="dialog-button-ok"
+                        on:click=move |_| {
+                            recording.set(None);
+                            is_open.set(false);
+                        }
+                        >"Close"</button>
+                </div>
+            </div>
+        </div>
+    }
+}
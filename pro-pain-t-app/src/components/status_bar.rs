@@ -44,6 +44,15 @@ pub fn StatusBar(is_open: RwSignal<bool>) -> impl IntoView {
                 >
                     "="
                 </button>
+                <button
+                    on:click=move |_| project.with_untracked(|project| {
+                        view_state.fit_to_window(project.width.get_untracked(), project.height.get_untracked())
+                    })
+                    title="Fit to window"
+                    class="status-bar-button"
+                >
+                    "Fit"
+                </button>
                 <button
                     on:click=move |_| view_state.zoom_in_by_step()
                     title="Zoom in"
@@ -0,0 +1,85 @@
+use crate::tools::symmetry::{Symmetry, SymmetryMode};
+use leptos::prelude::*;
+
+/// Mirror settings shared by the paint tools.
+#[component]
+pub fn SymmetryOptions(symmetry: RwSignal<Symmetry>) -> impl IntoView {
+    // Center coordinate in percent of the document
+    let center_input = move |label: &'static str, get: fn(&Symmetry) -> f32, set: fn(&mut Symmetry, f32)| {
+        view! {
+            <span>{label}</span>
+            <input
+                type="number"
+                min="0"
+                max="100"
+                step="1"
+                prop:value=move || ((symmetry.with(get) * 100.0).round() as u32).to_string()
+                style="width:50px;"
+                on:input=move |ev| {
+                    let value = event_target_value(&ev).parse::<f32>().unwrap_or(50.0) / 100.0;
+                    symmetry.update(|s| set(s, value.clamp(0.0, 1.0)));
+                }
+            />
+        }
+    };
+
+    view! {
+        <div style="width:100%; text-align:center; font-size:0.85rem; letter-spacing:0.06em; color:white;">
+            "Symmetry"
+        </div>
+        <select
+            style="width:100%;"
+            on:change=move |ev| {
+                let index = event_target_value(&ev).parse::<usize>().unwrap_or(0);
+                if let Some(selected) = SymmetryMode::ALL.get(index) {
+                    symmetry.update(|s| s.mode = *selected);
+                }
+            }
+        >
+            {SymmetryMode::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, option)| {
+                    view! {
+                        <option value=index.to_string() prop:selected=move || symmetry.with(|s| s.mode == option)>
+                            {option.label()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+        <Show when=move || symmetry.with(|s| s.mode == SymmetryMode::Radial) fallback=|| ()>
+            <div style="display:flex; align-items:center; gap:8px; width:100%; font-size:0.8rem; color:white;">
+                "Copies"
+                <input
+                    type="number"
+                    min="2"
+                    max="32"
+                    step="1"
+                    prop:value=move || symmetry.with(|s| s.segments).to_string()
+                    style="width:50px;"
+                    on:input=move |ev| {
+                        let value = event_target_value(&ev).parse().unwrap_or(6u32);
+                        symmetry.update(|s| s.segments = value.clamp(2, 32));
+                    }
+                />
+            </div>
+        </Show>
+        <Show when=move || symmetry.with(|s| s.is_active()) fallback=|| ()>
+            <div
+                style="display:flex; align-items:center; gap:4px; width:100%; font-size:0.8rem; color:white;"
+                title="Center of the axes in percent of the canvas size"
+            >
+                {center_input("X", |s| s.center.0, |s, v| s.center.0 = v)}
+                {center_input("Y", |s| s.center.1, |s, v| s.center.1 = v)}
+                <div
+                    class="tool-button"
+                    title="Move the center to the middle of the canvas"
+                    on:click=move |_| symmetry.update(|s| s.center = (0.5, 0.5))
+                >
+                    "⌖"
+                </div>
+            </div>
+        </Show>
+    }
+}
@@ -1,15 +1,28 @@
 use crate::components::color_picker::ColorPicker;
+use crate::structs::color::Color;
 use crate::tools::brush::BrushState;
 use crate::tools::eraser::EraserState;
 use crate::tools::eyedropper::EyedropperState;
+use crate::tools::gradient::GradientState;
+use crate::tools::lasso::LassoState;
 use crate::tools::line::LineState;
+use crate::tools::magic_wand::MagicWandState;
+use crate::tools::retouch::{RetouchKind, RetouchState};
+use crate::tools::shape::{ShapeKind, ShapeState};
 use crate::{
     state::workspace_state::WorkspaceState,
     structs::project::Project,
-    tools::{pan::PanState, pen::PenState, select::SelectState, tools::Tool, bucket::BucketState},
+    tools::{pan::PanState, pen::PenState, select::{SelectShape, SelectState}, tools::Tool, bucket::BucketState},
 };
 use leptos::prelude::*;
+use crate::components::brush_options::BrushOptions;
 use crate::components::brush_size_slider::BrushSizeSlider;
+use crate::components::gradient_options::GradientOptions;
+use crate::components::line_options::LineOptions;
+use crate::components::region_options::RegionOptions;
+use crate::components::retouch_options::RetouchOptions;
+use crate::components::shape_options::ShapeOptions;
+use crate::components::symmetry_options::SymmetryOptions;
 
 #[component]
 pub fn ToolPalette() -> impl IntoView {
@@ -17,6 +30,17 @@ pub fn ToolPalette() -> impl IntoView {
     let workspace_state = use_context::<WorkspaceState>().expect("WorkspaceState context missing");
     let current_color = project.with_untracked(|p| p.current_color);
     let current_tool = workspace_state.current_tool;
+    let secondary_color = workspace_state.secondary_color;
+    // Only changes when switching between retouch tools, not on every stroke
+    let retouch_kind = Memo::new(move |_| current_tool.with(|t| match t {
+        Tool::Retouch(state) => Some(state.kind()),
+        _ => None,
+    }));
+
+    let swatch_style = |color: Color| {
+        let [r, g, b, a] = color.to_rgba8();
+        format!("background:rgba({}, {}, {}, {:.3});", r, g, b, a as f32 / 255.0)
+    };
 
     view! {
         <nav class="tool-palette">
@@ -47,7 +71,7 @@ pub fn ToolPalette() -> impl IntoView {
                     on:click=move |_| {
                         workspace_state.set_tool(Tool::Line(LineState::default()), &project);
                     }
-                    title="Line tool"
+                    title="Line tool (drag, or click points in polyline mode; Shift snaps to 15°)"
                 >
                 "📏"
                 </div>
@@ -83,14 +107,124 @@ pub fn ToolPalette() -> impl IntoView {
                 </div>
                 <div
                     class="tool-button"
-                    class=("tool-button--active", move || matches!(current_tool.get(), Tool::Select(_)))
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Select(s) if s.shape() == SelectShape::Rectangle)))
                     on:click=move |_| {
                         workspace_state.set_tool(Tool::Select(SelectState::default()), &project);
                     }
-                    title="Select tool"
+                    title="Select tool (Shift adds, Alt subtracts, Shift+Alt intersects)"
                 >
                 "🔲"
                 </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Select(s) if s.shape() == SelectShape::Ellipse)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Select(SelectState::ellipse()), &project);
+                    }
+                    title="Ellipse select tool (Shift adds, Alt subtracts, Shift+Alt intersects)"
+                >
+                "⭕"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Lasso(l) if !l.is_polygonal())))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Lasso(LassoState::default()), &project);
+                    }
+                    title="Lasso tool (Shift adds, Alt subtracts, Shift+Alt intersects)"
+                >
+                "➰"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Lasso(l) if l.is_polygonal())))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Lasso(LassoState::polygonal()), &project);
+                    }
+                    title="Polygonal lasso tool (double-click or Enter closes, Backspace removes a point)"
+                >
+                "📐"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::MagicWand(_))))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::MagicWand(MagicWandState), &project);
+                    }
+                    title="Magic wand tool (Shift adds, Alt subtracts, Shift+Alt intersects)"
+                >
+                "🪄"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Gradient(_))))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Gradient(GradientState::default()), &project);
+                    }
+                    title="Gradient tool (drag, Shift snaps to 45°)"
+                >
+                "🌈"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Shape(s) if s.kind() == ShapeKind::Rectangle)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Shape(ShapeState::rectangle()), &project);
+                    }
+                    title="Rectangle tool (Shift draws a square)"
+                >
+                "▭"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Shape(s) if s.kind() == ShapeKind::Ellipse)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Shape(ShapeState::ellipse()), &project);
+                    }
+                    title="Ellipse tool (Shift draws a circle)"
+                >
+                "◯"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Shape(s) if s.kind() == ShapeKind::Polygon)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Shape(ShapeState::polygon()), &project);
+                    }
+                    title="Polygon tool (Shift keeps it regular)"
+                >
+                "⬠"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Retouch(s) if s.kind() == RetouchKind::Smudge)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Retouch(RetouchState::smudge()), &project);
+                    }
+                    title="Smudge tool"
+                >
+                "👆"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Retouch(s) if s.kind() == RetouchKind::Blur)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Retouch(RetouchState::blur()), &project);
+                    }
+                    title="Blur tool"
+                >
+                "💧"
+                </div>
+                <div
+                    class="tool-button"
+                    class=("tool-button--active", move || current_tool.with(|t| matches!(t, Tool::Retouch(s) if s.kind() == RetouchKind::Sharpen)))
+                    on:click=move |_| {
+                        workspace_state.set_tool(Tool::Retouch(RetouchState::sharpen()), &project);
+                    }
+                    title="Sharpen tool"
+                >
+                "🔺"
+                </div>
                 <div
                     class="tool-button"
                     class=("tool-button--active", move || current_tool.get().is_pan())
@@ -103,8 +237,60 @@ pub fn ToolPalette() -> impl IntoView {
                 </div>         
             </div>
 
+            <div class="color-swatches">
+                <div class="color-swatch" title="Current color" style=move || swatch_style(current_color.get())></div>
+                <div class="color-swatch" title="Secondary color" style=move || swatch_style(secondary_color.get())></div>
+                <div
+                    class="tool-button"
+                    title="Swap colors"
+                    on:click=move |_| workspace_state.swap_colors(&project)
+                >
+                "⇄"
+                </div>
+            </div>
             <ColorPicker color = current_color />
             <BrushSizeSlider brush_size = workspace_state.brush_size />
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Brush(_))) fallback=|| ()>
+                <BrushOptions settings = workspace_state.brush_settings />
+            </Show>
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Eraser(_))) fallback=|| ()>
+                <BrushOptions settings = workspace_state.eraser_settings opacity_label = "Strength" />
+                <label style="width:100%; font-size:0.8rem; color:white;" title="On the bottom layer, paint the document background color instead of transparency">
+                    <input
+                        type="checkbox"
+                        prop:checked=move || workspace_state.erase_to_background.get()
+                        on:change=move |ev| workspace_state.erase_to_background.set(event_target_checked(&ev))
+                    />
+                    "Erase to background"
+                </label>
+            </Show>
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Pen(_) | Tool::Brush(_) | Tool::Eraser(_))) fallback=|| ()>
+                <SymmetryOptions symmetry = workspace_state.symmetry />
+            </Show>
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Bucket(_) | Tool::MagicWand(_))) fallback=|| ()>
+                <RegionOptions
+                    tolerance = workspace_state.tolerance
+                    contiguous = workspace_state.contiguous
+                    eight_connected = workspace_state.eight_connected
+                    sample_merged = workspace_state.sample_merged
+                />
+            </Show>
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Gradient(_))) fallback=|| ()>
+                <GradientOptions shape = workspace_state.gradient_shape preset = workspace_state.gradient_preset />
+            </Show>
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Line(_))) fallback=|| ()>
+                <LineOptions options = workspace_state.line_options />
+            </Show>
+            <Show when=move || current_tool.with(|t| matches!(t, Tool::Shape(_))) fallback=|| ()>
+                <ShapeOptions
+                    style = workspace_state.shape_style
+                    sides = workspace_state.polygon_sides
+                    show_sides = Signal::derive(move || current_tool.with(|t| matches!(t, Tool::Shape(s) if s.kind() == ShapeKind::Polygon)))
+                />
+            </Show>
+            {move || retouch_kind.get().map(|kind| view! {
+                <RetouchOptions strength = workspace_state.retouch_strength(kind) />
+            })}
         </nav>
     }
 }
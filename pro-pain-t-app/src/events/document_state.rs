@@ -0,0 +1,41 @@
+use crate::structs::history::History;
+use crate::structs::project::Project;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use pro_pain_t_shared::dtos::document_state::DocumentStateDto;
+use pro_pain_t_shared::dtos::project::ProjectDto;
+use tauri_sys::core::invoke;
+
+/// Asks the backend for a project left behind by a crashed session. The
+/// restored project has no file yet and counts as unsaved.
+pub fn restore_recovered_project(project: RwSignal<Project>) {
+    spawn_local(async move {
+        let Some(recovered) = invoke::<Option<ProjectDto>>("take_recovered_project_command", ()).await else {
+            return;
+        };
+        let Some(mut project_loaded) = Project::from_file_data(recovered.data, recovered.path) else {
+            return;
+        };
+        project_loaded.file_path = None;
+        project_loaded.history.update_value(History::forget_saved);
+        project_loaded.is_dirty = true;
+        project.update(|project| project.replace_project_with(project_loaded));
+    });
+}
+
+/// Keeps the backend informed about the project's file path and unsaved
+/// changes, which drive the window title, "Save" and the close guard.
+pub fn sync_document_state(project: RwSignal<Project>) {
+    let document_state = Memo::new(move |_| {
+        project.with(|project| {
+            DocumentStateDto::new(project.name.clone(), project.file_path.clone(), project.is_dirty)
+        })
+    });
+
+    Effect::new(move |_| {
+        let state = document_state.get();
+        spawn_local(async move {
+            invoke::<()>("update_document_state_command", state).await;
+        });
+    });
+}
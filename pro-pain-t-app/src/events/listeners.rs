@@ -2,21 +2,26 @@ use crate::structs::project::Project;
 use futures::StreamExt;
 use leptos::prelude::{RwSignal};
 
-use crate::render::canvas_renderer::composite_layers;
+use crate::state::workspace_state::WorkspaceState;
 use crate::structs::color::Color;
+use crate::structs::history::History;
+use crate::structs::image_placement::ImagePlacement;
 use crate::structs::layer::Layer;
-use image::RgbImage;
-use leptos::prelude::{Get, Set, Update};
+use crate::view_state::ProjectViewState;
+use crate::tools::select::{copy_selection, cut_selection, paste_as_floating, SelectionBuffer};
+use image::RgbaImage;
+use leptos::prelude::{Get, GetUntracked, Set, Update, WithUntracked};
 use leptos::task::spawn_local;
-use pro_pain_t_shared::dtos::export_object::ExportObjectDto;
+use pro_pain_t_shared::dtos::clipboard_image::ClipboardImageDto;
 use pro_pain_t_shared::dtos::image::ImageDto;
+use pro_pain_t_shared::dtos::open_image::OpenImageDto;
 use pro_pain_t_shared::dtos::path::PathDto;
 use pro_pain_t_shared::dtos::project::ProjectDto;
 use pro_pain_t_shared::dtos::save_object::SaveObjectDto;
 use pro_pain_t_shared::events::events::{
-    EVENT_MENU_CANVAS_SIZE, EVENT_MENU_EXPORT_PROJECT, EVENT_MENU_IMPORT_AS_LAYER, EVENT_MENU_NEW_PROJECT, EVENT_MENU_OPEN_PROJECT, EVENT_MENU_REDO, EVENT_MENU_SAVE_PROJECT, EVENT_MENU_UNDO
+    EVENT_AUTOSAVE, EVENT_MENU_CANVAS_SIZE, EVENT_MENU_COPY, EVENT_MENU_CUT, EVENT_MENU_EXPORT_PROJECT, EVENT_MENU_FILL_WINDOW, EVENT_MENU_FIT_TO_WINDOW, EVENT_MENU_IMPORT_AS_LAYER, EVENT_MENU_NEW_PROJECT, EVENT_MENU_OPEN_IMAGE, EVENT_MENU_OPEN_PROJECT, EVENT_MENU_PASTE, EVENT_MENU_PASTE_AS_LAYER, EVENT_MENU_REDO, EVENT_MENU_SAVE_PROJECT, EVENT_MENU_SHORTCUTS, EVENT_MENU_UNDO
 };
-use tauri_sys::core::invoke;
+use tauri_sys::core::invoke_result;
 use tauri_sys::event::listen;
 use crate::events::error::show_error_dialog;
 
@@ -33,7 +38,26 @@ pub fn open_project_listener(project: RwSignal<Project>) {
     spawn_local(async move {
         let mut listener = listen::<ProjectDto>(EVENT_MENU_OPEN_PROJECT).await.unwrap();
         while let Some(data) = listener.next().await {
-            let project_loaded = Project::from_file_data(data.payload.data);
+            let Some(project_loaded) = Project::from_file_data(data.payload.data, data.payload.path) else {
+                continue;
+            };
+            project.update(|project| {
+                project.replace_project_with(project_loaded);
+            });
+        }
+    });
+}
+
+pub fn open_image_listener(project: RwSignal<Project>) {
+    spawn_local(async move {
+        let mut listener = listen::<OpenImageDto>(EVENT_MENU_OPEN_IMAGE).await.unwrap();
+        while let Some(data) = listener.next().await {
+            let image = data.payload.image;
+            let Some(image) = RgbaImage::from_raw(image.width, image.height, image.raw_data) else {
+                show_error_dialog("Unable to create image buffer".to_string());
+                continue;
+            };
+            let project_loaded = Project::from_image(data.payload.name, &image);
             project.update(|project| {
                 project.replace_project_with(project_loaded);
             });
@@ -45,8 +69,28 @@ pub fn save_project_listener(project: RwSignal<Project>) {
     spawn_local(async move {
         let mut listener = listen::<PathDto>(EVENT_MENU_SAVE_PROJECT).await.unwrap();
         while let Some(data) = listener.next().await {
+            let path = data.payload.path;
             let project_serialized = project.get().serialize();
-            invoke::<()>(
+            let result = invoke_result::<(), String>(
+                "save_project_command",
+                SaveObjectDto::new(path.clone(), project_serialized),
+            )
+            .await;
+            if result.is_ok() {
+                project.update(|project| project.mark_saved(path));
+            }
+        }
+    });
+}
+
+/// Writes the project into the recovery file chosen by the backend's autosave
+/// timer. Unlike a regular save this leaves the path and unsaved state alone.
+pub fn autosave_listener(project: RwSignal<Project>) {
+    spawn_local(async move {
+        let mut listener = listen::<PathDto>(EVENT_AUTOSAVE).await.unwrap();
+        while let Some(data) = listener.next().await {
+            let project_serialized = project.get().serialize();
+            let _ = invoke_result::<(), String>(
                 "save_project_command",
                 SaveObjectDto::new(data.payload.path, project_serialized),
             )
@@ -55,51 +99,42 @@ pub fn save_project_listener(project: RwSignal<Project>) {
     });
 }
 
-pub fn import_as_layer_listener(project: RwSignal<Project>) {
+/// Images matching the document size are added right away, any other size is
+/// handed to `pending_import` so the user can pick an `ImagePlacement`.
+pub fn import_as_layer_listener(project: RwSignal<Project>, pending_import: RwSignal<Option<RgbaImage>>) {
     spawn_local(async move {
         let mut listener = listen::<ImageDto>(EVENT_MENU_IMPORT_AS_LAYER)
             .await
             .unwrap();
         while let Some(data) = listener.next().await {
-            project.update(|project| {
-                let image = RgbImage::from_raw(
-                    data.payload.width,
-                    data.payload.height,
-                    data.payload.raw_data,
-                );
-                if image.is_none() {
-                    show_error_dialog("Unable to create image buffer".to_string());
-                    return;
-                }
-                let layer_id = project.next_layer_id.get();
-                let new_layer =
-                    Layer::from_image(&image.unwrap(), layer_id, "Imported image", Color::default_black());
-                project.add_new_layer(new_layer);
+            let image = RgbaImage::from_raw(
+                data.payload.width,
+                data.payload.height,
+                data.payload.raw_data,
+            );
+            if image.is_none() {
+                show_error_dialog("Unable to create image buffer".to_string());
+                continue;
+            }
+            let image = image.unwrap();
+
+            let document_size = project.with_untracked(|project| {
+                (project.width.get_untracked(), project.height.get_untracked())
             });
+            if image.dimensions() == document_size {
+                project.update(|project| project.import_image(&image, ImagePlacement::TopLeft));
+            } else {
+                pending_import.set(Some(image));
+            }
         }
     });
 }
 
-pub fn export_project_listener(project: RwSignal<Project>) {
+pub fn export_project_listener(export_window_signal: RwSignal<bool>) {
     spawn_local(async move {
-        let mut listener = listen::<PathDto>(EVENT_MENU_EXPORT_PROJECT).await.unwrap();
-        while let Some(data) = listener.next().await {
-            let layers = project.get().layers.get();
-            if layers.is_empty() {
-                continue;
-            }
-
-            let raw_image = composite_layers(&layers).0;
-            let image_dto = ImageDto::new(
-                project.get().width.get(),
-                project.get().height.get(),
-                raw_image,
-            );
-            invoke::<()>(
-                "export_project_command",
-                ExportObjectDto::new(data.payload.path, image_dto),
-            )
-            .await;
+        let mut listener = listen::<()>(EVENT_MENU_EXPORT_PROJECT).await.unwrap();
+        while let Some(_) = listener.next().await {
+            export_window_signal.set(true);
         }
     });
 }
@@ -113,22 +148,148 @@ pub fn canvas_size_listener(canvas_size_window_signal: RwSignal<bool>) {
     });
 }
 
-pub fn undo_listener(project: RwSignal<Project>) {
+pub fn shortcuts_listener(shortcuts_window_signal: RwSignal<bool>) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_SHORTCUTS).await.unwrap();
+        while let Some(_) = listener.next().await {
+            shortcuts_window_signal.set(true);
+        }
+    });
+}
+
+pub fn fit_to_window_listener(project: RwSignal<Project>, view_state: ProjectViewState) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_FIT_TO_WINDOW).await.unwrap();
+        while let Some(_) = listener.next().await {
+            let (width, height) = project.with_untracked(|project| {
+                (project.width.get_untracked(), project.height.get_untracked())
+            });
+            view_state.fit_to_window(width, height);
+        }
+    });
+}
+
+pub fn fill_window_listener(project: RwSignal<Project>, view_state: ProjectViewState) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_FILL_WINDOW).await.unwrap();
+        while let Some(_) = listener.next().await {
+            let (width, height) = project.with_untracked(|project| {
+                (project.width.get_untracked(), project.height.get_untracked())
+            });
+            view_state.fill_window(width, height);
+        }
+    });
+}
+
+pub fn undo_listener(project: RwSignal<Project>, workspace_state: WorkspaceState) {
     spawn_local(async move {
         let mut listener = listen::<()>(EVENT_MENU_UNDO).await.unwrap();
 
         while let Some(_) = listener.next().await {
-            project.get().history.apply_undo(&project);
+            History::apply_undo(&project, &workspace_state);
         }
     });
 }
 
-pub fn redo_listener(project: RwSignal<Project>) {
+pub fn redo_listener(project: RwSignal<Project>, workspace_state: WorkspaceState) {
     spawn_local(async move {
         let mut listener = listen::<()>(EVENT_MENU_REDO).await.unwrap();
 
         while let Some(_) = listener.next().await {
-            project.get().history.apply_redo(&project);
+            History::apply_redo(&project, &workspace_state);
+        }
+    });
+}
+async fn write_clipboard(buffer: SelectionBuffer) {
+    let image = ImageDto::new(buffer.width, buffer.height, buffer.to_rgba());
+    let result = invoke_result::<(), String>("write_clipboard_image_command", ClipboardImageDto::new(image)).await;
+    if let Err(err) = result {
+        show_error_dialog(err);
+    }
+}
+
+async fn read_clipboard() -> Option<SelectionBuffer> {
+    match invoke_result::<ImageDto, String>("read_clipboard_image_command", ()).await {
+        Ok(image) => Some(SelectionBuffer::from_rgba(image.width, image.height, &image.raw_data)),
+        Err(err) => {
+            show_error_dialog(err);
+            None
+        }
+    }
+}
+
+pub fn copy_listener(project: RwSignal<Project>, workspace_state: WorkspaceState) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_COPY).await.unwrap();
+        while let Some(_) = listener.next().await {
+            if let Some(buffer) = copy_selection(&project, &workspace_state) {
+                write_clipboard(buffer).await;
+            }
+        }
+    });
+}
+
+pub fn cut_listener(project: RwSignal<Project>, workspace_state: WorkspaceState) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_CUT).await.unwrap();
+        while let Some(_) = listener.next().await {
+            if let Some(buffer) = cut_selection(&project, &workspace_state) {
+                write_clipboard(buffer).await;
+            }
         }
     });
-}
\ No newline at end of file
+}
+
+/// Pastes into the selected layer as a floating selection.
+pub fn paste_listener(project: RwSignal<Project>, workspace_state: WorkspaceState) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_PASTE).await.unwrap();
+        while let Some(_) = listener.next().await {
+            let Some(layer_id) = workspace_state.selected_layer_id.get_untracked() else {
+                show_error_dialog("Select a layer to paste into".to_string());
+                continue;
+            };
+            let is_editable = project.with_untracked(|project| {
+                project.layers.with_untracked(|layers| {
+                    layers
+                        .iter()
+                        .any(|l| l.id == layer_id && !l.is_locked && l.is_visible)
+                })
+            });
+            if !is_editable {
+                show_error_dialog("The selected layer is locked or hidden".to_string());
+                continue;
+            }
+
+            if let Some(buffer) = read_clipboard().await {
+                paste_as_floating(&project, &workspace_state, layer_id, buffer);
+            }
+        }
+    });
+}
+
+/// Pastes into a new transparent layer as a floating selection.
+pub fn paste_as_layer_listener(project: RwSignal<Project>, workspace_state: WorkspaceState) {
+    spawn_local(async move {
+        let mut listener = listen::<()>(EVENT_MENU_PASTE_AS_LAYER).await.unwrap();
+        while let Some(_) = listener.next().await {
+            let Some(buffer) = read_clipboard().await else {
+                continue;
+            };
+
+            let mut layer_id = 0;
+            project.update(|project| {
+                layer_id = project.next_layer_id.get_untracked();
+                let layer = Layer::new(
+                    layer_id,
+                    "Pasted layer",
+                    project.width.get_untracked(),
+                    project.height.get_untracked(),
+                    Color::transparent(),
+                );
+                project.add_new_layer(layer);
+            });
+            paste_as_floating(&project, &workspace_state, layer_id, buffer);
+        }
+    });
+}
@@ -1,2 +1,4 @@
 pub mod listeners;
+pub mod document_state;
 pub mod error;
+pub mod shortcuts;
@@ -0,0 +1,133 @@
+use crate::state::workspace_state::WorkspaceState;
+use crate::structs::project::Project;
+use crate::tools::{
+    brush::BrushState, bucket::BucketState, eraser::EraserState, eyedropper::EyedropperState,
+    gradient::GradientState, lasso::LassoState, line::LineState, magic_wand::MagicWandState,
+    pan::PanState, pen::PenState, retouch::RetouchState,
+    select::{delete_selection, fill_selection, SelectState},
+    shape::ShapeState,
+    tools::Tool,
+};
+use crate::view_state::ProjectViewState;
+use leptos::prelude::{GetUntracked, RwSignal, Set, Update, WithUntracked};
+use leptos::task::spawn_local;
+use pro_pain_t_shared::dtos::shortcuts::ShortcutsDto;
+use pro_pain_t_shared::shortcuts::{key_from_code, Chord, ShortcutAction};
+use tauri_sys::core::invoke;
+use wasm_bindgen::prelude::*;
+use web_sys::wasm_bindgen::JsCast;
+
+const BRUSH_SIZE_STEP: f32 = 0.1;
+
+pub fn load_shortcuts(workspace_state: WorkspaceState) {
+    spawn_local(async move {
+        let shortcuts = invoke::<ShortcutsDto>("get_shortcuts_command", ()).await;
+        workspace_state.shortcuts.set(shortcuts);
+    });
+}
+
+/// Chord of a key press, `None` for keys that can't be bound.
+pub fn chord_from_event(ev: &web_sys::KeyboardEvent) -> Option<Chord> {
+    Some(Chord {
+        cmd_or_ctrl: ev.ctrl_key() || ev.meta_key(),
+        alt: ev.alt_key(),
+        shift: ev.shift_key(),
+        key: key_from_code(&ev.code())?,
+    })
+}
+
+/// Runs the shortcuts that have no menu item. The others arrive as menu
+/// events through their accelerators.
+pub fn register_shortcuts(project: RwSignal<Project>, view_state: ProjectViewState, workspace_state: WorkspaceState) {
+    let Some(window) = web_sys::window() else { return };
+
+    let closure = Closure::wrap(Box::new(move |ev: web_sys::KeyboardEvent| {
+        if is_typing_target(&ev) {
+            return;
+        }
+        let Some(chord) = chord_from_event(&ev) else { return };
+        let chord = chord.to_string();
+
+        let action = workspace_state.shortcuts.with_untracked(|shortcuts| {
+            shortcuts
+                .bindings
+                .iter()
+                .find(|(action, bound)| action.menu_id().is_none() && **bound == chord)
+                .map(|(action, _)| *action)
+        });
+        let Some(action) = action else { return };
+
+        run_action(action, &project, &view_state, &workspace_state);
+        ev.prevent_default();
+    }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
+
+    let _ = window.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref());
+
+    closure.forget();
+}
+
+fn run_action(
+    action: ShortcutAction,
+    project: &RwSignal<Project>,
+    view_state: &ProjectViewState,
+    workspace_state: &WorkspaceState,
+) {
+    if let Some(tool) = tool_for(action) {
+        workspace_state.set_tool(tool, project);
+        return;
+    }
+
+    match action {
+        ShortcutAction::ZoomIn => view_state.zoom_in_by_step(),
+        ShortcutAction::ZoomOut => view_state.zoom_out_by_step(),
+        ShortcutAction::ZoomReset => view_state.reset_zoom_to_100(),
+        ShortcutAction::BrushSizeUp => workspace_state
+            .brush_size
+            .update(|size| *size = (*size + BRUSH_SIZE_STEP).clamp(0.1, 3.0)),
+        ShortcutAction::BrushSizeDown => workspace_state
+            .brush_size
+            .update(|size| *size = (*size - BRUSH_SIZE_STEP).clamp(0.1, 3.0)),
+        ShortcutAction::SwapColors => workspace_state.swap_colors(project),
+        ShortcutAction::DeleteSelection => delete_selection(project, workspace_state),
+        ShortcutAction::FillSelection => {
+            let color = project.with_untracked(|project| project.current_color.get_untracked());
+            fill_selection(project, workspace_state, color);
+        }
+        _ => {}
+    }
+}
+
+fn tool_for(action: ShortcutAction) -> Option<Tool> {
+    let tool = match action {
+        ShortcutAction::ToolPen => Tool::Pen(PenState::default()),
+        ShortcutAction::ToolBrush => Tool::Brush(BrushState::default()),
+        ShortcutAction::ToolLine => Tool::Line(LineState::default()),
+        ShortcutAction::ToolEraser => Tool::Eraser(EraserState::default()),
+        ShortcutAction::ToolBucket => Tool::Bucket(BucketState::default()),
+        ShortcutAction::ToolEyedropper => Tool::EyeDropper(EyedropperState::default()),
+        ShortcutAction::ToolSelect => Tool::Select(SelectState::default()),
+        ShortcutAction::ToolEllipseSelect => Tool::Select(SelectState::ellipse()),
+        ShortcutAction::ToolLasso => Tool::Lasso(LassoState::default()),
+        ShortcutAction::ToolPolygonalLasso => Tool::Lasso(LassoState::polygonal()),
+        ShortcutAction::ToolMagicWand => Tool::MagicWand(MagicWandState),
+        ShortcutAction::ToolGradient => Tool::Gradient(GradientState::default()),
+        ShortcutAction::ToolRectangle => Tool::Shape(ShapeState::rectangle()),
+        ShortcutAction::ToolEllipse => Tool::Shape(ShapeState::ellipse()),
+        ShortcutAction::ToolPolygon => Tool::Shape(ShapeState::polygon()),
+        ShortcutAction::ToolSmudge => Tool::Retouch(RetouchState::smudge()),
+        ShortcutAction::ToolBlur => Tool::Retouch(RetouchState::blur()),
+        ShortcutAction::ToolSharpen => Tool::Retouch(RetouchState::sharpen()),
+        ShortcutAction::ToolPan => Tool::Pan(PanState::default()),
+        _ => return None,
+    };
+    Some(tool)
+}
+
+/// Keys typed into form fields belong to the field.
+pub fn is_typing_target(ev: &web_sys::KeyboardEvent) -> bool {
+    let Some(element) = ev.target().and_then(|t| t.dyn_into::<web_sys::HtmlElement>().ok()) else {
+        return false;
+    };
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT") || element.is_content_editable()
+}
+
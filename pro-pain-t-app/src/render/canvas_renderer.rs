@@ -1,8 +1,10 @@
-use crate::structs::{color::Color, layer::Layer};
-use wasm_bindgen::{Clamped, JsCast, JsValue};
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+use crate::render::dirty_region::DirtyRect;
+use crate::render::tile_compositor::TileCompositor;
+use crate::structs::{blend_mode::BlendMode, color::Color, layer::Layer};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
-fn create_offscreen_canvas(width: u32, height: u32) -> HtmlCanvasElement {
+pub fn create_offscreen_canvas(width: u32, height: u32) -> HtmlCanvasElement {
     let document = web_sys::window()
         .and_then(|w| w.document())
         .expect("Failed to acquire document object");
@@ -46,101 +48,100 @@ pub fn draw_checkerboard(ctx: &CanvasRenderingContext2d, width: u32, height: u32
     }
 }
 
-fn blend(dst: Color, src: Color) -> Color {
-    let sa = src.alpha.clamp(0.0, 1.0);
-    let da = dst.alpha.clamp(0.0, 1.0);
+/// Composites `src` over `dst` following the W3C compositing model: the blend
+/// mode mixes the colors where both are present, then the result is placed
+/// with source-over using the source alpha scaled by `opacity`.
+fn blend(dst: [u8; 4], src: [u8; 4], opacity: f32, mode: BlendMode) -> [u8; 4] {
+    let sa = (src[3] as f32 / 255.0) * opacity;
+    let da = dst[3] as f32 / 255.0;
 
     let out_a = sa + da * (1.0 - sa);
 
     if out_a == 0.0 {
-        return Color {
-            r: 0,
-            g: 0,
-            b: 0,
-            alpha: 0.0,
-        };
+        return [0, 0, 0, 0];
     }
 
-    let sr = src.r as f32 / 255.0;
-    let sg = src.g as f32 / 255.0;
-    let sb = src.b as f32 / 255.0;
-
-    let dr = dst.r as f32 / 255.0;
-    let dg = dst.g as f32 / 255.0;
-    let db = dst.b as f32 / 255.0;
-
-    let r = (sr * sa + dr * da * (1.0 - sa)) / out_a;
-    let g = (sg * sa + dg * da * (1.0 - sa)) / out_a;
-    let b = (sb * sa + db * da * (1.0 - sa)) / out_a;
-
-    Color {
-        r: (r * 255.0) as u8,
-        g: (g * 255.0) as u8,
-        b: (b * 255.0) as u8,
-        alpha: out_a,
-    }
+    let channel = |s: u8, d: u8| {
+        let s = s as f32 / 255.0;
+        let d = d as f32 / 255.0;
+        let mixed = (1.0 - da) * s + da * mode.blend_channel(d, s);
+        (((mixed * sa + d * da * (1.0 - sa)) / out_a) * 255.0) as u8
+    };
+
+    [
+        channel(src[0], dst[0]),
+        channel(src[1], dst[1]),
+        channel(src[2], dst[2]),
+        (out_a * 255.0).round() as u8,
+    ]
 }
 
-pub fn composite_layers(layers: &[Layer]) -> (Vec<u8>, u32, u32) {
+pub fn composite_layers(layers: &[Layer], background: Color) -> (Vec<u8>, u32, u32) {
     if layers.is_empty() {
         return (Vec::new(), 0, 0);
     }
 
-    let base_canvas = &layers[0].canvas;
-    let width = base_canvas.width;
-    let height = base_canvas.height;
+    let width = layers[0].canvas.width;
+    let height = layers[0].canvas.height;
 
-    let mut out = vec![
-        Color {
-            r: base_canvas.background_color.r,
-            g: base_canvas.background_color.g,
-            b: base_canvas.background_color.b,
-            alpha: base_canvas.background_color.alpha,
-        };
-        (width * height) as usize
-    ];
+    (composite_region(layers, background, DirtyRect::new(0, 0, width, height)), width, height)
+}
+
+/// Composites the area `rect` of the document into a `rect.w * rect.h` RGBA buffer.
+/// The document `background` is laid down once beneath the whole stack, layers
+/// themselves may be transparent.
+pub fn composite_region(layers: &[Layer], background: Color, rect: DirtyRect) -> Vec<u8> {
+    let mut out = background.to_rgba8().repeat(rect.w as usize * rect.h as usize);
 
     for layer in layers {
-        if !layer.is_visible {
+        let opacity = layer.opacity.clamp(0.0, 1.0);
+        if !layer.is_visible || opacity == 0.0 {
             continue;
         }
 
-        for pixel in &layer.canvas.content {
-            if pixel.x >= width || pixel.y >= height {
-                continue;
-            }
-
-            let idx = (pixel.y * width + pixel.x) as usize;
-
-            let dst = out[idx];
-            let src = pixel.color;
-
-            out[idx] = blend(dst, src);
+        // Layers may differ in size from the document (e.g. imported images),
+        // only the overlapping area is blended.
+        let canvas = &layer.canvas;
+        let x_end = rect.right().min(canvas.width);
+        let y_end = rect.bottom().min(canvas.height);
+        if rect.x >= x_end || rect.y >= y_end {
+            continue;
         }
-    }
-
-    let mut bytes = Vec::with_capacity((width * height * 4) as usize);
 
-    for c in out {
-        bytes.push(c.r);
-        bytes.push(c.g);
-        bytes.push(c.b);
-        bytes.push((c.alpha * 255.0) as u8);
+        let src = canvas.as_rgba();
+        let row_bytes = (x_end - rect.x) as usize * 4;
+
+        for y in rect.y..y_end {
+            let src_start = (y as usize * canvas.width as usize + rect.x as usize) * 4;
+            let dst_start = (y - rect.y) as usize * rect.w as usize * 4;
+            let src_row = &src[src_start..src_start + row_bytes];
+            let dst_row = &mut out[dst_start..dst_start + row_bytes];
+
+            for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(4)) {
+                if src[3] == 0 {
+                    continue;
+                }
+                let blended = blend(
+                    [dst[0], dst[1], dst[2], dst[3]],
+                    [src[0], src[1], src[2], src[3]],
+                    opacity,
+                    layer.blend_mode,
+                );
+                dst.copy_from_slice(&blended);
+            }
+        }
     }
 
-    (bytes, width, height)
+    out
 }
 
-/// Draw a project-sized pixel buffer into a viewport-sized canvas using pan+zoom.
+/// Draw the cached document composite into a viewport-sized canvas using pan+zoom.
 /// - `viewport_w/h` are in *device pixels* (canvas.width/canvas.height)
-/// - `proj_w/h` are in project pixels
 pub fn draw_project_viewport(
     ctx: &CanvasRenderingContext2d,
     viewport_w: u32,
     viewport_h: u32,
-    pixels: &[u8],
-    proj_w: u32,
-    proj_h: u32,
+    compositor: &TileCompositor,
     t: ViewTransform,
 ) {
     let _ = ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
@@ -148,6 +149,12 @@ pub fn draw_project_viewport(
     ctx.set_fill_style_str("#ccc");
     ctx.fill_rect(0.0, 0.0, viewport_w as f64, viewport_h as f64);
 
+    let proj_w = compositor.width();
+    let proj_h = compositor.height();
+    if proj_w == 0 || proj_h == 0 {
+        return;
+    }
+
     let scale = (t.zoom as f64) * t.device_pixel_ratio;
     let tx = (t.pan_x as f64) * t.device_pixel_ratio;
     let ty = (t.pan_y as f64) * t.device_pixel_ratio;
@@ -159,26 +166,11 @@ pub fn draw_project_viewport(
     ctx.rect(0.0, 0.0, proj_w as f64, proj_h as f64);
     ctx.clip();
 
-    let has_transparency = pixels.chunks_exact(4).any(|px| px[3] < 255);
-    if has_transparency {
+    if compositor.has_transparency() {
         draw_checkerboard(ctx, proj_w, proj_h, 8);
     }
 
-    let offscreen = create_offscreen_canvas(proj_w, proj_h);
-    let off_ctx = offscreen
-        .get_context("2d")
-        .unwrap()
-        .unwrap()
-        .dyn_into::<CanvasRenderingContext2d>()
-        .unwrap();
-
-    let image_data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(pixels), proj_w, proj_h)
-        .expect("Failed to create ImageData");
-    off_ctx
-        .put_image_data(&image_data, 0.0, 0.0)
-        .expect("Failed to put ImageData");
-
-    ctx.draw_image_with_html_canvas_element(&offscreen, 0.0, 0.0)
+    ctx.draw_image_with_html_canvas_element(compositor.surface(), 0.0, 0.0)
         .expect("Failed to draw offscreen canvas");
 
     ctx.restore();
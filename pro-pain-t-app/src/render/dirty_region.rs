@@ -0,0 +1,68 @@
+use crate::structs::history::PixelDiff;
+
+/// Axis-aligned area of the document in project pixels.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl DirtyRect {
+    pub fn new(x: u32, y: u32, w: u32, h: u32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// Bounding box of every pixel touched by `diffs`, `None` if there are none.
+    pub fn from_diffs(diffs: &[PixelDiff]) -> Option<Self> {
+        let first = diffs.first()?.after;
+        let (mut x0, mut y0, mut x1, mut y1) = (first.x, first.y, first.x, first.y);
+        for diff in diffs {
+            x0 = x0.min(diff.after.x);
+            y0 = y0.min(diff.after.y);
+            x1 = x1.max(diff.after.x);
+            y1 = y1.max(diff.after.y);
+        }
+        Some(Self::new(x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+    }
+
+    pub fn right(&self) -> u32 {
+        self.x.saturating_add(self.w)
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.h)
+    }
+
+    pub fn intersects(&self, other: &DirtyRect) -> bool {
+        self.x < other.right() && other.x < self.right() && self.y < other.bottom() && other.y < self.bottom()
+    }
+}
+
+/// Areas reported as changed by the tools since the compositor last caught up.
+///
+/// Tools report the bounds of the pixels they change right before the layers
+/// signal fires. If a layer change arrives with nothing reported, the change
+/// wasn't a plain pixel edit (new layer, visibility, undo, ...) and the whole
+/// document has to be recomposited.
+#[derive(Clone, Default)]
+pub struct DirtyRegion {
+    rects: Option<Vec<DirtyRect>>,
+}
+
+impl DirtyRegion {
+    /// Records a pixel-only edit. `None` means the edit didn't change anything.
+    pub fn add(&mut self, rect: Option<DirtyRect>) {
+        let rects = self.rects.get_or_insert_with(Vec::new);
+        if let Some(rect) = rect {
+            rects.push(rect);
+        }
+    }
+
+    /// Returns the collected areas and resets the region. `None` means the whole
+    /// document is dirty.
+    pub fn take(&mut self) -> Option<Vec<DirtyRect>> {
+        self.rects.take()
+    }
+}
@@ -1 +1,3 @@
 pub mod canvas_renderer;
+pub mod dirty_region;
+pub mod tile_compositor;
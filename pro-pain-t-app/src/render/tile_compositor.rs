@@ -0,0 +1,121 @@
+use crate::render::canvas_renderer::{composite_region, create_offscreen_canvas};
+use crate::render::dirty_region::DirtyRect;
+use crate::structs::{color::Color, layer::Layer};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+/// Edge length of a cached tile in project pixels.
+pub const TILE_SIZE: u32 = 64;
+
+struct Tile {
+    rect: DirtyRect,
+    pixels: Vec<u8>,
+    has_transparency: bool,
+}
+
+/// Keeps the composite of all layers split into tiles on an offscreen surface,
+/// so that only tiles touched by an edit get re-blended and uploaded.
+pub struct TileCompositor {
+    width: u32,
+    height: u32,
+    tiles: Vec<Tile>,
+    surface: HtmlCanvasElement,
+    surface_ctx: CanvasRenderingContext2d,
+}
+
+impl TileCompositor {
+    pub fn new() -> Self {
+        let surface = create_offscreen_canvas(0, 0);
+        let surface_ctx = surface
+            .get_context("2d")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<CanvasRenderingContext2d>()
+            .unwrap();
+
+        Self {
+            width: 0,
+            height: 0,
+            tiles: Vec::new(),
+            surface,
+            surface_ctx,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Offscreen canvas holding the composited document at 1:1 scale.
+    pub fn surface(&self) -> &HtmlCanvasElement {
+        &self.surface
+    }
+
+    pub fn has_transparency(&self) -> bool {
+        self.tiles.iter().any(|tile| tile.has_transparency)
+    }
+
+    /// Brings the cached composite up to date with `layers`.
+    /// `dirty` lists the areas changed since the last update, `None` re-blends
+    /// the whole document.
+    pub fn update(
+        &mut self,
+        layers: &[Layer],
+        background: Color,
+        width: u32,
+        height: u32,
+        dirty: Option<Vec<DirtyRect>>,
+    ) {
+        let dirty = if width != self.width || height != self.height {
+            self.reset(width, height);
+            None
+        } else {
+            dirty
+        };
+
+        for tile in self.tiles.iter_mut() {
+            let is_stale = match &dirty {
+                None => true,
+                Some(rects) => rects.iter().any(|rect| rect.intersects(&tile.rect)),
+            };
+            if !is_stale {
+                continue;
+            }
+
+            tile.pixels = composite_region(layers, background, tile.rect);
+            tile.has_transparency = tile.pixels.chunks_exact(4).any(|px| px[3] < 255);
+
+            let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+                Clamped(&tile.pixels),
+                tile.rect.w,
+                tile.rect.h,
+            )
+            .expect("Failed to create ImageData");
+            self.surface_ctx
+                .put_image_data(&image_data, tile.rect.x as f64, tile.rect.y as f64)
+                .expect("Failed to put ImageData");
+        }
+    }
+
+    fn reset(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.surface.set_width(width);
+        self.surface.set_height(height);
+
+        self.tiles.clear();
+        for y in (0..height).step_by(TILE_SIZE as usize) {
+            for x in (0..width).step_by(TILE_SIZE as usize) {
+                self.tiles.push(Tile {
+                    rect: DirtyRect::new(x, y, TILE_SIZE.min(width - x), TILE_SIZE.min(height - y)),
+                    pixels: Vec::new(),
+                    has_transparency: false,
+                });
+            }
+        }
+    }
+}
@@ -1,13 +1,55 @@
-use crate::structs::project::Project;
-use crate::tools::{pen::PenState, select::{commit_selection, SelectionState}, tools::Tool};
+use crate::render::dirty_region::{DirtyRect, DirtyRegion};
+use crate::structs::{color::Color, history::PixelDiff, project::Project};
+use crate::tools::{
+    brush_engine::BrushSettings,
+    gradient::{GradientPreset, GradientShape},
+    line::LineOptions,
+    pen::PenState,
+    retouch::RetouchKind,
+    shape::ShapeStyle,
+    select::{commit_selection, SelectionState},
+    symmetry::Symmetry,
+    tools::Tool,
+};
 use leptos::prelude::*;
+use pro_pain_t_shared::dtos::shortcuts::ShortcutsDto;
 
 #[derive(Clone, Copy)]
 pub struct WorkspaceState {
     pub selected_layer_id: RwSignal<Option<usize>>,
     pub current_tool: RwSignal<Tool>,
     pub brush_size: RwSignal<f32>,
+    pub brush_settings: RwSignal<BrushSettings>,
+    /// Eraser tip, its opacity is how much alpha one stroke removes.
+    pub eraser_settings: RwSignal<BrushSettings>,
+    /// Erasing the bottom layer paints the document background color instead of transparency.
+    pub erase_to_background: RwSignal<bool>,
+    /// Second color of the palette, swapped with the project's current color.
+    pub secondary_color: RwSignal<Color>,
     pub selection: RwSignal<Option<SelectionState>>,
+    /// Largest color distance (`0..=255`) the bucket and magic wand still
+    /// treat as a match.
+    pub tolerance: RwSignal<u8>,
+    /// Bucket and magic wand only take the area connected to the clicked pixel.
+    pub contiguous: RwSignal<bool>,
+    /// Pixels touching only by a corner count as connected.
+    pub eight_connected: RwSignal<bool>,
+    /// Bucket and magic wand sample all visible layers instead of the selected one.
+    pub sample_merged: RwSignal<bool>,
+    pub gradient_shape: RwSignal<GradientShape>,
+    pub gradient_preset: RwSignal<GradientPreset>,
+    pub shape_style: RwSignal<ShapeStyle>,
+    /// Sides of the shapes drawn by the polygon tool.
+    pub polygon_sides: RwSignal<u32>,
+    pub line_options: RwSignal<LineOptions>,
+    /// Mirroring applied by the pen, brush and eraser.
+    pub symmetry: RwSignal<Symmetry>,
+    /// How strongly one stroke of each retouch tool changes the pixels, `0.0..=1.0`.
+    pub smudge_strength: RwSignal<f32>,
+    pub blur_strength: RwSignal<f32>,
+    pub sharpen_strength: RwSignal<f32>,
+    pub dirty_region: StoredValue<DirtyRegion>,
+    pub shortcuts: RwSignal<ShortcutsDto>,
 }
 
 impl Default for WorkspaceState {
@@ -16,7 +58,26 @@ impl Default for WorkspaceState {
             selected_layer_id: RwSignal::new(Some(0)),
             current_tool: RwSignal::new(Tool::Pen(PenState::default())),
             brush_size: RwSignal::new(1.0),
+            brush_settings: RwSignal::new(BrushSettings::default()),
+            eraser_settings: RwSignal::new(BrushSettings::default()),
+            erase_to_background: RwSignal::new(false),
+            secondary_color: RwSignal::new(Color::default_white()),
             selection: RwSignal::new(None),
+            tolerance: RwSignal::new(32),
+            contiguous: RwSignal::new(true),
+            eight_connected: RwSignal::new(false),
+            sample_merged: RwSignal::new(false),
+            gradient_shape: RwSignal::new(GradientShape::Linear),
+            gradient_preset: RwSignal::new(GradientPreset::ForegroundToBackground),
+            shape_style: RwSignal::new(ShapeStyle::Outline),
+            polygon_sides: RwSignal::new(5),
+            line_options: RwSignal::new(LineOptions::default()),
+            symmetry: RwSignal::new(Symmetry::default()),
+            smudge_strength: RwSignal::new(0.5),
+            blur_strength: RwSignal::new(0.5),
+            sharpen_strength: RwSignal::new(0.3),
+            dirty_region: StoredValue::new(DirtyRegion::default()),
+            shortcuts: RwSignal::new(ShortcutsDto::default()),
         }
     }
 }
@@ -26,13 +87,57 @@ impl WorkspaceState {
         Self::default()
     }
 
+    pub fn retouch_strength(&self, kind: RetouchKind) -> RwSignal<f32> {
+        match kind {
+            RetouchKind::Smudge => self.smudge_strength,
+            RetouchKind::Blur => self.blur_strength,
+            RetouchKind::Sharpen => self.sharpen_strength,
+        }
+    }
+
+    /// Switching tools keeps the selection outline so it can be moved, filled
+    /// or painted into with the next tool.
     pub fn set_tool(&self, tool: Tool, project: &RwSignal<Project>) {
-        self.selection.with(|sel| {
+        self.settle_selection(project);
+        self.current_tool.set(tool);
+    }
+
+    /// Drops a floating selection back into its layer, keeping its outline.
+    pub fn settle_selection(&self, project: &RwSignal<Project>) {
+        self.selection.with_untracked(|sel| {
             if let Some(sel) = sel {
                 commit_selection(project, sel);
             }
         });
-        self.selection.set(None);
-        self.current_tool.set(tool);
+        self.selection.update(|sel| {
+            if let Some(sel) = sel.as_mut() {
+                sel.buffer = None;
+                sel.original_pixels.clear();
+            }
+        });
+    }
+
+    pub fn swap_colors(&self, project: &RwSignal<Project>) {
+        let current_color = project.with_untracked(|project| project.current_color);
+        let previous = current_color.get_untracked();
+        current_color.set(self.secondary_color.get_untracked());
+        self.secondary_color.set(previous);
+    }
+
+    /// Reports the pixels a tool is about to publish through the layers signal,
+    /// so the viewport only recomposites the touched tiles.
+    pub fn mark_dirty(&self, diffs: &[PixelDiff]) {
+        self.mark_dirty_rect(DirtyRect::from_diffs(diffs));
+    }
+
+    /// Like `mark_dirty`, for edits that already know their bounds.
+    pub fn mark_dirty_rect(&self, rect: Option<DirtyRect>) {
+        self.dirty_region.update_value(|region| region.add(rect));
+    }
+
+    pub fn take_dirty(&self) -> Option<Vec<DirtyRect>> {
+        let mut rects = None;
+        self.dirty_region.update_value(|region| rects = region.take());
+        rects
     }
 }
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// How a layer's colors are combined with the layers beneath it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    Difference,
+    Add,
+    ColorDodge,
+    ColorBurn,
+}
+
+impl BlendMode {
+    /// Every mode, in the order they are listed in the UI. The index doubles as
+    /// the mode's id in project files.
+    pub const ALL: [BlendMode; 10] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::Darken,
+        BlendMode::Lighten,
+        BlendMode::Difference,
+        BlendMode::Add,
+        BlendMode::ColorDodge,
+        BlendMode::ColorBurn,
+    ];
+
+    pub fn id(self) -> u8 {
+        Self::ALL
+            .iter()
+            .position(|mode| *mode == self)
+            .expect("every blend mode is listed in BlendMode::ALL") as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BlendMode::Normal => "Normal",
+            BlendMode::Multiply => "Multiply",
+            BlendMode::Screen => "Screen",
+            BlendMode::Overlay => "Overlay",
+            BlendMode::Darken => "Darken",
+            BlendMode::Lighten => "Lighten",
+            BlendMode::Difference => "Difference",
+            BlendMode::Add => "Add",
+            BlendMode::ColorDodge => "Color dodge",
+            BlendMode::ColorBurn => "Color burn",
+        }
+    }
+
+    /// Blends a single channel, both values in `0.0..=1.0`.
+    /// `backdrop` is the color below the layer, `source` the layer's own color.
+    pub fn blend_channel(self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            BlendMode::Normal => source,
+            BlendMode::Multiply => backdrop * source,
+            BlendMode::Screen => backdrop + source - backdrop * source,
+            BlendMode::Overlay => {
+                if backdrop <= 0.5 {
+                    2.0 * backdrop * source
+                } else {
+                    let b = 2.0 * backdrop - 1.0;
+                    b + source - b * source
+                }
+            }
+            BlendMode::Darken => backdrop.min(source),
+            BlendMode::Lighten => backdrop.max(source),
+            BlendMode::Difference => (backdrop - source).abs(),
+            BlendMode::Add => (backdrop + source).min(1.0),
+            BlendMode::ColorDodge => {
+                if backdrop <= 0.0 {
+                    0.0
+                } else if source >= 1.0 {
+                    1.0
+                } else {
+                    (backdrop / (1.0 - source)).min(1.0)
+                }
+            }
+            BlendMode::ColorBurn => {
+                if backdrop >= 1.0 {
+                    1.0
+                } else if source <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - ((1.0 - backdrop) / source).min(1.0)
+                }
+            }
+        }
+    }
+}
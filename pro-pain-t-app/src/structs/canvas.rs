@@ -1,12 +1,12 @@
 use crate::structs::{color::Color, pixel::Pixel};
-use image::RgbImage;
+use image::RgbaImage;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Canvas {
     pub width: u32,
     pub height: u32,
-    pub content: Vec<Pixel>, // Two-dimensional - position of [x, y] is y * width + x
+    data: Vec<u8>, // Row-major RGBA - bytes of [x, y] start at (y * width + x) * 4
     pub background_color: Color,
 }
 
@@ -16,56 +16,78 @@ impl Canvas {
             .checked_mul(height)
             .expect("canvas dimensions too large: width * height overflowed u32");
 
-        let mut content: Vec<Pixel> = Vec::with_capacity(capacity as usize);
-
-        for y in 0..height {
-            for x in 0..width {
-                content.push(Pixel::new(x, y, background_color));
-            }
-        }
+        let data = background_color.to_rgba8().repeat(capacity as usize);
 
         Self {
             width,
             height,
-            content,
+            data,
             background_color,
         }
     }
 
-    pub fn from_image(image: &RgbImage, background_color: Color) -> Self {
-        let content = image
-            .enumerate_pixels()
-            .into_iter()
-            .map(|pixel| Pixel::from_rgb(pixel.0, pixel.1, *pixel.2))
-            .collect::<Vec<Pixel>>();
+    pub fn from_image(image: &RgbaImage, background_color: Color) -> Self {
+        let data = image.as_raw().clone();
 
         Self {
             width: image.width(),
             height: image.height(),
-            content,
+            data,
             background_color,
         }
     }
 
-    pub fn set_pixel(&mut self, pixel: Pixel) -> Result<(), String> {
-        if pixel.x >= self.width || pixel.y >= self.height {
-            return Err("Pixel out of bounds".to_string());
+    /// Builds a canvas from tightly packed RGBA bytes in row-major order.
+    pub fn from_rgba(width: u32, height: u32, rgba: Vec<u8>, background_color: Color) -> Self {
+        assert_eq!(
+            rgba.len(),
+            width as usize * height as usize * 4,
+            "RGBA buffer doesn't match canvas dimensions"
+        );
+
+        Self {
+            width,
+            height,
+            data: rgba,
+            background_color,
         }
+    }
 
-        let index = (pixel.y * self.width + pixel.x) as usize;
-        self.content[index] = pixel;
+    /// Raw row-major RGBA bytes of the whole canvas.
+    pub fn as_rgba(&self) -> &[u8] {
+        &self.data
+    }
 
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y as usize * self.width as usize + x as usize) * 4)
+    }
+
+    pub fn get_rgba(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let i = self.index(x, y)?;
+        Some([self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]])
+    }
+
+    pub fn set_rgba(&mut self, x: u32, y: u32, rgba: [u8; 4]) -> Result<(), String> {
+        let Some(i) = self.index(x, y) else {
+            return Err("Pixel out of bounds".to_string());
+        };
+        self.data[i..i + 4].copy_from_slice(&rgba);
         Ok(())
     }
 
+    pub fn set_pixel(&mut self, pixel: Pixel) -> Result<(), String> {
+        self.set_rgba(pixel.x, pixel.y, pixel.color.to_rgba8())
+    }
+
     pub fn get_pixel(&self, x: u32, y: u32) -> Result<Pixel, String> {
-        if x >= self.width || y >= self.height {
+        let Some(rgba) = self.get_rgba(x, y) else {
             return Err("Pixel out of bounds".to_string());
-        }
-
-        let index = (y * self.width + x) as usize;
+        };
 
-        Ok(self.content[index])
+        Ok(Pixel::new(x, y, Color::from_rgba8(rgba)))
     }
 
     pub fn resize(&mut self, new_width: u32, new_height: u32) {
@@ -77,28 +99,24 @@ impl Canvas {
             .checked_mul(new_height)
             .expect("Canvas dimensions too large: width * height overflowed u32");
 
-        let mut new_content: Vec<Pixel> = Vec::with_capacity(capacity as usize);
-
-        for y in 0..new_height {
-            for x in 0..new_width {
-                if x < self.width && y < self.height {
-                    let old_index = (y * self.width + x) as usize;
-                    let pixel = self.content[old_index];
-                    new_content.push(pixel);
-                } else {
-                    new_content.push(Pixel::new(x, y, self.background_color));
-                }
-            }
+        let mut new_data = self.background_color.to_rgba8().repeat(capacity as usize);
+
+        let copy_w = self.width.min(new_width) as usize * 4;
+        for y in 0..self.height.min(new_height) as usize {
+            let src = y * self.width as usize * 4;
+            let dst = y * new_width as usize * 4;
+            new_data[dst..dst + copy_w].copy_from_slice(&self.data[src..src + copy_w]);
         }
 
         self.width = new_width;
         self.height = new_height;
-        self.content = new_content;
+        self.data = new_data;
     }
 
     pub fn clear(&mut self) {
-        self.content
-            .iter_mut()
-            .for_each(|p| p.color = self.background_color);
+        let background = self.background_color.to_rgba8();
+        self.data
+            .chunks_exact_mut(4)
+            .for_each(|px| px.copy_from_slice(&background));
     }
 }
@@ -20,4 +20,62 @@ impl Color {
     pub fn default_black() -> Self {
         Self::new(0, 0, 0, 1.0)
     }
+
+    pub fn transparent() -> Self {
+        Self::new(0, 0, 0, 0.0)
+    }
+
+    pub fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Self::new(rgba[0], rgba[1], rgba[2], rgba[3] as f32 / 255.0)
+    }
+
+    pub fn to_rgba8(self) -> [u8; 4] {
+        [self.r, self.g, self.b, (self.alpha.clamp(0.0, 1.0) * 255.0).round() as u8]
+    }
+
+    /// Euclidean distance of the 8-bit RGBA channels, scaled to `0.0..=255.0`.
+    pub fn distance(self, other: Color) -> f32 {
+        let a = self.to_rgba8();
+        let b = other.to_rgba8();
+        let sum: f32 = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| (*a as f32 - *b as f32).powi(2))
+            .sum();
+        (sum / 4.0).sqrt()
+    }
+
+    /// `top` composited over `self` (source over), with `top` faded by
+    /// `opacity`. The result is rounded to the canvas' 8-bit precision.
+    pub fn blend_over(self, top: Color, opacity: f32) -> Color {
+        let src_a = top.alpha.clamp(0.0, 1.0) * opacity.clamp(0.0, 1.0);
+        let dst_a = self.alpha.clamp(0.0, 1.0);
+        let out_a = src_a + dst_a * (1.0 - src_a);
+        if out_a <= 0.0 {
+            return Color::transparent();
+        }
+
+        let channel = |s: u8, d: u8| {
+            ((s as f32 * src_a + d as f32 * dst_a * (1.0 - src_a)) / out_a).round() as u8
+        };
+        let blended = Color {
+            r: channel(top.r, self.r),
+            g: channel(top.g, self.g),
+            b: channel(top.b, self.b),
+            alpha: out_a,
+        };
+        Color::from_rgba8(blended.to_rgba8())
+    }
+
+    /// Straight interpolation of every channel, `t = 0` is `self`, `t = 1` is `other`.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            alpha: self.alpha + (other.alpha - self.alpha) * t,
+        }
+    }
 }
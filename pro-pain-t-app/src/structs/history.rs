@@ -1,7 +1,17 @@
-use crate::structs::{pixel::Pixel, project::Project};
-use leptos::prelude::{Get, RwSignal, Update};
+use crate::state::workspace_state::WorkspaceState;
+use crate::structs::{
+    canvas::Canvas,
+    layer::{Layer, LayerProperties},
+    pixel::Pixel,
+    project::Project,
+    stroke_patch::StrokePatch,
+};
+use leptos::prelude::{GetUntracked, RwSignal, Set, StoredValue, Update, UpdateValue, WithUntracked, WithValue};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::{collections::VecDeque, mem::size_of};
+
+/// Memory the undo and redo stacks may use together, in bytes.
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PixelDiff {
@@ -15,81 +25,386 @@ pub struct StrokeDiff {
     pub pixels: Vec<PixelDiff>,
 }
 
+/// A single undoable change to the document. Every variant carries enough
+/// state to be both reverted and applied again.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum HistoryOperation {
+    /// Pixels changed by a tool.
+    Stroke(StrokePatch),
+    /// `layer` was inserted at `index` (new, duplicated or imported layer).
+    AddLayer { index: usize, layer: Layer },
+    /// `layer` was removed from `index`.
+    RemoveLayer { index: usize, layer: Layer },
+    /// The layer at `from` was moved to `to`.
+    MoveLayer { from: usize, to: usize },
+    /// The whole canvas of a layer was swapped, e.g. when clearing it.
+    ReplaceCanvas {
+        layer_id: usize,
+        before: Canvas,
+        after: Canvas,
+    },
+    /// Title, visibility, lock, opacity, blend mode or background changed.
+    LayerProperties {
+        layer_id: usize,
+        before: LayerProperties,
+        after: LayerProperties,
+    },
+    /// The document was resized. Resizing crops, so the canvases from before
+    /// are kept to restore cut off pixels.
+    ResizeCanvas {
+        before: (u32, u32),
+        after: (u32, u32),
+        canvases_before: Vec<(usize, Canvas)>,
+    },
+}
+
+impl StrokeDiff {
+    /// Puts back the pixels from before the stroke, for a stroke abandoned
+    /// before it was recorded.
+    pub fn restore(&self, project: &Project) {
+        if self.pixels.is_empty() {
+            return;
+        }
+        with_canvas(project, self.layer_id, |canvas| {
+            // A pixel painted twice has two diffs, the first one holds its original color
+            for diff in self.pixels.iter().rev() {
+                let _ = canvas.set_pixel(diff.before);
+            }
+        });
+    }
+}
+
+impl From<StrokeDiff> for HistoryOperation {
+    fn from(stroke: StrokeDiff) -> Self {
+        HistoryOperation::Stroke(StrokePatch::from_diff(&stroke))
+    }
+}
+
+impl HistoryOperation {
+    /// Approximate memory held by the operation.
+    pub fn byte_size(&self) -> usize {
+        let data = match self {
+            HistoryOperation::Stroke(patch) => patch.byte_size(),
+            HistoryOperation::AddLayer { layer, .. } | HistoryOperation::RemoveLayer { layer, .. } => {
+                layer.canvas.as_rgba().len() + layer.title.len()
+            }
+            HistoryOperation::MoveLayer { .. } => 0,
+            HistoryOperation::ReplaceCanvas { before, after, .. } => {
+                before.as_rgba().len() + after.as_rgba().len()
+            }
+            HistoryOperation::LayerProperties { before, after, .. } => {
+                before.title.len() + after.title.len()
+            }
+            HistoryOperation::ResizeCanvas { canvases_before, .. } => canvases_before
+                .iter()
+                .map(|(_, canvas)| canvas.as_rgba().len())
+                .sum(),
+        };
+        size_of::<Self>() + data
+    }
+
+    /// Reports the pixels a stroke is about to change to the compositor. Other
+    /// operations report nothing, so the whole document is recomposited.
+    fn mark_dirty(&self, workspace_state: &WorkspaceState) {
+        if let HistoryOperation::Stroke(patch) = self {
+            workspace_state.mark_dirty_rect(patch.bounds());
+        }
+    }
+
+    fn revert(&self, project: &Project) {
+        match self {
+            HistoryOperation::Stroke(patch) => {
+                with_canvas(project, patch.layer_id, |canvas| patch.revert(canvas));
+            }
+            HistoryOperation::AddLayer { layer, .. } => {
+                project.layers.update(|layers| layers.retain(|l| l.id != layer.id));
+            }
+            HistoryOperation::RemoveLayer { index, layer } => {
+                project.layers.update(|layers| {
+                    let index = (*index).min(layers.len());
+                    layers.insert(index, layer.clone());
+                });
+            }
+            HistoryOperation::MoveLayer { from, to } => move_layer(project, *to, *from),
+            HistoryOperation::ReplaceCanvas { layer_id, before, .. } => {
+                set_canvas(project, *layer_id, before.clone());
+            }
+            HistoryOperation::LayerProperties { layer_id, before, .. } => {
+                set_properties(project, *layer_id, before.clone());
+            }
+            HistoryOperation::ResizeCanvas { before, canvases_before, .. } => {
+                project.layers.update(|layers| {
+                    for (layer_id, canvas) in canvases_before {
+                        if let Some(layer) = layers.iter_mut().find(|l| l.id == *layer_id) {
+                            layer.canvas = canvas.clone();
+                        }
+                    }
+                });
+                project.width.set(before.0);
+                project.height.set(before.1);
+            }
+        }
+    }
+
+    fn apply(&self, project: &Project) {
+        match self {
+            HistoryOperation::Stroke(patch) => {
+                with_canvas(project, patch.layer_id, |canvas| patch.apply(canvas));
+            }
+            HistoryOperation::AddLayer { index, layer } => {
+                project.layers.update(|layers| {
+                    let index = (*index).min(layers.len());
+                    layers.insert(index, layer.clone());
+                });
+            }
+            HistoryOperation::RemoveLayer { layer, .. } => {
+                project.layers.update(|layers| layers.retain(|l| l.id != layer.id));
+            }
+            HistoryOperation::MoveLayer { from, to } => move_layer(project, *from, *to),
+            HistoryOperation::ReplaceCanvas { layer_id, after, .. } => {
+                set_canvas(project, *layer_id, after.clone());
+            }
+            HistoryOperation::LayerProperties { layer_id, after, .. } => {
+                set_properties(project, *layer_id, after.clone());
+            }
+            HistoryOperation::ResizeCanvas { after, .. } => {
+                project.layers.update(|layers| {
+                    for layer in layers.iter_mut() {
+                        layer.resize_canvas(after.0, after.1);
+                    }
+                });
+                project.width.set(after.0);
+                project.height.set(after.1);
+            }
+        }
+    }
+}
+
+fn with_canvas(project: &Project, layer_id: usize, f: impl FnOnce(&mut Canvas)) {
+    project.layers.update(|layers| {
+        if let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) {
+            f(&mut layer.canvas);
+        }
+    });
+}
+
+fn set_canvas(project: &Project, layer_id: usize, canvas: Canvas) {
+    project.layers.update(|layers| {
+        if let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) {
+            layer.canvas = canvas;
+        }
+    });
+}
+
+fn set_properties(project: &Project, layer_id: usize, properties: LayerProperties) {
+    project.layers.update(|layers| {
+        if let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) {
+            layer.set_properties(properties);
+        }
+    });
+}
+
+/// Selects another layer when the selected one was removed from `index`,
+/// preferring the layer that took its place like deleting from the layer panel.
+fn keep_layer_selected(project: &Project, workspace_state: &WorkspaceState, index: usize) {
+    let selected = workspace_state.selected_layer_id.get_untracked();
+    let replacement = project.layers.with_untracked(|layers| {
+        if layers.iter().any(|l| Some(l.id) == selected) {
+            return selected;
+        }
+        layers.get(index).or(layers.last()).map(|l| l.id)
+    });
+    if replacement != selected {
+        workspace_state.selected_layer_id.set(replacement);
+    }
+}
+
+fn move_layer(project: &Project, from: usize, to: usize) {
+    project.layers.update(|layers| {
+        if from >= layers.len() || to >= layers.len() {
+            return;
+        }
+        let layer = layers.remove(from);
+        layers.insert(to, layer);
+    });
+}
+
+/// Undo/redo stacks bounded by the memory their operations hold rather than
+/// by step count. The newest operation is always kept, even if it alone is
+/// larger than the budget.
+///
+/// Every operation gets an id, and the document's state is named by the id
+/// of the last one applied. That lets undoing back to the saved state count
+/// as unsaved changes gone.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct History {
-    max_count: usize,
-    undo: VecDeque<StrokeDiff>,
-    redo: VecDeque<StrokeDiff>,
+    memory_budget: usize,
+    used_bytes: usize,
+    undo: VecDeque<(u64, HistoryOperation)>,
+    redo: VecDeque<(u64, HistoryOperation)>,
+    next_id: u64,
+    /// State left once everything still in `undo` is undone.
+    base: u64,
+    /// State of the last save, `None` if the document was never saved.
+    saved: Option<u64>,
 }
 
 impl History {
-    pub fn new(max_count: usize) -> Self {
+    pub fn new(memory_budget: usize) -> Self {
         Self {
-            max_count,
-            undo: VecDeque::with_capacity(max_count),
-            redo: VecDeque::with_capacity(max_count),
+            memory_budget,
+            used_bytes: 0,
+            undo: VecDeque::new(),
+            redo: VecDeque::new(),
+            next_id: 1,
+            base: 0,
+            saved: Some(0),
         }
     }
 
-    pub fn add(&mut self, stroke: StrokeDiff) {
-        if self.undo.len() >= self.max_count {
-            self.undo.pop_front();
+    pub fn add(&mut self, operation: impl Into<HistoryOperation>) {
+        let operation = operation.into();
+        if matches!(&operation, HistoryOperation::Stroke(patch) if patch.is_empty()) {
+            return;
+        }
+
+        for (_, discarded) in self.redo.drain(..) {
+            self.used_bytes -= discarded.byte_size();
+        }
+        self.used_bytes += operation.byte_size();
+        self.undo.push_back((self.next_id, operation));
+        self.next_id += 1;
+
+        while self.used_bytes > self.memory_budget && self.undo.len() > 1 {
+            if let Some((id, evicted)) = self.undo.pop_front() {
+                self.used_bytes -= evicted.byte_size();
+                self.base = id;
+            }
         }
-        self.undo.push_back(stroke);
-        self.redo.clear();
     }
 
-    pub fn undo(&mut self) -> Option<StrokeDiff> {
-        let stroke = self.undo.pop_back()?;
-        self.redo.push_back(stroke.clone());
-        Some(stroke)
+    pub fn undo(&mut self) -> Option<HistoryOperation> {
+        let entry = self.undo.pop_back()?;
+        let operation = entry.1.clone();
+        self.redo.push_back(entry);
+        Some(operation)
     }
 
-    pub fn redo(&mut self) -> Option<StrokeDiff> {
-        let stroke = self.redo.pop_back()?;
-        self.undo.push_back(stroke.clone());
-        Some(stroke)
+    pub fn redo(&mut self) -> Option<HistoryOperation> {
+        let entry = self.redo.pop_back()?;
+        let operation = entry.1.clone();
+        self.undo.push_back(entry);
+        Some(operation)
     }
 
-    pub fn apply_undo(&mut self, project: &RwSignal<Project>) {
+    fn state(&self) -> u64 {
+        self.undo.back().map_or(self.base, |(id, _)| *id)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.state());
+    }
+
+    /// For documents with content that was never saved, like a recovered one.
+    pub fn forget_saved(&mut self) {
+        self.saved = None;
+    }
 
-        let mut stroke_opt = None;
+    /// Whether the document is in the state it was last saved in.
+    pub fn is_saved(&self) -> bool {
+        self.saved == Some(self.state())
+    }
+
+    /// A fresh history with the default budget, stored outside of `Project`
+    /// so cloning a project doesn't copy its undo log.
+    pub fn new_stored() -> StoredValue<History> {
+        StoredValue::new(History::new(DEFAULT_MEMORY_BUDGET))
+    }
+
+    pub fn apply_undo(project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
+        let mut operation_opt = None;
         project.update(|project| {
-            stroke_opt = project.history.undo();
+            operation_opt = project.history.try_update_value(|history| history.undo()).flatten();
+            if operation_opt.is_some() {
+                project.is_dirty = !project.history.with_value(History::is_saved);
+            }
         });
 
-        let Some(stroke) = stroke_opt else { return };
+        let Some(operation) = operation_opt else { return };
 
-        project.get().layers.update(|layers| {
-            if let Some(layer) = layers.iter_mut().find(|l| l.id == stroke.layer_id) {
-                let canvas = &mut layer.canvas;
-                for diff in &stroke.pixels {
-                    let _ = canvas.set_pixel(diff.before);
-                }
+        operation.mark_dirty(workspace_state);
+        project.with_untracked(|project| {
+            operation.revert(project);
+            if let HistoryOperation::AddLayer { index, .. } = operation {
+                keep_layer_selected(project, workspace_state, index);
             }
         });
-
     }
 
-    pub fn apply_redo(&mut self, project: &RwSignal<Project>) {
-     
-        let mut stroke_opt = None;
+    pub fn apply_redo(project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
+        let mut operation_opt = None;
         project.update(|project| {
-            stroke_opt = project.history.redo();
+            operation_opt = project.history.try_update_value(|history| history.redo()).flatten();
+            if operation_opt.is_some() {
+                project.is_dirty = !project.history.with_value(History::is_saved);
+            }
         });
 
-        let Some(stroke) = stroke_opt else { return };
+        let Some(operation) = operation_opt else { return };
 
-        project.get().layers.update(|layers| {
-            if let Some(layer) = layers.iter_mut().find(|l| l.id == stroke.layer_id) {
-                let canvas = &mut layer.canvas;
-                for diff in &stroke.pixels {
-                    let _ = canvas.set_pixel(diff.after);
-                }
+        operation.mark_dirty(workspace_state);
+        project.with_untracked(|project| {
+            operation.apply(project);
+            if let HistoryOperation::RemoveLayer { index, .. } = operation {
+                keep_layer_selected(project, workspace_state, index);
             }
         });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::color::Color;
 
+    #[test]
+    fn undoing_a_new_layer_selects_a_remaining_one() {
+        let project = RwSignal::new(Project::new("Layers".to_string(), 2, 2, Color::default_white()));
+        let workspace_state = WorkspaceState::default();
+        project.update(|project| project.add_new_layer(Layer::new(1, "Layer 1".to_string(), 2, 2, Color::transparent())));
+        workspace_state.selected_layer_id.set(Some(1));
+
+        History::apply_undo(&project, &workspace_state);
+        assert_eq!(workspace_state.selected_layer_id.get_untracked(), Some(0));
+
+        // Redoing doesn't take the selection away from a layer that still exists
+        History::apply_redo(&project, &workspace_state);
+        assert_eq!(workspace_state.selected_layer_id.get_untracked(), Some(0));
     }
 
+    #[test]
+    fn undoing_back_to_the_saved_state_clears_unsaved_changes() {
+        let project = RwSignal::new(Project::new("Saved".to_string(), 2, 2, Color::default_white()));
+        let workspace_state = WorkspaceState::default();
+        let add_layer = |id: usize| {
+            project.update(|project| project.add_new_layer(Layer::new(id, format!("Layer {}", id), 2, 2, Color::transparent())));
+        };
+        let is_dirty = || project.with_untracked(|project| project.is_dirty);
+
+        add_layer(1);
+        project.update(|project| project.mark_saved("saved.ppp".to_string()));
+        add_layer(2);
+        assert!(is_dirty());
 
+        History::apply_undo(&project, &workspace_state);
+        assert!(!is_dirty());
+        History::apply_undo(&project, &workspace_state);
+        assert!(is_dirty());
+        History::apply_redo(&project, &workspace_state);
+        assert!(!is_dirty());
+
+        // A different edit from the saved state is a new state, even with the same content
+        add_layer(2);
+        assert!(is_dirty());
+    }
 }
@@ -0,0 +1,65 @@
+use image::{imageops, RgbaImage};
+
+/// Where an imported image goes when its size differs from the document.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ImagePlacement {
+    #[default]
+    Center,
+    TopLeft,
+    /// Scaled to fit inside the document keeping its aspect ratio, then centered.
+    Fit,
+    /// The document is resized to the image first.
+    ResizeCanvas,
+}
+
+impl ImagePlacement {
+    pub const ALL: [ImagePlacement; 4] = [
+        ImagePlacement::Center,
+        ImagePlacement::TopLeft,
+        ImagePlacement::Fit,
+        ImagePlacement::ResizeCanvas,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImagePlacement::Center => "Center",
+            ImagePlacement::TopLeft => "Top-left",
+            ImagePlacement::Fit => "Fit to canvas",
+            ImagePlacement::ResizeCanvas => "Resize canvas to image",
+        }
+    }
+
+    /// Lays `image` out on a transparent `width` x `height` image, cropping
+    /// whatever falls outside.
+    pub fn place(self, image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+        let mut placed = RgbaImage::new(width, height);
+
+        match self {
+            ImagePlacement::TopLeft | ImagePlacement::ResizeCanvas => {
+                imageops::replace(&mut placed, image, 0, 0);
+            }
+            ImagePlacement::Center => {
+                let (x, y) = centered(image, width, height);
+                imageops::replace(&mut placed, image, x, y);
+            }
+            ImagePlacement::Fit => {
+                let scale = (width as f64 / image.width().max(1) as f64)
+                    .min(height as f64 / image.height().max(1) as f64);
+                let fit_w = ((image.width() as f64 * scale).round() as u32).clamp(1, width.max(1));
+                let fit_h = ((image.height() as f64 * scale).round() as u32).clamp(1, height.max(1));
+                let scaled = imageops::resize(image, fit_w, fit_h, imageops::FilterType::Triangle);
+                let (x, y) = centered(&scaled, width, height);
+                imageops::replace(&mut placed, &scaled, x, y);
+            }
+        }
+
+        placed
+    }
+}
+
+fn centered(image: &RgbaImage, width: u32, height: u32) -> (i64, i64) {
+    (
+        (width as i64 - image.width() as i64) / 2,
+        (height as i64 - image.height() as i64) / 2,
+    )
+}
@@ -1,5 +1,5 @@
-use crate::structs::{canvas::Canvas, color::Color};
-use image::RgbImage;
+use crate::structs::{blend_mode::BlendMode, canvas::Canvas, color::Color};
+use image::RgbaImage;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -9,6 +9,19 @@ pub struct Layer {
     pub is_locked: bool,
     pub canvas: Canvas,
     pub is_visible: bool,
+    pub opacity: f32, // 0.0 - 1.0, multiplied with every pixel's alpha when compositing
+    pub blend_mode: BlendMode,
+}
+
+/// Everything about a layer except its id and pixels.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerProperties {
+    pub title: String,
+    pub is_locked: bool,
+    pub is_visible: bool,
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    pub background_color: Color,
 }
 
 impl Layer {
@@ -25,11 +38,13 @@ impl Layer {
             is_locked: false,
             canvas: Canvas::new(width, height, background_color),
             is_visible: true,
+            opacity: 1.0,
+            blend_mode: BlendMode::Normal,
         }
     }
 
     pub fn from_image(
-        image: &RgbImage,
+        image: &RgbaImage,
         id: usize,
         title: impl Into<String>,
         background_color: Color,
@@ -40,29 +55,37 @@ impl Layer {
             is_locked: false,
             canvas: Canvas::from_image(image, background_color),
             is_visible: true,
+            opacity: 1.0,
+            blend_mode: BlendMode::Normal,
+        }
+    }
+
+    pub fn properties(&self) -> LayerProperties {
+        LayerProperties {
+            title: self.title.clone(),
+            is_locked: self.is_locked,
+            is_visible: self.is_visible,
+            opacity: self.opacity,
+            blend_mode: self.blend_mode,
+            background_color: self.canvas.background_color,
         }
     }
 
+    pub fn set_properties(&mut self, properties: LayerProperties) {
+        self.title = properties.title;
+        self.is_locked = properties.is_locked;
+        self.is_visible = properties.is_visible;
+        self.opacity = properties.opacity;
+        self.blend_mode = properties.blend_mode;
+        self.canvas.background_color = properties.background_color;
+    }
+
     pub fn resize_canvas(&mut self, new_width: u32, new_height: u32) {
         self.canvas.resize(new_width, new_height);
     }
 
     pub fn to_rgba(&self) -> (Vec<u8>, u32, u32) {
         let canvas = &self.canvas;
-        let width = canvas.width;
-        let height = canvas.height;
-
-        let mut out = vec![0u8; (width * height * 4) as usize];
-
-        for pixel in &canvas.content {
-            let i = ((pixel.y * width + pixel.x) * 4) as usize;
-
-            out[i] = pixel.color.r;
-            out[i + 1] = pixel.color.g;
-            out[i + 2] = pixel.color.b;
-            out[i + 3] = (pixel.color.alpha * 255.0) as u8;
-        }
-
-        (out, width, height)
+        (canvas.as_rgba().to_vec(), canvas.width, canvas.height)
     }
 }
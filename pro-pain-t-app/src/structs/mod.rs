@@ -1,6 +1,10 @@
+pub mod blend_mode;
 pub mod canvas;
 pub mod color;
 pub mod history;
+pub mod image_placement;
 pub mod layer;
 pub mod pixel;
 pub mod project;
+pub mod project_file;
+pub mod stroke_patch;
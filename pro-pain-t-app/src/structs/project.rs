@@ -1,5 +1,12 @@
-use crate::structs::{color::Color, history::History, layer::Layer};
-use leptos::prelude::{Get, RwSignal, Set, Update};
+use crate::structs::{
+    color::Color,
+    history::{History, HistoryOperation, DEFAULT_MEMORY_BUDGET},
+    image_placement::ImagePlacement,
+    layer::{Layer, LayerProperties},
+    project_file,
+};
+use image::RgbaImage;
+use leptos::prelude::{Get, GetUntracked, GetValue, RwSignal, Set, SetValue, StoredValue, Update, UpdateValue, With, WithValue};
 use serde::{Deserialize, Serialize};
 use crate::events::error::show_error_dialog;
 
@@ -10,9 +17,13 @@ pub struct Project {
     pub height: RwSignal<u32>,
     pub background_color: Color,
     pub layers: RwSignal<Vec<Layer>>,
-    pub history: History,
+    /// Undo log, not serialized with the project.
+    #[serde(skip, default = "History::new_stored")]
+    pub history: StoredValue<History>,
     pub current_color: RwSignal<Color>,
     pub next_layer_id: RwSignal<usize>, // best approach for serializing ids
+    pub file_path: Option<String>, // `.ppp` file the project was opened from or last saved to
+    pub is_dirty: bool,            // Differs from what was last opened or saved, see `History::is_saved`
 }
 
 impl Project {
@@ -27,14 +38,23 @@ impl Project {
                 "Layer 0".to_string(),
                 width,
                 height,
-                background_color,
+                Color::transparent(),
             )]),
             current_color: RwSignal::new(Color::default_black()),
-            history: History::new(10),
+            history: History::new_stored(),
             next_layer_id: RwSignal::new(1),
+            file_path: None,
+            is_dirty: false,
         }
     }
 
+    /// A project sized to `image`, with the image as its only layer.
+    pub fn from_image(name: String, image: &RgbaImage) -> Self {
+        let project = Self::new(name, image.width(), image.height(), Color::default_white());
+        project.layers.set(vec![Layer::from_image(image, 0, "Layer 0", Color::transparent())]);
+        project
+    }
+
     pub fn default() -> Self {
         Self::new(
             "Unnamed project".to_string(),
@@ -44,13 +64,17 @@ impl Project {
         )
     }
 
-    pub fn from_file_data(data: Vec<u8>) -> Self {
-        let result = ron::de::from_bytes(&*data);
-        if result.is_err() {
-            show_error_dialog("Failed to deserialize project".to_string());
-            return Self::default();
+    /// Loads a `.ppp` file, migrating legacy RON projects to the current format.
+    /// Shows an error dialog and returns `None` if the file can't be read.
+    pub fn from_file_data(data: Vec<u8>, path: String) -> Option<Self> {
+        let result = project_file::read_project(&data);
+        if let Err(message) = result {
+            show_error_dialog(message);
+            return None;
         }
-        result.unwrap()
+        let mut project = result.unwrap();
+        project.file_path = Some(path);
+        Some(project)
     }
 
     pub fn replace_project_with_blank(
@@ -69,10 +93,12 @@ impl Project {
             "Layer 0".to_string(),
             width,
             height,
-            background_color,
+            Color::transparent(),
         )]);
-        self.history = History::new(10);
+        self.history.set_value(History::new(DEFAULT_MEMORY_BUDGET));
         self.next_layer_id.set(1);
+        self.file_path = None;
+        self.is_dirty = false;
     }
 
     pub fn replace_project_with(&mut self, new_project: Project) {
@@ -81,22 +107,154 @@ impl Project {
         self.height.set(new_project.height.get());
         self.background_color = new_project.background_color;
         self.layers.set(new_project.layers.get());
-        self.history = new_project.history;
+        self.history.set_value(new_project.history.get_value());
         self.next_layer_id.set(new_project.next_layer_id.get());
+        self.file_path = new_project.file_path;
+        self.is_dirty = new_project.is_dirty;
+    }
+
+    /// Adds an edit to the undo history and marks the project as changed.
+    pub fn record(&mut self, operation: impl Into<HistoryOperation>) {
+        self.history.update_value(|history| history.add(operation));
+        self.is_dirty = !self.history.with_value(History::is_saved);
+    }
+
+    pub fn mark_saved(&mut self, path: String) {
+        self.file_path = Some(path);
+        self.history.update_value(History::mark_saved);
+        self.is_dirty = false;
     }
 
-    pub fn add_new_layer(&self, layer: Layer) {
+    pub fn add_new_layer(&mut self, layer: Layer) {
+        let index = self.layers.with(|layers| layers.len());
         self.layers.update(|layers| {
-            layers.push(layer);
+            layers.push(layer.clone());
         });
         self.next_layer_id.set(self.next_layer_id.get() + 1);
+        self.record(HistoryOperation::AddLayer { index, layer });
+    }
+
+    /// Adds `image` as a new layer laid out according to `placement`.
+    pub fn import_image(&mut self, image: &RgbaImage, placement: ImagePlacement) {
+        if placement == ImagePlacement::ResizeCanvas {
+            self.resize_canvas(image.width(), image.height());
+        }
+
+        let placed = placement.place(image, self.width.get_untracked(), self.height.get_untracked());
+        let layer_id = self.next_layer_id.get_untracked();
+        let layer = Layer::from_image(&placed, layer_id, "Imported image", Color::transparent());
+        self.add_new_layer(layer);
+    }
+
+    fn layer_index(&self, layer_id: usize) -> Option<usize> {
+        self.layers.with(|layers| layers.iter().position(|l| l.id == layer_id))
+    }
+
+    /// Inserts a copy of the layer directly above it.
+    pub fn duplicate_layer(&mut self, layer_id: usize) {
+        let Some(index) = self.layer_index(layer_id) else { return };
+
+        let mut layer = self.layers.with(|layers| layers[index].clone());
+        layer.id = self.next_layer_id.get_untracked();
+        layer.title = format!("{} (Copy)", layer.title);
+
+        self.layers.update(|layers| layers.insert(index + 1, layer.clone()));
+        self.next_layer_id.set(layer.id + 1);
+        self.record(HistoryOperation::AddLayer { index: index + 1, layer });
+    }
+
+    pub fn remove_layer(&mut self, layer_id: usize) {
+        let Some(index) = self.layer_index(layer_id) else { return };
+
+        let mut removed = None;
+        self.layers.update(|layers| removed = Some(layers.remove(index)));
+        if let Some(layer) = removed {
+            self.record(HistoryOperation::RemoveLayer { index, layer });
+        }
+    }
+
+    /// Moves the layer by `offset` positions in the stack, if it stays in bounds.
+    pub fn move_layer(&mut self, layer_id: usize, offset: isize) {
+        let Some(from) = self.layer_index(layer_id) else { return };
+        let Some(to) = from.checked_add_signed(offset) else { return };
+        if to >= self.layer_count() {
+            return;
+        }
+
+        self.layers.update(|layers| {
+            let layer = layers.remove(from);
+            layers.insert(to, layer);
+        });
+        self.record(HistoryOperation::MoveLayer { from, to });
+    }
+
+    /// Fills the whole layer with its background color.
+    pub fn clear_layer(&mut self, layer_id: usize) {
+        let mut change = None;
+        self.layers.update(|layers| {
+            if let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) {
+                let before = layer.canvas.clone();
+                layer.canvas.clear();
+                change = Some((before, layer.canvas.clone()));
+            }
+        });
+
+        if let Some((before, after)) = change {
+            self.record(HistoryOperation::ReplaceCanvas { layer_id, before, after });
+        }
+    }
+
+    /// Applies `properties` to the layer. Nothing is recorded if they didn't change.
+    pub fn set_layer_properties(&mut self, layer_id: usize, properties: LayerProperties) {
+        let Some(index) = self.layer_index(layer_id) else { return };
+
+        let before = self.layers.with(|layers| layers[index].properties());
+        self.record_layer_properties(layer_id, before, properties.clone());
+        self.layers.update(|layers| layers[index].set_properties(properties));
+    }
+
+    /// Records a property change that was already applied to the layer, e.g.
+    /// while an opacity slider was being dragged.
+    pub fn record_layer_properties(
+        &mut self,
+        layer_id: usize,
+        before: LayerProperties,
+        after: LayerProperties,
+    ) {
+        if before != after {
+            self.record(HistoryOperation::LayerProperties { layer_id, before, after });
+        }
+    }
+
+    /// Resizes every layer, cropping or extending it with its background color.
+    pub fn resize_canvas(&mut self, width: u32, height: u32) {
+        let before = (self.width.get_untracked(), self.height.get_untracked());
+        if before == (width, height) {
+            return;
+        }
+
+        let mut canvases_before = Vec::new();
+        self.layers.update(|layers| {
+            for layer in layers.iter_mut() {
+                canvases_before.push((layer.id, layer.canvas.clone()));
+                layer.resize_canvas(width, height);
+            }
+        });
+        self.width.set(width);
+        self.height.set(height);
+
+        self.record(HistoryOperation::ResizeCanvas {
+            before,
+            after: (width, height),
+            canvases_before,
+        });
     }
 
     pub fn layer_count(&self) -> usize {
         self.layers.get().len()
     }
 
-    pub fn serialize(&self) -> String {
-        ron::ser::to_string(self).expect("Couldn't serialize project")
+    pub fn serialize(&self) -> Vec<u8> {
+        project_file::write_project(self)
     }
 }
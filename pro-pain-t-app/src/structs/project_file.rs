@@ -0,0 +1,541 @@
+//! Binary `.ppp` project container.
+//!
+//! Layout (all integers are little-endian):
+//! - magic bytes `PPPT`
+//! - format version (`u16`)
+//! - body, see `write_body` / `read_body`
+//!
+//! Files that do not start with the magic bytes are treated as legacy RON
+//! projects and migrated on load.
+
+use crate::structs::{blend_mode::BlendMode, canvas::Canvas, color::Color, history::History, layer::Layer, project::Project};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use leptos::prelude::{Get, RwSignal};
+use serde::Deserialize;
+use std::io::{Read, Write};
+
+pub const MAGIC: &[u8; 4] = b"PPPT";
+pub const VERSION_1: u16 = 1;
+/// Adds per-layer opacity and blend mode.
+pub const VERSION_2: u16 = 2;
+pub const CURRENT_VERSION: u16 = VERSION_2;
+
+const LAYER_FLAG_VISIBLE: u8 = 0b01;
+const LAYER_FLAG_LOCKED: u8 = 0b10;
+
+/// Largest layer width or height a file may declare.
+const MAX_DIMENSION: u32 = 1 << 15;
+
+pub fn write_project(project: &Project) -> Vec<u8> {
+    let mut writer = ByteWriter::default();
+    writer.bytes(MAGIC);
+    writer.u16(CURRENT_VERSION);
+    write_body(&mut writer, project);
+    writer.into_inner()
+}
+
+pub fn read_project(data: &[u8]) -> Result<Project, String> {
+    if !data.starts_with(MAGIC) {
+        return read_legacy_ron(data);
+    }
+
+    let mut reader = ByteReader::new(&data[MAGIC.len()..]);
+    match reader.u16()? {
+        version @ (VERSION_1 | VERSION_2) => read_body(&mut reader, version),
+        version => Err(format!("Unsupported project file version {}", version)),
+    }
+}
+
+/// Project header followed by every layer with its pixels stored as four zlib
+/// compressed planes (all reds, all greens, all blues, all alphas).
+/// Undo history is not persisted. Always writes `CURRENT_VERSION`.
+fn write_body(writer: &mut ByteWriter, project: &Project) {
+    writer.string(&project.name);
+    writer.u32(project.width.get());
+    writer.u32(project.height.get());
+    writer.color(project.background_color);
+    writer.color(project.current_color.get());
+    writer.u32(project.next_layer_id.get() as u32);
+
+    let layers = project.layers.get();
+    writer.u32(layers.len() as u32);
+    for layer in &layers {
+        writer.u32(layer.id as u32);
+        writer.string(&layer.title);
+
+        let mut flags = 0;
+        if layer.is_visible {
+            flags |= LAYER_FLAG_VISIBLE;
+        }
+        if layer.is_locked {
+            flags |= LAYER_FLAG_LOCKED;
+        }
+        writer.u8(flags);
+        writer.u8((layer.opacity.clamp(0.0, 1.0) * 255.0).round() as u8);
+        writer.u8(layer.blend_mode.id());
+
+        writer.color(layer.canvas.background_color);
+        writer.u32(layer.canvas.width);
+        writer.u32(layer.canvas.height);
+
+        let planes = interleaved_to_planes(layer.canvas.as_rgba());
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&planes)
+            .expect("writing into an in-memory buffer can't fail");
+        let compressed = encoder
+            .finish()
+            .expect("writing into an in-memory buffer can't fail");
+        writer.u32(compressed.len() as u32);
+        writer.bytes(&compressed);
+    }
+}
+
+/// Reads a body written by `write_body` of the given format version.
+fn read_body(reader: &mut ByteReader, version: u16) -> Result<Project, String> {
+    let name = reader.string()?;
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let background_color = reader.color()?;
+    let current_color = reader.color()?;
+    let next_layer_id = reader.u32()? as usize;
+
+    // Counts and sizes come from the file, nothing is allocated up front from them
+    let layer_count = reader.u32()?;
+    let mut layers = Vec::new();
+    for _ in 0..layer_count {
+        let id = reader.u32()? as usize;
+        let title = reader.string()?;
+        let flags = reader.u8()?;
+        let (opacity, blend_mode) = if version >= VERSION_2 {
+            let opacity = reader.u8()? as f32 / 255.0;
+            let blend_mode = BlendMode::from_id(reader.u8()?).ok_or("Unknown layer blend mode")?;
+            (opacity, blend_mode)
+        } else {
+            (1.0, BlendMode::Normal)
+        };
+        let layer_background = reader.color()?;
+        let layer_width = reader.u32()?;
+        let layer_height = reader.u32()?;
+
+        let expected_len = rgba_len(layer_width, layer_height)?;
+
+        let compressed_len = reader.u32()? as usize;
+        let compressed = reader.bytes(compressed_len)?;
+        // One byte past the expected size is enough to tell the data is too long
+        let mut planes = Vec::new();
+        ZlibDecoder::new(compressed)
+            .take(expected_len as u64 + 1)
+            .read_to_end(&mut planes)
+            .map_err(|_| "Corrupted layer pixel data".to_string())?;
+        if planes.len() != expected_len {
+            return Err("Layer pixel data doesn't match its dimensions".to_string());
+        }
+
+        let rgba = planes_to_interleaved(&planes);
+        layers.push(Layer {
+            id,
+            title,
+            is_locked: flags & LAYER_FLAG_LOCKED != 0,
+            canvas: Canvas::from_rgba(layer_width, layer_height, rgba, layer_background),
+            is_visible: flags & LAYER_FLAG_VISIBLE != 0,
+            opacity,
+            blend_mode,
+        });
+    }
+
+    Ok(Project {
+        name,
+        width: RwSignal::new(width),
+        height: RwSignal::new(height),
+        background_color,
+        layers: RwSignal::new(layers),
+        history: History::new_stored(),
+        current_color: RwSignal::new(current_color),
+        next_layer_id: RwSignal::new(next_layer_id),
+        file_path: None,
+        is_dirty: false,
+    })
+}
+
+fn interleaved_to_planes(rgba: &[u8]) -> Vec<u8> {
+    let pixel_count = rgba.len() / 4;
+    let mut planes = vec![0u8; pixel_count * 4];
+    for (i, px) in rgba.chunks_exact(4).enumerate() {
+        for channel in 0..4 {
+            planes[channel * pixel_count + i] = px[channel];
+        }
+    }
+    planes
+}
+
+fn planes_to_interleaved(planes: &[u8]) -> Vec<u8> {
+    let pixel_count = planes.len() / 4;
+    let mut rgba = vec![0u8; pixel_count * 4];
+    for (i, px) in rgba.chunks_exact_mut(4).enumerate() {
+        for channel in 0..4 {
+            px[channel] = planes[channel * pixel_count + i];
+        }
+    }
+    rgba
+}
+
+// ===== Legacy RON projects =====
+// Mirrors of the structs as they were serialized before the binary container
+// existed, kept separate so the in-memory types are free to change.
+
+#[derive(Deserialize)]
+struct LegacyProject {
+    name: String,
+    width: u32,
+    height: u32,
+    background_color: Color,
+    layers: Vec<LegacyLayer>,
+    current_color: Color,
+    next_layer_id: usize,
+}
+
+#[derive(Deserialize)]
+struct LegacyLayer {
+    id: usize,
+    title: String,
+    is_locked: bool,
+    canvas: LegacyCanvas,
+    is_visible: bool,
+}
+
+#[derive(Deserialize)]
+struct LegacyCanvas {
+    width: u32,
+    height: u32,
+    content: Vec<LegacyPixel>,
+    background_color: Color,
+}
+
+#[derive(Deserialize)]
+struct LegacyPixel {
+    x: u32,
+    y: u32,
+    color: Color,
+}
+
+/// Bytes of RGBA pixels in a `width` × `height` layer, checked before anything
+/// is allocated for it.
+fn rgba_len(width: u32, height: u32) -> Result<usize, String> {
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err("Layer dimensions too large".to_string());
+    }
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(4))
+        .ok_or_else(|| "Layer dimensions too large".to_string())
+}
+
+fn read_legacy_ron(data: &[u8]) -> Result<Project, String> {
+    let legacy: LegacyProject =
+        ron::de::from_bytes(data).map_err(|_| "Failed to deserialize project".to_string())?;
+
+    let layers = legacy
+        .layers
+        .into_iter()
+        .map(|legacy_layer| {
+            let canvas = legacy_layer.canvas;
+            let mut rgba = vec![0u8; rgba_len(canvas.width, canvas.height)?];
+            for pixel in canvas.content {
+                if pixel.x >= canvas.width || pixel.y >= canvas.height {
+                    continue;
+                }
+                let i = (pixel.y as usize * canvas.width as usize + pixel.x as usize) * 4;
+                rgba[i..i + 4].copy_from_slice(&pixel.color.to_rgba8());
+            }
+
+            Ok(Layer {
+                id: legacy_layer.id,
+                title: legacy_layer.title,
+                is_locked: legacy_layer.is_locked,
+                canvas: Canvas::from_rgba(canvas.width, canvas.height, rgba, canvas.background_color),
+                is_visible: legacy_layer.is_visible,
+                opacity: 1.0,
+                blend_mode: BlendMode::Normal,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Project {
+        name: legacy.name,
+        width: RwSignal::new(legacy.width),
+        height: RwSignal::new(legacy.height),
+        background_color: legacy.background_color,
+        layers: RwSignal::new(layers),
+        history: History::new_stored(),
+        current_color: RwSignal::new(legacy.current_color),
+        next_layer_id: RwSignal::new(legacy.next_layer_id),
+        file_path: None,
+        is_dirty: false,
+    })
+}
+
+// ===== Little-endian primitives =====
+
+#[derive(Default)]
+struct ByteWriter {
+    buf: Vec<u8>,
+}
+
+impl ByteWriter {
+    fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes(value.as_bytes());
+    }
+
+    fn color(&mut self, color: Color) {
+        self.bytes(&color.to_rgba8());
+    }
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("Unexpected end of project file")?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.bytes(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| "Invalid UTF-8 in project file".to_string())
+    }
+
+    fn color(&mut self) -> Result<Color, String> {
+        Ok(Color::from_rgba8(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use leptos::prelude::{Set, Update, With};
+
+    const V1_PIXELS: [u8; 8] = [255, 0, 0, 255, 0, 0, 255, 128];
+
+    fn layer_rgba(project: &Project, index: usize) -> Vec<u8> {
+        project.layers.with(|layers| layers[index].canvas.as_rgba().to_vec())
+    }
+
+    /// A `VERSION_1` file with one layer `layer_width` pixels wide and one
+    /// high, written the way the old writer did.
+    fn version_1_file(layer_width: u32, rgba: &[u8]) -> Vec<u8> {
+        let mut writer = ByteWriter::default();
+        writer.bytes(MAGIC);
+        writer.u16(VERSION_1);
+        writer.string("Old");
+        writer.u32(layer_width);
+        writer.u32(1);
+        writer.color(Color::default_white());
+        writer.color(Color::default_black());
+        writer.u32(1);
+
+        writer.u32(1);
+        writer.u32(0);
+        writer.string("Layer 0");
+        writer.u8(LAYER_FLAG_VISIBLE);
+        writer.color(Color::transparent());
+        writer.u32(layer_width);
+        writer.u32(1);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&interleaved_to_planes(rgba)).unwrap();
+        let compressed = encoder.finish().unwrap();
+        writer.u32(compressed.len() as u32);
+        writer.bytes(&compressed);
+        writer.into_inner()
+    }
+
+    #[test]
+    fn version_2_round_trips() {
+        let project = Project::new("Round trip".to_string(), 3, 2, Color::new(10, 20, 30, 1.0));
+        project.current_color.set(Color::new(1, 2, 3, 1.0));
+        project.layers.update(|layers| {
+            let layer = &mut layers[0];
+            let _ = layer.canvas.set_rgba(2, 1, [200, 100, 50, 255]);
+            layer.title = "Ink".to_string();
+            layer.is_locked = true;
+            layer.opacity = 128.0 / 255.0;
+            layer.blend_mode = BlendMode::Multiply;
+        });
+
+        let read = read_project(&write_project(&project)).unwrap();
+
+        assert_eq!(read.name, "Round trip");
+        assert_eq!((read.width.get(), read.height.get()), (3, 2));
+        assert_eq!(read.background_color, project.background_color);
+        assert_eq!(read.current_color.get(), Color::new(1, 2, 3, 1.0));
+        assert_eq!(read.next_layer_id.get(), project.next_layer_id.get());
+        read.layers.with(|layers| {
+            assert_eq!(layers.len(), 1);
+            let layer = &layers[0];
+            assert_eq!((layer.id, layer.title.as_str()), (0, "Ink"));
+            assert!(layer.is_locked && layer.is_visible);
+            assert_eq!(layer.opacity, 128.0 / 255.0);
+            assert_eq!(layer.blend_mode, BlendMode::Multiply);
+        });
+        assert_eq!(layer_rgba(&read, 0), layer_rgba(&project, 0));
+    }
+
+    #[test]
+    fn version_1_layers_get_full_opacity_and_normal_blending() {
+        let read = read_project(&version_1_file(2, &V1_PIXELS)).unwrap();
+
+        assert_eq!(read.name, "Old");
+        read.layers.with(|layers| {
+            assert_eq!(layers.len(), 1);
+            assert_eq!(layers[0].opacity, 1.0);
+            assert_eq!(layers[0].blend_mode, BlendMode::Normal);
+            assert!(layers[0].is_visible && !layers[0].is_locked);
+        });
+        assert_eq!(layer_rgba(&read, 0), V1_PIXELS);
+    }
+
+    #[test]
+    fn legacy_ron_projects_are_migrated() {
+        let ron = r#"(
+            name: "Legacy",
+            width: 2,
+            height: 2,
+            background_color: (r: 255, g: 255, b: 255, alpha: 1.0),
+            layers: [(
+                id: 0,
+                title: "Layer 0",
+                is_locked: false,
+                canvas: (
+                    width: 2,
+                    height: 2,
+                    content: [
+                        (x: 1, y: 0, color: (r: 255, g: 0, b: 0, alpha: 1.0)),
+                        (x: 5, y: 5, color: (r: 0, g: 255, b: 0, alpha: 1.0)),
+                    ],
+                    background_color: (r: 0, g: 0, b: 0, alpha: 0.0),
+                ),
+                is_visible: true,
+            )],
+            current_color: (r: 0, g: 0, b: 0, alpha: 1.0),
+            next_layer_id: 1,
+        )"#;
+
+        let read = read_project(ron.as_bytes()).unwrap();
+
+        assert_eq!(read.name, "Legacy");
+        assert_eq!((read.width.get(), read.height.get()), (2, 2));
+        read.layers.with(|layers| {
+            assert_eq!(layers[0].opacity, 1.0);
+            assert_eq!(layers[0].blend_mode, BlendMode::Normal);
+        });
+        // The pixel outside the canvas is dropped
+        let mut expected = vec![0; 16];
+        expected[4..8].copy_from_slice(&[255, 0, 0, 255]);
+        assert_eq!(layer_rgba(&read, 0), expected);
+    }
+
+    #[test]
+    fn oversized_legacy_layers_are_rejected() {
+        let ron = r#"(
+            name: "Huge",
+            width: 4000000000,
+            height: 4000000000,
+            background_color: (r: 255, g: 255, b: 255, alpha: 1.0),
+            layers: [(
+                id: 0,
+                title: "Layer 0",
+                is_locked: false,
+                canvas: (
+                    width: 4000000000,
+                    height: 4000000000,
+                    content: [],
+                    background_color: (r: 0, g: 0, b: 0, alpha: 0.0),
+                ),
+                is_visible: true,
+            )],
+            current_color: (r: 0, g: 0, b: 0, alpha: 1.0),
+            next_layer_id: 1,
+        )"#;
+
+        assert!(read_project(ron.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let data = write_project(&Project::new("Cut".to_string(), 4, 4, Color::default_white()));
+        for len in [MAGIC.len() + 1, MAGIC.len() + 10, data.len() / 2, data.len() - 1] {
+            assert!(read_project(&data[..len]).is_err(), "accepted {} of {} bytes", len, data.len());
+        }
+
+        let data = version_1_file(2, &V1_PIXELS);
+        assert!(read_project(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let mut data = write_project(&Project::default());
+        data[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&99u16.to_le_bytes());
+
+        let Err(message) = read_project(&data) else { panic!("accepted version 99") };
+        assert!(message.contains("99"));
+    }
+
+    #[test]
+    fn pixel_data_not_matching_the_layer_size_is_rejected() {
+        // Planes for two pixels stored in a layer declared one pixel wide
+        assert!(read_project(&version_1_file(1, &V1_PIXELS)).is_err());
+        assert!(read_project(&version_1_file(3, &V1_PIXELS)).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::render::dirty_region::DirtyRect;
+use crate::structs::{
+    canvas::Canvas,
+    history::{PixelDiff, StrokeDiff},
+};
+use serde::{Deserialize, Serialize};
+
+/// Compact history form of a `StrokeDiff`.
+///
+/// Only the bounding rect of the stroke is kept, as two RGBA tiles (before and
+/// after) that are run-length encoded. Pixels inside the rect that the stroke
+/// didn't touch are zero in both tiles, so they compress to long runs and are
+/// skipped when the patch is applied: only pixels whose before and after differ
+/// are ever written back.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StrokePatch {
+    pub layer_id: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    before: Vec<u8>,
+    after: Vec<u8>,
+}
+
+impl StrokePatch {
+    /// Packs the diff. When a stroke touched a pixel more than once, the first
+    /// `before` and the last `after` win.
+    ///
+    /// The runs are written straight from the diffs in row order, so memory
+    /// grows with the stroke rather than with its bounding rect.
+    pub fn from_diff(diff: &StrokeDiff) -> Self {
+        let rect = DirtyRect::from_diffs(&diff.pixels).unwrap_or(DirtyRect::new(0, 0, 0, 0));
+        let index = |x: u32, y: u32| (y - rect.y) as u64 * rect.w as u64 + (x - rect.x) as u64;
+
+        // The sort is stable, so the diffs of one pixel keep the stroke's order
+        let mut pixels: Vec<&PixelDiff> = diff.pixels.iter().collect();
+        pixels.sort_by_key(|pixel_diff| (pixel_diff.after.y, pixel_diff.after.x));
+
+        let mut before = RleWriter::default();
+        let mut after = RleWriter::default();
+        // Untouched pixels between the written ones are zero in both tiles
+        let mut next = 0;
+        for same_pixel in pixels.chunk_by(|a, b| (a.after.x, a.after.y) == (b.after.x, b.after.y)) {
+            let (first, last) = (same_pixel[0], same_pixel[same_pixel.len() - 1]);
+            let i = index(first.after.x, first.after.y);
+            before.push([0; 4], i - next);
+            after.push([0; 4], i - next);
+            before.push(first.before.color.to_rgba8(), 1);
+            after.push(last.after.color.to_rgba8(), 1);
+            next = i + 1;
+        }
+        let len = rect.w as u64 * rect.h as u64;
+        before.push([0; 4], len - next);
+        after.push([0; 4], len - next);
+
+        Self {
+            layer_id: diff.layer_id,
+            x: rect.x,
+            y: rect.y,
+            width: rect.w,
+            height: rect.h,
+            before: before.finish(),
+            after: after.finish(),
+        }
+    }
+
+    /// Area of the document the patch covers, `None` if it's empty.
+    pub fn bounds(&self) -> Option<DirtyRect> {
+        (!self.is_empty()).then(|| DirtyRect::new(self.x, self.y, self.width, self.height))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Heap bytes held by the patch.
+    pub fn byte_size(&self) -> usize {
+        self.before.len() + self.after.len()
+    }
+
+    /// Writes the `before` state of every changed pixel into `canvas`.
+    pub fn revert(&self, canvas: &mut Canvas) {
+        self.write(canvas, true);
+    }
+
+    /// Writes the `after` state of every changed pixel into `canvas`.
+    pub fn apply(&self, canvas: &mut Canvas) {
+        self.write(canvas, false);
+    }
+
+    /// Walks both tiles run by run, so the untouched stretches between the
+    /// changed pixels are skipped without being decoded.
+    fn write(&self, canvas: &mut Canvas, use_before: bool) {
+        let len = self.width as u64 * self.height as u64;
+        let mut before = RleReader::new(&self.before, len);
+        let mut after = RleReader::new(&self.after, len);
+        let mut i = 0;
+
+        while let (Some((b, b_count)), Some((a, a_count))) = (before.peek(), after.peek()) {
+            let count = b_count.min(a_count);
+            if b != a {
+                let rgba = if use_before { b } else { a };
+                for i in i..i + count {
+                    let x = self.x + (i % self.width as u64) as u32;
+                    let y = self.y + (i / self.width as u64) as u32;
+                    let _ = canvas.set_rgba(x, y, rgba);
+                }
+            }
+            before.advance(count);
+            after.advance(count);
+            i += count;
+        }
+    }
+}
+
+/// Encodes RGBA pixels as runs of `(count as LEB128, r, g, b, a)`, merging
+/// consecutive pushes of the same color.
+#[derive(Default)]
+struct RleWriter {
+    out: Vec<u8>,
+    run: Option<([u8; 4], u64)>,
+}
+
+impl RleWriter {
+    fn push(&mut self, pixel: [u8; 4], count: u64) {
+        if count == 0 {
+            return;
+        }
+        match &mut self.run {
+            Some((color, run)) if *color == pixel => *run += count,
+            _ => {
+                self.flush();
+                self.run = Some((pixel, count));
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        let Some((pixel, mut count)) = self.run.take() else { return };
+        while count >= 0x80 {
+            self.out.push((count as u8 & 0x7f) | 0x80);
+            count >>= 7;
+        }
+        self.out.push(count as u8);
+        self.out.extend_from_slice(&pixel);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.flush();
+        self.out.shrink_to_fit();
+        self.out
+    }
+}
+
+/// Reads back the runs of an `RleWriter`. It always yields exactly `len`
+/// pixels, padding with zeros, so a damaged stream can't make a patch write
+/// outside its rect.
+struct RleReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Pixels not yet consumed.
+    remaining: u64,
+    run: Option<([u8; 4], u64)>,
+}
+
+impl<'a> RleReader<'a> {
+    fn new(data: &'a [u8], len: u64) -> Self {
+        Self { data, pos: 0, remaining: len, run: None }
+    }
+
+    /// The current color and how many more pixels have it.
+    fn peek(&mut self) -> Option<([u8; 4], u64)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        while self.run.is_none_or(|(_, count)| count == 0) {
+            self.run = Some(self.read_run().unwrap_or(([0; 4], self.remaining)));
+        }
+        self.run.map(|(pixel, count)| (pixel, count.min(self.remaining)))
+    }
+
+    fn advance(&mut self, count: u64) {
+        self.remaining -= count;
+        if let Some((_, run)) = &mut self.run {
+            *run -= count;
+        }
+    }
+
+    fn read_run(&mut self) -> Option<([u8; 4], u64)> {
+        let mut count: u64 = 0;
+        let mut shift = 0;
+        while self.pos < self.data.len() {
+            let byte = self.data[self.pos];
+            self.pos += 1;
+            count |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 || shift >= 64 {
+                break;
+            }
+        }
+
+        let pixel = self.data.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        Some(([pixel[0], pixel[1], pixel[2], pixel[3]], count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{color::Color, pixel::Pixel};
+
+    fn diff(x: u32, y: u32, before: Color, after: Color) -> PixelDiff {
+        PixelDiff { before: Pixel::new(x, y, before), after: Pixel::new(x, y, after) }
+    }
+
+    #[test]
+    fn revert_and_apply_keep_the_first_before_and_the_last_after() {
+        let (white, red, blue) = (Color::default_white(), Color::new(255, 0, 0, 1.0), Color::new(0, 0, 255, 1.0));
+        // Out of row order, with (3, 2) painted twice
+        let patch = StrokePatch::from_diff(&StrokeDiff {
+            layer_id: 0,
+            pixels: vec![
+                diff(3, 2, white, red),
+                diff(1, 0, white, red),
+                diff(3, 2, red, blue),
+                diff(0, 2, white, blue),
+            ],
+        });
+        assert_eq!((patch.x, patch.y, patch.width, patch.height), (0, 0, 4, 3));
+
+        let mut canvas = Canvas::new(5, 3, white);
+        patch.apply(&mut canvas);
+        let colors = |canvas: &Canvas| [(1, 0), (3, 2), (0, 2), (2, 1)].map(|(x, y)| canvas.get_pixel(x, y).unwrap().color);
+        assert_eq!(colors(&canvas), [red, blue, blue, white]);
+
+        patch.revert(&mut canvas);
+        assert_eq!(colors(&canvas), [white; 4]);
+    }
+}
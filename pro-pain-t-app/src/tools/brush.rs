@@ -1,55 +1,50 @@
-use crate::structs::history::{PixelDiff, StrokeDiff};
-use crate::structs::pixel::Pixel;
+use crate::state::workspace_state::WorkspaceState;
+use crate::structs::history::StrokeDiff;
+use crate::structs::project::Project;
+use crate::tools::brush_engine::{BrushStroke, StrokePoint};
 use crate::tools::context::ToolContext;
-use crate::tools::geometry::{draw_line, screen_to_canvas};
-use leptos::prelude::{Get, Update};
+use crate::tools::geometry::screen_to_canvas_f;
+use leptos::prelude::{Get, GetUntracked, RwSignal, Update, WithUntracked};
 use serde::{Deserialize, Serialize};
 use web_sys::PointerEvent;
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct BrushState {
-    is_drawing: bool,
-    last_pos: Option<(i32, i32)>,
-    current_stroke: Vec<PixelDiff>,
+    /// Layer the stroke in progress paints on, `None` between strokes.
+    layer_id: Option<usize>,
+    stroke: BrushStroke,
 }
 
 impl BrushState {
     pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        self.is_drawing = true;
-        self.last_pos = None;
-        self.current_stroke.clear();
+        self.layer_id = ctx.selected_layer;
+        self.stroke.clear();
         self.apply_at(e, ctx);
     }
 
     pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        if !self.is_drawing {
-            return;
-        }
         self.apply_at(e, ctx);
     }
 
     pub fn on_pointer_up(&mut self, _e: &PointerEvent, ctx: &ToolContext) {
-        self.is_drawing = false;
-        self.last_pos = None;
+        let Some(layer_id) = self.layer_id.take() else { return; };
 
-        if self.current_stroke.is_empty() {
+        if self.stroke.is_empty() {
             return;
         }
 
-        let Some(layer_id) = ctx.selected_layer else { return; };
-
         ctx.project.update(|project| {
-            project.history.add(StrokeDiff {
+            project.record(StrokeDiff {
                 layer_id,
-                pixels: std::mem::take(&mut self.current_stroke),
+                pixels: self.stroke.take_pixels(),
             });
         });
     }
 
     fn apply_at(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        let Some(layer_id) = ctx.selected_layer else { return; };
+        let Some(layer_id) = self.layer_id else { return; };
 
-        let (x, y) = screen_to_canvas(
+        let (x, y) = screen_to_canvas_f(
             ctx.canvas,
             e.client_x() as f64,
             e.client_y() as f64,
@@ -57,55 +52,35 @@ impl BrushState {
             ctx.pan_x,
             ctx.pan_y,
         );
+        let point = StrokePoint { x, y, pressure: StrokePoint::pressure_of(e) };
 
         ctx.project.get().layers.update(|layers| {
             let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
             if layer.is_locked || !layer.is_visible { return; }
 
             let brush_size = ctx.workspace_state.brush_size.get().max(0.1);
-            let radius = (brush_size * 2.0).ceil() as i32;
+            let settings = ctx.workspace_state.brush_settings.get_untracked();
+            let copies = ctx.workspace_state.symmetry.get_untracked().copies(layer.canvas.width, layer.canvas.height);
             let color = ctx.project.get().current_color.get();
 
-            let canvas = &mut layer.canvas;
-
-            let mut draw_pixel = |px: i32, py: i32| {
-                for dy in -radius..=radius {
-                    for dx in -radius..=radius {
-                        if dx*dx + dy*dy > (radius*radius) { continue; }
-                        let nx = px + dx;
-                        let ny = py + dy;
-                        if nx < 0 || ny < 0 { continue; }
-                        let ux = nx as u32;
-                        let uy = ny as u32;
-                        if let Ok(old_pixel) = canvas.get_pixel(ux, uy) {
-                            if old_pixel.color != color {
-                                let new_pixel = Pixel::new(ux, uy, color);
-                                self.current_stroke.push(PixelDiff {
-                                    before: old_pixel,
-                                    after: new_pixel,
-                                });
-                                let _ = canvas.set_pixel(new_pixel);
-                            }
-                        }
-                    }
-                }
-            };
-
-            let current = (x, y);
-            if let Some((lx, ly)) = self.last_pos {
-                draw_line(lx, ly, x, y, &mut draw_pixel);
-            } else {
-                draw_pixel(x, y);
-            }
-
-            self.last_pos = Some(current);
+            let touched = self.stroke.stroke_to(
+                &mut layer.canvas,
+                point,
+                brush_size,
+                &settings,
+                &copies,
+                &|before, coverage| before.blend_over(color, coverage),
+            );
+            ctx.workspace_state.mark_dirty(&touched);
         });
     }
 
-    pub fn cancel(&mut self) {
-        self.is_drawing = false;
-        self.last_pos = None;
-        self.current_stroke.clear();
+    /// Abandons the stroke, putting back the pixels it painted over.
+    pub fn cancel(&mut self, project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
+        let pixels = self.stroke.take_pixels();
+        let Some(layer_id) = self.layer_id.take() else { return; };
+        workspace_state.mark_dirty(&pixels);
+        project.with_untracked(|project| StrokeDiff { layer_id, pixels }.restore(project));
     }
 
     pub fn cursor(&self) -> &'static str {
@@ -0,0 +1,220 @@
+//! Dab based painting shared by the brush-like tools.
+//!
+//! A stroke stamps round dabs at regular spacing along the pointer path. Each
+//! pixel remembers its color from before the stroke and how much the stroke
+//! has covered it so far, so overlapping dabs build up smoothly instead of
+//! re-blending over their own result.
+use crate::structs::canvas::Canvas;
+use crate::structs::color::Color;
+use crate::structs::history::PixelDiff;
+use crate::structs::pixel::Pixel;
+use crate::tools::symmetry::SymmetryCopy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use web_sys::PointerEvent;
+
+/// Tip radius in pixels per unit of `WorkspaceState::brush_size`.
+pub const RADIUS_PER_SIZE: f32 = 2.0;
+/// Dabs are never closer than this many pixels, whatever the spacing.
+const MIN_DAB_DISTANCE: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub struct BrushSettings {
+    /// Part of the radius painted at full strength, `0.0` is fully soft and
+    /// `1.0` a hard (anti-aliased) edge.
+    pub hardness: f32,
+    /// Distance between dabs as a fraction of the tip diameter.
+    pub spacing: f32,
+    /// Strength of a single dab, overlapping dabs build up to `opacity`.
+    pub flow: f32,
+    /// Most a single stroke can cover a pixel.
+    pub opacity: f32,
+    pub pressure_size: bool,
+    pub pressure_opacity: bool,
+}
+
+impl Default for BrushSettings {
+    fn default() -> Self {
+        Self {
+            hardness: 0.8,
+            spacing: 0.15,
+            flow: 1.0,
+            opacity: 1.0,
+            pressure_size: true,
+            pressure_opacity: false,
+        }
+    }
+}
+
+/// Position and pressure of the pointer in document coordinates.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct StrokePoint {
+    pub x: f32,
+    pub y: f32,
+    pub pressure: f32,
+}
+
+impl StrokePoint {
+    /// Mice report a constant `0.5` while a button is held, treat them as full pressure.
+    pub fn pressure_of(e: &PointerEvent) -> f32 {
+        if e.pointer_type() == "mouse" || e.pressure() <= 0.0 {
+            1.0
+        } else {
+            e.pressure().clamp(0.0, 1.0)
+        }
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct BrushStroke {
+    last: Option<StrokePoint>,
+    /// Path length left before the next dab.
+    until_next_dab: f32,
+    /// Per touched pixel: index into `pixels` and accumulated coverage.
+    #[serde(skip)]
+    coverage: HashMap<(u32, u32), (usize, f32)>,
+    pixels: Vec<PixelDiff>,
+}
+
+impl BrushStroke {
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Diffs of the whole stroke, leaving the stroke empty.
+    pub fn take_pixels(&mut self) -> Vec<PixelDiff> {
+        let pixels = std::mem::take(&mut self.pixels);
+        self.clear();
+        pixels
+    }
+
+    pub fn clear(&mut self) {
+        self.last = None;
+        self.until_next_dab = 0.0;
+        self.coverage.clear();
+        self.pixels.clear();
+    }
+
+    /// Continues the stroke to `point`, stamping dabs along the way, each one
+    /// repeated at every symmetry copy. `paint` turns a pixel's color from
+    /// before the stroke and the stroke's coverage of it into its new color.
+    /// Returns the pixels changed by this call.
+    pub fn stroke_to(
+        &mut self,
+        canvas: &mut Canvas,
+        point: StrokePoint,
+        size: f32,
+        settings: &BrushSettings,
+        copies: &[SymmetryCopy],
+        paint: &impl Fn(Color, f32) -> Color,
+    ) -> Vec<PixelDiff> {
+        let mut touched = Vec::new();
+
+        let Some(last) = self.last else {
+            touched.extend(self.dab(canvas, point, size, settings, copies, paint));
+            self.last = Some(point);
+            self.until_next_dab = dab_distance(point, size, settings);
+            return touched;
+        };
+
+        let dx = point.x - last.x;
+        let dy = point.y - last.y;
+        let length = dx.hypot(dy);
+        let mut travelled = self.until_next_dab;
+
+        while travelled <= length {
+            let t = travelled / length;
+            let dab = StrokePoint {
+                x: last.x + dx * t,
+                y: last.y + dy * t,
+                pressure: last.pressure + (point.pressure - last.pressure) * t,
+            };
+            touched.extend(self.dab(canvas, dab, size, settings, copies, paint));
+            travelled += dab_distance(dab, size, settings);
+        }
+
+        self.until_next_dab = travelled - length;
+        self.last = Some(point);
+        touched
+    }
+
+    fn dab(
+        &mut self,
+        canvas: &mut Canvas,
+        point: StrokePoint,
+        size: f32,
+        settings: &BrushSettings,
+        copies: &[SymmetryCopy],
+        paint: &impl Fn(Color, f32) -> Color,
+    ) -> Vec<PixelDiff> {
+        let mut touched = Vec::new();
+        let radius = dab_radius(point, size, settings);
+        let flow = settings.flow.clamp(0.0, 1.0)
+            * if settings.pressure_opacity { point.pressure } else { 1.0 };
+        let opacity = settings.opacity.clamp(0.0, 1.0);
+
+        for copy in copies {
+            let (cx, cy) = copy.apply((point.x, point.y));
+            let x0 = (cx - radius).floor().max(0.0) as u32;
+            let y0 = (cy - radius).floor().max(0.0) as u32;
+            let x1 = ((cx + radius).ceil().max(0.0) as u32).min(canvas.width);
+            let y1 = ((cy + radius).ceil().max(0.0) as u32).min(canvas.height);
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let distance = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy);
+                    let tip = tip_coverage(distance, radius, settings.hardness) * flow;
+                    if tip <= 0.0 {
+                        continue;
+                    }
+
+                    let (index, covered) = match self.coverage.get(&(x, y)) {
+                        Some(entry) => *entry,
+                        None => {
+                            let Ok(before) = canvas.get_pixel(x, y) else { continue };
+                            self.pixels.push(PixelDiff { before, after: before });
+                            (self.pixels.len() - 1, 0.0)
+                        }
+                    };
+
+                    let covered = (covered + (1.0 - covered) * tip).min(1.0);
+                    self.coverage.insert((x, y), (index, covered));
+
+                    let diff = &mut self.pixels[index];
+                    let color = paint(diff.before.color, covered * opacity);
+                    if color == diff.after.color {
+                        continue;
+                    }
+                    diff.after = Pixel::new(x, y, color);
+                    let _ = canvas.set_pixel(diff.after);
+                    touched.push(diff.clone());
+                }
+            }
+        }
+        touched
+    }
+}
+
+fn dab_radius(point: StrokePoint, size: f32, settings: &BrushSettings) -> f32 {
+    let pressure = if settings.pressure_size { point.pressure } else { 1.0 };
+    (size * RADIUS_PER_SIZE * pressure).max(0.5)
+}
+
+fn dab_distance(point: StrokePoint, size: f32, settings: &BrushSettings) -> f32 {
+    (dab_radius(point, size, settings) * 2.0 * settings.spacing).max(MIN_DAB_DISTANCE)
+}
+
+/// Strength of the tip `distance` pixels from its center: full inside
+/// `hardness * radius`, then a smooth falloff to zero at the edge. The edge
+/// itself is anti-aliased over one pixel.
+pub fn tip_coverage(distance: f32, radius: f32, hardness: f32) -> f32 {
+    let edge = (radius - distance + 0.5).clamp(0.0, 1.0);
+    let solid = radius * hardness.clamp(0.0, 1.0);
+    if distance <= solid {
+        return edge;
+    }
+
+    let t = ((distance - solid) / (radius - solid).max(f32::EPSILON)).clamp(0.0, 1.0);
+    let falloff = 1.0 - t * t * (3.0 - 2.0 * t);
+    falloff.min(edge)
+}
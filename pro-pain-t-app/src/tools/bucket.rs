@@ -1,12 +1,14 @@
 use crate::structs::history::{PixelDiff, StrokeDiff};
+use crate::structs::color::Color;
 use crate::structs::pixel::Pixel;
-use leptos::prelude::{Get, Update};
+use leptos::prelude::{Get, GetUntracked, Update, WithUntracked};
 use serde::{Deserialize, Serialize};
 
 use crate::tools::context::ToolContext;
+use crate::render::canvas_renderer::composite_layers;
 use crate::tools::geometry::screen_to_canvas;
+use crate::tools::selection_mask::selection_coverage;
 use web_sys::PointerEvent;
-use std::collections::VecDeque;
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct BucketState {
@@ -42,7 +44,35 @@ impl BucketState {
             return;
         }
 
-        let fill_color = ctx.project.get().current_color.get();
+        // Compare in the canvas' 8-bit precision, otherwise a fill whose alpha
+        // rounds to an existing color would be recorded as a change.
+        let fill_color = Color::from_rgba8(ctx.project.get().current_color.get().to_rgba8());
+        let tolerance = ctx.workspace_state.tolerance.get_untracked() as f32;
+        let contiguous = ctx.workspace_state.contiguous.get_untracked();
+        let eight_connected = ctx.workspace_state.eight_connected.get_untracked();
+
+        // A selection on the layer limits the fill to its pixels
+        ctx.workspace_state.settle_selection(ctx.project);
+        let selection = ctx.workspace_state.selection.with_untracked(|selection| {
+            selection
+                .as_ref()
+                .filter(|sel| sel.layer_id == layer_id)
+                .map(|sel| (sel.rect, sel.mask.clone()))
+        });
+        let coverage = |px: u32, py: u32| match &selection {
+            Some((rect, mask)) => selection_coverage(*rect, mask.as_ref(), px as i32, py as i32),
+            None => 255,
+        };
+        if coverage(x as u32, y as u32) == 0 {
+            return;
+        }
+
+        let merged = ctx.workspace_state.sample_merged.get_untracked().then(|| {
+            ctx.project
+                .get()
+                .layers
+                .with_untracked(|layers| composite_layers(layers, Color::transparent()))
+        });
 
         ctx.project.get().layers.update(|layers| {
             let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
@@ -56,41 +86,41 @@ impl BucketState {
             let ux = x as u32;
             let uy = y as u32;
 
-            let start_pixel = canvas
-                .get_pixel(ux, uy)
-                .ok()
-                .unwrap_or(Pixel::new(ux, uy, canvas.background_color));
-
-            let target_color = start_pixel.color;
-
-            if target_color == fill_color {
-                return;
-            }
-
-            let mut queue = VecDeque::new();
-            queue.push_back((x, y));
-
-            let w = canvas.width as i32;
-            let h = canvas.height as i32;
-
-            while let Some((cx, cy)) = queue.pop_front() {
-                if cx < 0 || cy < 0 || cx >= w || cy >= h {
-                    continue;
-                }
-
-                let ux = cx as u32;
-                let uy = cy as u32;
+            let region = {
+                let sample = |px: u32, py: u32| match &merged {
+                    Some((rgba, width, height)) if px < *width && py < *height => {
+                        let i = (py * width + px) as usize * 4;
+                        Color::from_rgba8([rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]])
+                    }
+                    Some(_) => Color::transparent(),
+                    None => canvas
+                        .get_pixel(px, py)
+                        .map(|p| p.color)
+                        .unwrap_or(canvas.background_color),
+                };
+                let target_color = sample(ux, uy);
+
+                grow_region(canvas.width, canvas.height, (ux, uy), contiguous, eight_connected, |px, py| {
+                    coverage(px, py) > 0 && sample(px, py).distance(target_color) <= tolerance
+                })
+            };
+
+            for (index, _) in region.iter().enumerate().filter(|(_, inside)| **inside) {
+                let px = index as u32 % canvas.width;
+                let py = index as u32 / canvas.width;
 
                 let old_pixel = canvas
-                    .get_pixel(ux, uy)
+                    .get_pixel(px, py)
                     .ok()
-                    .unwrap_or(Pixel::new(ux, uy, canvas.background_color));
-
-                if old_pixel.color != target_color {
+                    .unwrap_or(Pixel::new(px, py, canvas.background_color));
+                let new_color = match coverage(px, py) {
+                    255 => fill_color,
+                    partial => old_pixel.color.lerp(fill_color, partial as f32 / 255.0),
+                };
+                if old_pixel.color == new_color {
                     continue;
                 }
-
-                let new_pixel = Pixel::new(ux, uy, fill_color);
+                let new_pixel = Pixel::new(px, py, new_color);
 
                 self.current_stroke.push(PixelDiff {
                     before: old_pixel.clone(),
@@ -98,12 +128,9 @@ impl BucketState {
                 });
 
                 let _ = canvas.set_pixel(new_pixel);
-
-                queue.push_back((cx + 1, cy));
-                queue.push_back((cx - 1, cy));
-                queue.push_back((cx, cy + 1));
-                queue.push_back((cx, cy - 1));
             }
+
+            ctx.workspace_state.mark_dirty(&self.current_stroke);
         });
 
         if self.current_stroke.is_empty() {
@@ -111,7 +138,7 @@ impl BucketState {
         }
 
         ctx.project.update(|project| {
-            project.history.add(StrokeDiff {
+            project.record(StrokeDiff {
                 layer_id,
                 pixels: std::mem::take(&mut self.current_stroke),
             });
@@ -126,3 +153,70 @@ impl BucketState {
         "cell"
     }
 }
+
+/// Pixels that `matches` accepts, as a `width * height` row-major map. With
+/// `contiguous` only the ones connected to `start` through other matching
+/// pixels are included, otherwise every matching pixel is. Connected means
+/// sharing an edge, or also a corner when `eight_connected`.
+pub fn grow_region(
+    width: u32,
+    height: u32,
+    start: (u32, u32),
+    contiguous: bool,
+    eight_connected: bool,
+    matches: impl Fn(u32, u32) -> bool,
+) -> Vec<bool> {
+    let mut region = vec![false; (width * height) as usize];
+    if start.0 >= width || start.1 >= height {
+        return region;
+    }
+
+    if !contiguous {
+        for y in 0..height {
+            for x in 0..width {
+                region[(y * width + x) as usize] = matches(x, y);
+            }
+        }
+        return region;
+    }
+
+    // Scanline fill: each popped seed fills its whole horizontal run, then
+    // seeds one pixel per run of open pixels in the rows above and below.
+    let mut seeds = vec![start];
+    while let Some((x, y)) = seeds.pop() {
+        let row = (y * width) as usize;
+        if region[row + x as usize] || !matches(x, y) {
+            continue;
+        }
+
+        let mut left = x;
+        while left > 0 && !region[row + left as usize - 1] && matches(left - 1, y) {
+            left -= 1;
+        }
+        let mut right = x;
+        while right + 1 < width && !region[row + right as usize + 1] && matches(right + 1, y) {
+            right += 1;
+        }
+        region[row + left as usize..=row + right as usize].fill(true);
+
+        let (from, to) = if eight_connected {
+            (left.saturating_sub(1), (right + 1).min(width - 1))
+        } else {
+            (left, right)
+        };
+        let neighbours = [y.checked_sub(1), (y + 1 < height).then_some(y + 1)];
+        for ny in neighbours.into_iter().flatten() {
+            let next_row = (ny * width) as usize;
+            let mut in_run = false;
+            for nx in from..=to {
+                let open = !region[next_row + nx as usize] && matches(nx, ny);
+                if open && !in_run {
+                    seeds.push((nx, ny));
+                }
+                in_run = open;
+            }
+        }
+    }
+
+    region
+}
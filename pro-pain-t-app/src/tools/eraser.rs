@@ -1,55 +1,49 @@
-use crate::structs::history::{PixelDiff, StrokeDiff};
-use crate::structs::pixel::Pixel;
+use crate::state::workspace_state::WorkspaceState;
+use crate::structs::color::Color;
+use crate::structs::history::StrokeDiff;
+use crate::structs::project::Project;
+use crate::tools::brush_engine::{BrushStroke, StrokePoint};
 use crate::tools::context::ToolContext;
-use crate::tools::geometry::{draw_line, screen_to_canvas};
-use leptos::prelude::{Get, Update};
+use crate::tools::geometry::screen_to_canvas_f;
+use leptos::prelude::{Get, GetUntracked, RwSignal, Update, WithUntracked};
 use serde::{Deserialize, Serialize};
 use web_sys::PointerEvent;
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct EraserState {
-    is_erasing: bool,
-    last_pos: Option<(i32, i32)>,
-    current_stroke: Vec<PixelDiff>,
+    /// Layer the stroke in progress paints on, `None` between strokes.
+    layer_id: Option<usize>,
+    stroke: BrushStroke,
 }
 
 impl EraserState {
     pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        self.is_erasing = true;
-        self.last_pos = None;
-        self.current_stroke.clear();
+        self.layer_id = ctx.selected_layer;
+        self.stroke.clear();
         self.apply_at(e, ctx);
     }
 
     pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        if !self.is_erasing {
-            return;
-        }
         self.apply_at(e, ctx);
     }
 
     pub fn on_pointer_up(&mut self, _e: &PointerEvent, ctx: &ToolContext) {
-        self.is_erasing = false;
-        self.last_pos = None;
+        let Some(layer_id) = self.layer_id.take() else { return; };
 
-        if self.current_stroke.is_empty() {
+        if self.stroke.is_empty() {
             return;
         }
 
-        let Some(layer_id) = ctx.selected_layer else { return; };
-
         ctx.project.update(|project| {
-            project.history.add(StrokeDiff {
+            project.record(StrokeDiff {
                 layer_id,
-                pixels: std::mem::take(&mut self.current_stroke),
+                pixels: self.stroke.take_pixels(),
             });
         });
     }
 
     fn apply_at(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        let Some(layer_id) = ctx.selected_layer else { return; };
-
-        let (x, y) = screen_to_canvas(
+        let (x, y) = screen_to_canvas_f(
             ctx.canvas,
             e.client_x() as f64,
             e.client_y() as f64,
@@ -57,58 +51,118 @@ impl EraserState {
             ctx.pan_x,
             ctx.pan_y,
         );
+        let point = StrokePoint { x, y, pressure: StrokePoint::pressure_of(e) };
 
-        ctx.project.get().layers.update(|layers| {
-            let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
-            if layer.is_locked || !layer.is_visible { return; }
+        self.erase_to(point, &ctx.project.get(), ctx.workspace_state);
+    }
 
-            let brush_size = ctx.workspace_state.brush_size.get().max(0.1);
-            let radius = (brush_size * 12.0).ceil() as i32;
-            let bg_color = layer.canvas.background_color;
-
-            let canvas = &mut layer.canvas;
-
-            let mut draw_pixel = |px: i32, py: i32| {
-                for dy in -radius..=radius {
-                    for dx in -radius..=radius {
-                        if dx*dx + dy*dy > (radius*radius) { continue; }
-                        let nx = px + dx;
-                        let ny = py + dy;
-                        if nx < 0 || ny < 0 { continue; }
-                        let ux = nx as u32;
-                        let uy = ny as u32;
-                        if let Ok(old_pixel) = canvas.get_pixel(ux, uy) {
-                            if old_pixel.color != bg_color {
-                                let new_pixel = Pixel::new(ux, uy, bg_color);
-                                self.current_stroke.push(PixelDiff {
-                                    before: old_pixel,
-                                    after: new_pixel,
-                                });
-                                let _ = canvas.set_pixel(new_pixel);
-                            }
-                        }
-                    }
-                }
-            };
+    /// Continues the stroke to `point`.
+    fn erase_to(&mut self, point: StrokePoint, project: &Project, workspace_state: &WorkspaceState) {
+        let Some(layer_id) = self.layer_id else { return; };
 
-            let current = (x, y);
-            if let Some((lx, ly)) = self.last_pos {
-                draw_line(lx, ly, x, y, &mut draw_pixel);
-            } else {
-                draw_pixel(x, y);
-            }
+        project.layers.update(|layers| {
+            // The bottom layer can be erased to the document background instead of transparency
+            let is_bottom = layers.first().is_some_and(|l| l.id == layer_id);
+            let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
+            if layer.is_locked || !layer.is_visible { return; }
 
-            self.last_pos = Some(current);
+            let brush_size = workspace_state.brush_size.get().max(0.1);
+            let settings = workspace_state.eraser_settings.get_untracked();
+            let copies = workspace_state.symmetry.get_untracked().copies(layer.canvas.width, layer.canvas.height);
+            let background = (is_bottom && workspace_state.erase_to_background.get_untracked())
+                .then_some(project.background_color);
+
+            let touched = self.stroke.stroke_to(
+                &mut layer.canvas,
+                point,
+                brush_size,
+                &settings,
+                &copies,
+                &|before, coverage| match background {
+                    Some(background) => Color::from_rgba8(before.lerp(background, coverage).to_rgba8()),
+                    None => Color::from_rgba8(
+                        Color { alpha: before.alpha * (1.0 - coverage), ..before }.to_rgba8(),
+                    ),
+                },
+            );
+            workspace_state.mark_dirty(&touched);
         });
     }
 
-    pub fn cancel(&mut self) {
-        self.is_erasing = false;
-        self.last_pos = None;
-        self.current_stroke.clear();
+    /// Abandons the stroke, putting back the pixels it painted over.
+    pub fn cancel(&mut self, project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
+        let pixels = self.stroke.take_pixels();
+        let Some(layer_id) = self.layer_id.take() else { return; };
+        workspace_state.mark_dirty(&pixels);
+        project.with_untracked(|project| StrokeDiff { layer_id, pixels }.restore(project));
     }
 
     pub fn cursor(&self) -> &'static str {
         "crosshair"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::history::History;
+    use crate::structs::layer::Layer;
+    use crate::tools::tools::Tool;
+    use leptos::prelude::{Set, With};
+
+    fn red_project(background: Color) -> Project {
+        let project = Project::new("Erase".to_string(), 3, 3, background);
+        project.layers.update(|layers| {
+            for y in 0..3 {
+                for x in 0..3 {
+                    let _ = layers[0].canvas.set_rgba(x, y, [255, 0, 0, 255]);
+                }
+            }
+        });
+        project
+    }
+
+    #[test]
+    fn erasing_the_bottom_layer_to_background_paints_the_document_background() {
+        let background = Color::new(10, 200, 30, 1.0);
+        let project = red_project(background);
+        let workspace_state = WorkspaceState::default();
+        workspace_state.brush_size.set(3.0);
+        workspace_state.erase_to_background.set(true);
+
+        let mut eraser = EraserState { layer_id: Some(0), ..Default::default() };
+        eraser.erase_to(StrokePoint { x: 1.5, y: 1.5, pressure: 1.0 }, &project, &workspace_state);
+
+        let center = project.layers.with(|layers| layers[0].canvas.get_rgba(1, 1));
+        assert_eq!(center, Some(background.to_rgba8()));
+    }
+
+    #[test]
+    fn a_cancelled_stroke_leaves_its_layer_untouched() {
+        let project = RwSignal::new(red_project(Color::default_white()));
+        project.with_untracked(|project| {
+            project.layers.update(|layers| layers.push(Layer::new(1, "Layer 1".to_string(), 3, 3, Color::transparent())));
+        });
+        let rgba = |index: usize| {
+            project.with_untracked(|project| project.layers.with(|layers| layers[index].canvas.as_rgba().to_vec()))
+        };
+        let before = (rgba(0), rgba(1));
+        let workspace_state = WorkspaceState::default();
+        workspace_state.brush_size.set(3.0);
+
+        let mut eraser = EraserState { layer_id: Some(0), ..Default::default() };
+        project.with_untracked(|project| {
+            eraser.erase_to(StrokePoint { x: 1.5, y: 1.5, pressure: 1.0 }, project, &workspace_state);
+        });
+        assert_ne!(rgba(0), before.0);
+        // Selecting another layer mid-stroke doesn't move where the pixels go back to
+        workspace_state.selected_layer_id.set(Some(1));
+        let mut tool = Tool::Eraser(eraser);
+        tool.on_pointer_cancel(&project, &workspace_state);
+
+        assert_eq!((rgba(0), rgba(1)), before);
+        // Nothing was recorded, so undo has nothing to take back
+        History::apply_undo(&project, &workspace_state);
+        assert!(!project.with_untracked(|project| project.is_dirty));
+    }
+}
@@ -14,6 +14,32 @@ pub fn screen_to_canvas(
     (x, y)
 }
 
+/// Like `screen_to_canvas`, without rounding to a pixel.
+pub fn screen_to_canvas_f(
+    canvas: &HtmlCanvasElement,
+    client_x: f64,
+    client_y: f64,
+    zoom: f32,
+    pan_x: f32,
+    pan_y: f32,
+) -> (f32, f32) {
+    let rect = canvas.get_bounding_client_rect();
+    let x = (client_x - rect.left() - pan_x as f64) / zoom as f64;
+    let y = (client_y - rect.top() - pan_y as f64) / zoom as f64;
+    (x as f32, y as f32)
+}
+
+/// Rotates `end` around `start` to the nearest multiple of `step_degrees`,
+/// keeping its distance.
+pub fn snap_angle(start: (f32, f32), end: (f32, f32), step_degrees: f32) -> (f32, f32) {
+    let dx = end.0 - start.0;
+    let dy = end.1 - start.1;
+    let length = dx.hypot(dy);
+    let step = step_degrees.to_radians();
+    let angle = (dy.atan2(dx) / step).round() * step;
+    (start.0 + length * angle.cos(), start.1 + length * angle.sin())
+}
+
 // Bresenham line helper
 pub fn draw_line(x0: i32, y0: i32, x1: i32, y1: i32, mut plot: impl FnMut(i32, i32)) {
     let dx = (x1 - x0).abs();
@@ -0,0 +1,226 @@
+//! Fills the selected layer with a color gradient dragged across the canvas.
+use crate::structs::color::Color;
+use crate::structs::history::{PixelDiff, StrokeDiff};
+use crate::structs::pixel::Pixel;
+use crate::tools::context::ToolContext;
+use crate::tools::geometry::{screen_to_canvas_f, snap_angle};
+use crate::tools::select::layer_is_editable;
+use crate::tools::selection_mask::selection_coverage;
+use leptos::prelude::{GetUntracked, Update, WithUntracked};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use web_sys::PointerEvent;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum GradientShape {
+    Linear,
+    Radial,
+    Angular,
+    Reflected,
+}
+
+impl GradientShape {
+    pub const ALL: [GradientShape; 4] = [
+        GradientShape::Linear,
+        GradientShape::Radial,
+        GradientShape::Angular,
+        GradientShape::Reflected,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GradientShape::Linear => "Linear",
+            GradientShape::Radial => "Radial",
+            GradientShape::Angular => "Angular",
+            GradientShape::Reflected => "Reflected",
+        }
+    }
+
+    /// Position `0.0..=1.0` along the gradient of the point `p` for a drag
+    /// from `start` to `end`.
+    fn position(self, start: (f32, f32), end: (f32, f32), p: (f32, f32)) -> f32 {
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let (px, py) = (p.0 - start.0, p.1 - start.1);
+        let length_sq = dx * dx + dy * dy;
+
+        let t = match self {
+            GradientShape::Linear => (px * dx + py * dy) / length_sq,
+            GradientShape::Reflected => ((px * dx + py * dy) / length_sq).abs(),
+            GradientShape::Radial => (px * px + py * py).sqrt() / length_sq.sqrt(),
+            GradientShape::Angular => ((py.atan2(px) - dy.atan2(dx)) / TAU).rem_euclid(1.0),
+        };
+        t.clamp(0.0, 1.0)
+    }
+}
+
+/// Colors at the two ends of the gradient.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum GradientPreset {
+    ForegroundToBackground,
+    ForegroundToTransparent,
+}
+
+impl GradientPreset {
+    pub const ALL: [GradientPreset; 2] = [
+        GradientPreset::ForegroundToBackground,
+        GradientPreset::ForegroundToTransparent,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GradientPreset::ForegroundToBackground => "Current to secondary",
+            GradientPreset::ForegroundToTransparent => "Current to transparent",
+        }
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct GradientState {
+    pointer_id: Option<i32>,
+    layer_id: Option<usize>,
+    /// Drag start and end in document coordinates.
+    start: Option<(f32, f32)>,
+    end: Option<(f32, f32)>,
+}
+
+impl GradientState {
+    pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if e.button() != 0 || self.pointer_id.is_some() {
+            return;
+        }
+        let Some(layer_id) = ctx.selected_layer else { return };
+        if !layer_is_editable(ctx.project, layer_id) {
+            return;
+        }
+
+        let point = pointer_position(e, ctx);
+        self.pointer_id = Some(e.pointer_id());
+        self.layer_id = Some(layer_id);
+        self.start = Some(point);
+        self.end = Some(point);
+    }
+
+    pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        let Some(start) = self.start else { return };
+
+        // Shift keeps the direction at multiples of 45°
+        let point = pointer_position(e, ctx);
+        self.end = Some(if e.shift_key() { snap_angle(start, point, 45.0) } else { point });
+    }
+
+    pub fn on_pointer_up(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        if let (Some(layer_id), Some(start), Some(end)) = (self.layer_id, self.start, self.end) {
+            apply_gradient(ctx, layer_id, start, end);
+        }
+        self.cancel();
+    }
+
+    pub fn cancel(&mut self) {
+        self.pointer_id = None;
+        self.layer_id = None;
+        self.start = None;
+        self.end = None;
+    }
+
+    pub fn cursor(&self) -> &'static str {
+        "crosshair"
+    }
+
+    /// The dragged line.
+    pub fn preview_path(&self) -> Option<Vec<(f32, f32)>> {
+        Some(vec![self.start?, self.end?])
+    }
+}
+
+fn pointer_position(e: &PointerEvent, ctx: &ToolContext) -> (f32, f32) {
+    screen_to_canvas_f(
+        ctx.canvas,
+        e.client_x() as f64,
+        e.client_y() as f64,
+        ctx.zoom,
+        ctx.pan_x,
+        ctx.pan_y,
+    )
+}
+
+/// Blends the gradient over the layer as one undo step, only inside the
+/// selection when the layer has one.
+fn apply_gradient(ctx: &ToolContext, layer_id: usize, start: (f32, f32), end: (f32, f32)) {
+    // A click without a drag has no direction
+    if (end.0 - start.0).hypot(end.1 - start.1) < 0.5 {
+        return;
+    }
+
+    let shape = ctx.workspace_state.gradient_shape.get_untracked();
+    let from = ctx.project.with_untracked(|project| project.current_color.get_untracked());
+    let to = match ctx.workspace_state.gradient_preset.get_untracked() {
+        GradientPreset::ForegroundToBackground => ctx.workspace_state.secondary_color.get_untracked(),
+        GradientPreset::ForegroundToTransparent => Color { alpha: 0.0, ..from },
+    };
+
+    ctx.workspace_state.settle_selection(ctx.project);
+    let selection = ctx.workspace_state.selection.with_untracked(|selection| {
+        selection
+            .as_ref()
+            .filter(|sel| sel.layer_id == layer_id)
+            .map(|sel| (sel.rect, sel.mask.clone()))
+    });
+
+    let mut diffs: Vec<PixelDiff> = Vec::new();
+
+    ctx.project.update(|project| {
+        project.layers.update(|layers| {
+            let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return };
+            let canvas = &mut layer.canvas;
+
+            // Only the selection's bounds need visiting
+            let (x0, y0, x1, y1) = match &selection {
+                Some((rect, _)) => (
+                    rect.x.max(0) as u32,
+                    rect.y.max(0) as u32,
+                    ((rect.x + rect.w).max(0) as u32).min(canvas.width),
+                    ((rect.y + rect.h).max(0) as u32).min(canvas.height),
+                ),
+                None => (0, 0, canvas.width, canvas.height),
+            };
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let coverage = match &selection {
+                        Some((rect, mask)) => selection_coverage(*rect, mask.as_ref(), x as i32, y as i32),
+                        None => 255,
+                    };
+                    if coverage == 0 {
+                        continue;
+                    }
+
+                    let Ok(before) = canvas.get_pixel(x, y) else { continue };
+                    let t = shape.position(start, end, (x as f32 + 0.5, y as f32 + 0.5));
+                    let color = before.color.blend_over(from.lerp(to, t), coverage as f32 / 255.0);
+                    if before.color == color {
+                        continue;
+                    }
+
+                    let after = Pixel::new(x, y, color);
+                    diffs.push(PixelDiff { before, after });
+                    let _ = canvas.set_pixel(after);
+                }
+            }
+
+            ctx.workspace_state.mark_dirty(&diffs);
+        });
+
+        if !diffs.is_empty() {
+            project.record(StrokeDiff {
+                layer_id,
+                pixels: std::mem::take(&mut diffs),
+            });
+        }
+    });
+}
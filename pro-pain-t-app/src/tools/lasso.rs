@@ -0,0 +1,194 @@
+//! Freehand and polygonal lasso selection.
+use crate::tools::context::ToolContext;
+use crate::tools::geometry::screen_to_canvas_f;
+use crate::tools::select::{combine_into_selection, document_rect, layer_is_editable};
+use crate::tools::selection_mask::{SelectionMask, SelectionOp};
+use leptos::prelude::{Set, WithUntracked};
+use serde::{Deserialize, Serialize};
+use web_sys::{KeyboardEvent, PointerEvent};
+
+/// Two clicks closer together than this (milliseconds) close a polygon.
+const DOUBLE_CLICK_MS: f64 = 400.0;
+/// Clicking within this many screen pixels of the first vertex closes a polygon.
+const CLOSE_DISTANCE_PX: f32 = 6.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LassoState {
+    polygonal: bool,
+    pointer_id: Option<i32>,
+    layer_id: Option<usize>,
+    op: SelectionOp,
+    /// Outline so far, in document coordinates.
+    points: Vec<(f32, f32)>,
+    /// Pointer position, the rubber band end of a polygon.
+    cursor: Option<(f32, f32)>,
+    last_click_time: f64,
+}
+
+impl Default for LassoState {
+    fn default() -> Self {
+        Self {
+            polygonal: false,
+            pointer_id: None,
+            layer_id: None,
+            op: SelectionOp::Replace,
+            points: Vec::new(),
+            cursor: None,
+            last_click_time: 0.0,
+        }
+    }
+}
+
+impl LassoState {
+    pub fn polygonal() -> Self {
+        Self {
+            polygonal: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_polygonal(&self) -> bool {
+        self.polygonal
+    }
+
+    pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if e.button() != 0 || self.pointer_id.is_some() {
+            return;
+        }
+        let point = pointer_position(e, ctx);
+
+        if self.polygonal && !self.points.is_empty() {
+            let is_double_click = e.time_stamp() - self.last_click_time <= DOUBLE_CLICK_MS;
+            let first = self.points[0];
+            let closes = (point.0 - first.0).hypot(point.1 - first.1) * ctx.zoom <= CLOSE_DISTANCE_PX;
+            self.last_click_time = e.time_stamp();
+
+            if is_double_click || closes {
+                self.finish(ctx);
+            } else {
+                self.points.push(point);
+            }
+            return;
+        }
+
+        let Some(layer_id) = ctx.selected_layer else { return };
+        if !layer_is_editable(ctx.project, layer_id) {
+            return;
+        }
+        prepare_selection(ctx, layer_id);
+
+        self.layer_id = Some(layer_id);
+        self.op = SelectionOp::from_modifiers(e.shift_key(), e.alt_key());
+        self.points = vec![point];
+        self.cursor = Some(point);
+        self.last_click_time = e.time_stamp();
+        if !self.polygonal {
+            self.pointer_id = Some(e.pointer_id());
+        }
+    }
+
+    pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.points.is_empty() {
+            return;
+        }
+        let point = pointer_position(e, ctx);
+        self.cursor = Some(point);
+
+        if self.polygonal || self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        // Sub-pixel steps don't change the mask, skip them to keep the outline short
+        let last = self.points[self.points.len() - 1];
+        if (point.0 - last.0).abs() >= 0.5 || (point.1 - last.1).abs() >= 0.5 {
+            self.points.push(point);
+        }
+    }
+
+    pub fn on_pointer_up(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.polygonal || self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        self.finish(ctx);
+    }
+
+    /// Enter closes a polygon, Backspace removes its last vertex.
+    pub fn on_key_down(&mut self, e: &KeyboardEvent, ctx: &ToolContext) -> bool {
+        if !self.polygonal || self.points.is_empty() {
+            return false;
+        }
+        match e.key().as_str() {
+            "Enter" => {
+                self.finish(ctx);
+                true
+            }
+            "Backspace" => {
+                self.points.pop();
+                if self.points.is_empty() {
+                    self.cancel();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.pointer_id = None;
+        self.layer_id = None;
+        self.points.clear();
+        self.cursor = None;
+    }
+
+    pub fn cursor(&self) -> &'static str {
+        "crosshair"
+    }
+
+    /// Outline drawn while the lasso is open.
+    pub fn preview_path(&self) -> Option<Vec<(f32, f32)>> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let mut path = self.points.clone();
+        if self.polygonal {
+            path.extend(self.cursor);
+        }
+        Some(path)
+    }
+
+    /// Turns the outline into a mask and combines it with the selection.
+    fn finish(&mut self, ctx: &ToolContext) {
+        let Some(layer_id) = self.layer_id else {
+            self.cancel();
+            return;
+        };
+
+        let shape = SelectionMask::from_polygon(&self.points, document_rect(ctx));
+        combine_into_selection(ctx.workspace_state, layer_id, shape, self.op);
+        self.cancel();
+    }
+}
+
+fn pointer_position(e: &PointerEvent, ctx: &ToolContext) -> (f32, f32) {
+    screen_to_canvas_f(
+        ctx.canvas,
+        e.client_x() as f64,
+        e.client_y() as f64,
+        ctx.zoom,
+        ctx.pan_x,
+        ctx.pan_y,
+    )
+}
+
+/// Drops a floating selection back into its layer, keeping its outline so the
+/// lasso can be combined with it. Selections of other layers are dropped.
+fn prepare_selection(ctx: &ToolContext, layer_id: usize) {
+    ctx.workspace_state.settle_selection(ctx.project);
+
+    let other_layer = ctx
+        .workspace_state
+        .selection
+        .with_untracked(|selection| selection.as_ref().is_some_and(|sel| sel.layer_id != layer_id));
+    if other_layer {
+        ctx.workspace_state.selection.set(None);
+    }
+}
@@ -1,98 +1,310 @@
-use crate::structs::history::{PixelDiff, StrokeDiff};
-use crate::structs::pixel::Pixel;
-use leptos::prelude::{Get, Update};
+use crate::structs::history::StrokeDiff;
+use leptos::prelude::{Get, GetUntracked, Update};
 use serde::{Deserialize, Serialize};
 use crate::tools::context::ToolContext;
-use crate::tools::geometry::{draw_line, screen_to_canvas};
-use web_sys::PointerEvent;
+use crate::tools::geometry::{draw_line, screen_to_canvas_f, snap_angle};
+use crate::tools::shape::{fill_polygon, set_pixel};
+use web_sys::{KeyboardEvent, PointerEvent};
+
+/// Shift snaps the line to multiples of this angle.
+const SNAP_DEGREES: f32 = 15.0;
+/// Two clicks closer together than this (milliseconds) finish a polyline.
+const DOUBLE_CLICK_MS: f64 = 400.0;
+
+/// Ends of the line that get an arrowhead.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum LineArrows {
+    None,
+    End,
+    Start,
+    Both,
+}
+
+impl LineArrows {
+    pub const ALL: [LineArrows; 4] = [LineArrows::None, LineArrows::End, LineArrows::Start, LineArrows::Both];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineArrows::None => "No arrows",
+            LineArrows::End => "Arrow at end",
+            LineArrows::Start => "Arrow at start",
+            LineArrows::Both => "Arrows at both ends",
+        }
+    }
+
+    fn at_start(self) -> bool {
+        matches!(self, LineArrows::Start | LineArrows::Both)
+    }
+
+    fn at_end(self) -> bool {
+        matches!(self, LineArrows::End | LineArrows::Both)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum LineDash {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineDash {
+    pub const ALL: [LineDash; 3] = [LineDash::Solid, LineDash::Dashed, LineDash::Dotted];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineDash::Solid => "Solid",
+            LineDash::Dashed => "Dashed",
+            LineDash::Dotted => "Dotted",
+        }
+    }
+
+    /// Whether the line is drawn `distance` pixels along its path. Dash
+    /// lengths grow with the stroke `width`.
+    fn is_on(self, distance: f32, width: f32) -> bool {
+        let width = width.max(1.0);
+        let (on, off) = match self {
+            LineDash::Solid => return true,
+            LineDash::Dashed => (4.0 * width, 3.0 * width),
+            LineDash::Dotted => (width, 2.0 * width),
+        };
+        distance.rem_euclid(on + off) < on
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub struct LineOptions {
+    /// Clicks add segments until a double-click or Enter, instead of one drag.
+    pub polyline: bool,
+    pub arrows: LineArrows,
+    pub dash: LineDash,
+}
+
+impl Default for LineOptions {
+    fn default() -> Self {
+        Self {
+            polyline: false,
+            arrows: LineArrows::None,
+            dash: LineDash::Solid,
+        }
+    }
+}
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct LineState {
-    start_point: Option<(i32, i32)>,
-    current_stroke: Vec<PixelDiff>,
+    /// Pointer dragging a single line, unused in polyline mode.
+    pointer_id: Option<i32>,
+    /// Placed points in document coordinates.
+    points: Vec<(f32, f32)>,
+    /// Pointer position, the loose end of the line.
+    cursor: Option<(f32, f32)>,
+    /// Options and stroke width of the line being drawn, read at its first
+    /// point so the preview matches what is drawn.
+    options: LineOptions,
+    size: f32,
+    last_click_time: f64,
 }
 
 impl LineState {
     pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-        let (x, y) = screen_to_canvas(
-            ctx.canvas,
-            e.client_x() as f64,
-            e.client_y() as f64,
-            ctx.zoom,
-            ctx.pan_x,
-            ctx.pan_y,
-        );
-
-        if self.start_point.is_none() {
-            self.start_point = Some((x, y));
-        } else {
-            let (sx, sy) = self.start_point.unwrap();
-            let Some(layer_id) = ctx.selected_layer else { return; };
-            let size = ctx.workspace_state.brush_size.get().max(0.1);
-            let color = ctx.project.get().current_color.get();
-
-            ctx.project.update(|project| {
-                project.layers.update(|layers| {
-                    let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
-                    if layer.is_locked || !layer.is_visible { return; }
-
-                    let canvas = &mut layer.canvas;
+        if e.button() != 0 || self.pointer_id.is_some() {
+            return;
+        }
+        let point = pointer_position(e, ctx);
+
+        if self.options.polyline && !self.points.is_empty() {
+            let is_double_click = e.time_stamp() - self.last_click_time <= DOUBLE_CLICK_MS;
+            self.last_click_time = e.time_stamp();
+
+            // The first click of the double-click already placed the last point
+            if is_double_click {
+                self.finish(ctx);
+            } else {
+                self.points.push(self.cursor.unwrap_or(point));
+            }
+            return;
+        }
+
+        self.options = ctx.workspace_state.line_options.get_untracked();
+        self.size = ctx.workspace_state.brush_size.get_untracked().max(0.1);
+        self.points = vec![point];
+        self.cursor = Some(point);
+        self.last_click_time = e.time_stamp();
+        if !self.options.polyline {
+            self.pointer_id = Some(e.pointer_id());
+        }
+    }
+
+    pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        let Some(&last) = self.points.last() else { return };
+
+        let point = pointer_position(e, ctx);
+        self.cursor = Some(if e.shift_key() { snap_angle(last, point, SNAP_DEGREES) } else { point });
+    }
+
+    pub fn on_pointer_up(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        self.points.extend(self.cursor);
+        self.finish(ctx);
+    }
+
+    /// Enter finishes a polyline, Backspace removes its last point.
+    pub fn on_key_down(&mut self, e: &KeyboardEvent, ctx: &ToolContext) -> bool {
+        if !self.options.polyline || self.points.is_empty() {
+            return false;
+        }
+        match e.key().as_str() {
+            "Enter" => {
+                self.finish(ctx);
+                true
+            }
+            "Backspace" => {
+                self.points.pop();
+                if self.points.is_empty() {
+                    self.cancel();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn finish(&mut self, ctx: &ToolContext) {
+        let points = std::mem::take(&mut self.points);
+        if points.len() >= 2 {
+            self.draw(ctx, &points);
+        }
+        self.cancel();
+    }
+
+    fn draw(&mut self, ctx: &ToolContext, points: &[(f32, f32)]) {
+        let Some(layer_id) = ctx.selected_layer else { return; };
+        let size = self.size;
+        let color = ctx.project.get().current_color.get();
+        let options = self.options;
+        let mut diffs = Vec::new();
+
+        ctx.project.update(|project| {
+            project.layers.update(|layers| {
+                let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
+                if layer.is_locked || !layer.is_visible { return; }
+
+                let canvas = &mut layer.canvas;
+                let radius = size / 2.0;
+                // Path length up to the start of the current segment, keeps dashes going around corners
+                let mut travelled = 0.0;
+
+                for segment in points.windows(2) {
+                    let (sx, sy) = (segment[0].0.floor() as i32, segment[0].1.floor() as i32);
+                    let (ex, ey) = (segment[1].0.floor() as i32, segment[1].1.floor() as i32);
+
                     let mut plot_pixel = |px: i32, py: i32| {
-                        let radius = size / 2.0;
+                        let along = travelled + ((px - sx) as f32).hypot((py - sy) as f32);
+                        if !options.dash.is_on(along, size) {
+                            return;
+                        }
                         for dy in -(radius as i32)..=(radius as i32) {
                             for dx in -(radius as i32)..=(radius as i32) {
                                 let dist = ((dx as f32).powi(2) + (dy as f32).powi(2)).sqrt();
                                 if dist > radius { continue; }
-
-                                let nx = px + dx;
-                                let ny = py + dy;
-                                if nx < 0 || ny < 0 { continue; }
-                                let ux = nx as u32;
-                                let uy = ny as u32;
-                                let old_pixel = canvas.get_pixel(ux, uy).unwrap_or(Pixel::new(ux, uy, canvas.background_color));
-                                if old_pixel.color != color {
-                                    self.current_stroke.push(PixelDiff {
-                                        before: old_pixel,
-                                        after: Pixel::new(ux, uy, color),
-                                    });
-                                    let _ = canvas.set_pixel(Pixel::new(ux, uy, color));
-                                }
+                                set_pixel(canvas, px + dx, py + dy, color, &mut diffs);
                             }
                         }
                     };
 
-                    draw_line(sx, sy, x, y, &mut plot_pixel);
-                });
+                    draw_line(sx, sy, ex, ey, &mut plot_pixel);
+                    travelled += ((ex - sx) as f32).hypot((ey - sy) as f32);
+                }
+
+                for head in arrowheads(points, options.arrows, size) {
+                    fill_polygon(canvas, &head, color, &mut diffs);
+                }
+
+                ctx.workspace_state.mark_dirty(&diffs);
             });
 
-            if !self.current_stroke.is_empty() {
-                let Some(layer_id) = ctx.selected_layer else { return; };
-                ctx.project.update(|project| {
-                    project.history.add(StrokeDiff {
-                        layer_id,
-                        pixels: std::mem::take(&mut self.current_stroke),
-                    });
+            if !diffs.is_empty() {
+                project.record(StrokeDiff {
+                    layer_id,
+                    pixels: std::mem::take(&mut diffs),
                 });
             }
-
-            self.start_point = None;
-        }
+        });
     }
 
-    pub fn on_pointer_move(&mut self, _e: &PointerEvent, _ctx: &ToolContext) {
+    pub fn cancel(&mut self) {
+        self.pointer_id = None;
+        self.points.clear();
+        self.cursor = None;
     }
 
-    pub fn on_pointer_up(&mut self, _e: &PointerEvent, _ctx: &ToolContext) {
-        self.start_point = self.start_point.take();
-        self.current_stroke.clear();
+    pub fn cursor(&self) -> &'static str {
+        "crosshair"
     }
 
-    pub fn cancel(&mut self) {
-        self.start_point = None;
-        self.current_stroke.clear();
+    /// The line being drawn up to the pointer, and its arrowheads.
+    pub fn preview_paths(&self) -> Vec<Vec<(f32, f32)>> {
+        if self.points.is_empty() {
+            return Vec::new();
+        }
+        let mut path = self.points.clone();
+        path.extend(self.cursor);
+
+        let heads = arrowheads(&path, self.options.arrows, self.size).into_iter().map(|mut head| {
+            head.push(head[0]);
+            head
+        });
+        std::iter::once(path).chain(heads).collect()
     }
+}
 
-    pub fn cursor(&self) -> &'static str {
-        "crosshair"
+fn pointer_position(e: &PointerEvent, ctx: &ToolContext) -> (f32, f32) {
+    screen_to_canvas_f(
+        ctx.canvas,
+        e.client_x() as f64,
+        e.client_y() as f64,
+        ctx.zoom,
+        ctx.pan_x,
+        ctx.pan_y,
+    )
+}
+
+/// Triangles at the requested ends of the polyline, pointing outwards. Their
+/// size follows the stroke `width`.
+fn arrowheads(points: &[(f32, f32)], arrows: LineArrows, width: f32) -> Vec<Vec<(f32, f32)>> {
+    let length = (width * 4.0).max(6.0);
+    // Tips sit on pixel centers like the rasterized line
+    let center = |p: (f32, f32)| (p.0.floor() + 0.5, p.1.floor() + 0.5);
+
+    let head = |tip: (f32, f32), from: (f32, f32)| {
+        let (tip, from) = (center(tip), center(from));
+        let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+        let distance = dx.hypot(dy);
+        if distance < f32::EPSILON {
+            return None;
+        }
+        let (ux, uy) = (dx / distance, dy / distance);
+        let base = (tip.0 - ux * length, tip.1 - uy * length);
+        let half = length * 0.4;
+        Some(vec![
+            tip,
+            (base.0 - uy * half, base.1 + ux * half),
+            (base.0 + uy * half, base.1 - ux * half),
+        ])
+    };
+
+    let n = points.len();
+    if n < 2 {
+        return Vec::new();
+    }
+    let mut heads = Vec::new();
+    if arrows.at_start() {
+        heads.extend(head(points[0], points[1]));
+    }
+    if arrows.at_end() {
+        heads.extend(head(points[n - 1], points[n - 2]));
     }
+    heads
 }
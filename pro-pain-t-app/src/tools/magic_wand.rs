@@ -0,0 +1,93 @@
+//! Selects pixels of a similar color to the one clicked.
+use crate::render::canvas_renderer::composite_layers;
+use crate::structs::color::Color;
+use crate::tools::bucket::grow_region;
+use crate::tools::context::ToolContext;
+use crate::tools::geometry::screen_to_canvas;
+use crate::tools::select::{combine_into_selection, layer_is_editable, SelectionRect};
+use crate::tools::selection_mask::{SelectionMask, SelectionOp};
+use leptos::prelude::{GetUntracked, WithUntracked};
+use serde::{Deserialize, Serialize};
+use web_sys::PointerEvent;
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct MagicWandState;
+
+impl MagicWandState {
+    pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if e.button() != 0 {
+            return;
+        }
+        let Some(layer_id) = ctx.selected_layer else { return };
+        if !layer_is_editable(ctx.project, layer_id) {
+            return;
+        }
+
+        let (x, y) = screen_to_canvas(
+            ctx.canvas,
+            e.client_x() as f64,
+            e.client_y() as f64,
+            ctx.zoom,
+            ctx.pan_x,
+            ctx.pan_y,
+        );
+
+        let Some((rgba, width, height)) = sample_pixels(ctx, layer_id) else { return };
+        if x < 0 || y < 0 || x as u32 >= width || y as u32 >= height {
+            return;
+        }
+
+        let tolerance = ctx.workspace_state.tolerance.get_untracked() as f32;
+        let contiguous = ctx.workspace_state.contiguous.get_untracked();
+        let eight_connected = ctx.workspace_state.eight_connected.get_untracked();
+        let color_at = |px: u32, py: u32| {
+            let i = (py * width + px) as usize * 4;
+            Color::from_rgba8([rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]])
+        };
+        let target = color_at(x as u32, y as u32);
+
+        let region = grow_region(width, height, (x as u32, y as u32), contiguous, eight_connected, |px, py| {
+            color_at(px, py).distance(target) <= tolerance
+        });
+        let coverage = region.into_iter().map(|inside| if inside { 255 } else { 0 }).collect();
+        let bounds = SelectionRect { x: 0, y: 0, w: width as i32, h: height as i32 };
+        let shape = SelectionMask::from_coverage(bounds, coverage);
+
+        ctx.workspace_state.settle_selection(ctx.project);
+        let op = SelectionOp::from_modifiers(e.shift_key(), e.alt_key());
+        combine_into_selection(ctx.workspace_state, layer_id, shape, op);
+    }
+
+    pub fn on_pointer_move(&mut self, _e: &PointerEvent, _ctx: &ToolContext) {
+        // don't do anything
+    }
+
+    pub fn on_pointer_up(&mut self, _e: &PointerEvent, _ctx: &ToolContext) {
+        // don't do anything
+    }
+
+    pub fn cancel(&mut self) {}
+
+    pub fn cursor(&self) -> &'static str {
+        "crosshair"
+    }
+}
+
+/// RGBA pixels the wand compares, either the selected layer or every visible
+/// layer blended together.
+fn sample_pixels(ctx: &ToolContext, layer_id: usize) -> Option<(Vec<u8>, u32, u32)> {
+    let sample_merged = ctx.workspace_state.sample_merged.get_untracked();
+
+    ctx.project.with_untracked(|project| {
+        project.layers.with_untracked(|layers| {
+            if sample_merged {
+                let (rgba, width, height) = composite_layers(layers, Color::transparent());
+                return (width > 0 && height > 0).then_some((rgba, width, height));
+            }
+            layers
+                .iter()
+                .find(|l| l.id == layer_id)
+                .map(|layer| (layer.canvas.as_rgba().to_vec(), layer.canvas.width, layer.canvas.height))
+        })
+    })
+}
@@ -8,4 +8,12 @@ pub mod bucket;
 pub mod eyedropper;
 pub mod eraser;
 pub mod brush;
-pub mod line;
\ No newline at end of file
+pub mod line;
+pub mod selection_mask;
+pub mod lasso;
+pub mod magic_wand;
+pub mod gradient;
+pub mod shape;
+pub mod brush_engine;
+pub mod symmetry;
+pub mod retouch;
\ No newline at end of file
@@ -1,38 +1,42 @@
+use crate::state::workspace_state::WorkspaceState;
 use crate::structs::history::{PixelDiff, StrokeDiff};
 use crate::structs::pixel::Pixel;
-use leptos::prelude::{Get, Update};
+use crate::structs::project::Project;
+use leptos::prelude::{Get, GetUntracked, RwSignal, Update, WithUntracked};
 use serde::{Deserialize, Serialize};
 
 use crate::tools::context::ToolContext;
 use crate::tools::geometry::{draw_line, screen_to_canvas};
+use crate::tools::symmetry::SymmetryCopy;
 use web_sys::{PointerEvent};
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct PenState {
-    pub is_drawing: bool,
+    /// Layer the stroke in progress paints on, `None` between strokes.
+    pub layer_id: Option<usize>,
     pub last_pos: Option<(i32, i32)>,
     pub current_stroke: Vec<PixelDiff>,
 }
 
 impl PenState {
-    pub fn on_pointer_down(&mut self, _e: &PointerEvent, _ctx: &ToolContext) {
-        self.is_drawing = true;
+    pub fn on_pointer_down(&mut self, _e: &PointerEvent, ctx: &ToolContext) {
+        self.layer_id = ctx.selected_layer;
         self.last_pos = None;
         self.current_stroke.clear();
     }
 
     pub fn on_pointer_up(&mut self, _e: &PointerEvent, ctx: &ToolContext) {
-        self.is_drawing = false;
+        let layer_id = self.layer_id.take();
         self.last_pos = None;
 
         if self.current_stroke.is_empty() {
             return;
         }
 
-        let Some(layer_id) = ctx.selected_layer else { return; };
+        let Some(layer_id) = layer_id else { return; };
 
         ctx.project.update(|project| {
-            project.history.add(StrokeDiff {
+            project.record(StrokeDiff {
                 layer_id,
                 pixels: std::mem::take(&mut self.current_stroke),
             });
@@ -40,11 +44,7 @@ impl PenState {
     }
 
     pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
-    if !self.is_drawing {
-        return;
-    }
-
-    let Some(layer_id) = ctx.selected_layer else { return; };
+    let Some(layer_id) = self.layer_id else { return; };
 
     let (x, y) = screen_to_canvas(
         ctx.canvas,
@@ -65,6 +65,7 @@ impl PenState {
 
     let color = ctx.project.get().current_color.get();
     let current = (x, y);
+    let stroke_start = self.current_stroke.len();
 
     ctx.project.get().layers.update(|layers| {
         let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return; };
@@ -74,9 +75,11 @@ impl PenState {
         }
 
         let canvas = &mut layer.canvas;
+        let copies = ctx.workspace_state.symmetry.get_untracked().copies(canvas.width, canvas.height);
 
         let mut draw_pixel = |px: i32, py: i32| {
-            if px < 0 || py < 0 { return; }
+            // Mirrored copies can land outside the canvas
+            if px < 0 || py < 0 || px as u32 >= canvas.width || py as u32 >= canvas.height { return; }
 
             let ux = px as u32;
             let uy = py as u32;
@@ -96,23 +99,40 @@ impl PenState {
             }
         };
 
-        if let Some((lx, ly)) = self.last_pos {
-            draw_line(lx, ly, x, y, &mut draw_pixel);
-        } else {
-            draw_pixel(x, y);
+        for copy in &copies {
+            let (cx, cy) = mirror_pixel(copy, current);
+            if let Some(last) = self.last_pos {
+                let (lx, ly) = mirror_pixel(copy, last);
+                draw_line(lx, ly, cx, cy, &mut draw_pixel);
+            } else {
+                draw_pixel(cx, cy);
+            }
         }
+
+        ctx.workspace_state.mark_dirty(&self.current_stroke[stroke_start..]);
     });
 
     self.last_pos = Some(current);
 }
 
-    pub fn cancel(&mut self) {
-        self.is_drawing = false;
+    /// Abandons the stroke, putting back the pixels it painted over.
+    pub fn cancel(&mut self, project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
         self.last_pos = None;
-        self.current_stroke.clear();
+
+        let pixels = std::mem::take(&mut self.current_stroke);
+        let Some(layer_id) = self.layer_id.take() else { return; };
+        workspace_state.mark_dirty(&pixels);
+        project.with_untracked(|project| StrokeDiff { layer_id, pixels }.restore(project));
     }
 
     pub fn cursor(&self) -> &'static str {
         "default"
     }
-}
\ No newline at end of file
+}
+
+/// Maps a pixel through a symmetry copy by its center, so mirrored pixels
+/// land exactly on pixels.
+fn mirror_pixel(copy: &SymmetryCopy, (x, y): (i32, i32)) -> (i32, i32) {
+    let (mx, my) = copy.apply((x as f32 + 0.5, y as f32 + 0.5));
+    (mx.floor() as i32, my.floor() as i32)
+}
@@ -0,0 +1,317 @@
+//! Smudge, blur and sharpen: brush tools that rework the pixels already on
+//! the selected layer instead of painting a color.
+use crate::state::workspace_state::WorkspaceState;
+use crate::structs::canvas::Canvas;
+use crate::structs::color::Color;
+use crate::structs::history::{PixelDiff, StrokeDiff};
+use crate::structs::pixel::Pixel;
+use crate::structs::project::Project;
+use crate::tools::brush_engine::{tip_coverage, RADIUS_PER_SIZE};
+use crate::tools::context::ToolContext;
+use crate::tools::geometry::screen_to_canvas_f;
+use crate::tools::select::layer_is_editable;
+use leptos::prelude::{GetUntracked, RwSignal, Update, WithUntracked};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use web_sys::PointerEvent;
+
+/// Soft tips keep the edge of the retouched area invisible.
+const HARDNESS: f32 = 0.3;
+/// Distance between dabs as a fraction of the tip radius.
+const SPACING: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum RetouchKind {
+    /// Drags the colors under the tip along the stroke.
+    Smudge,
+    /// Averages every pixel with its neighbours.
+    Blur,
+    /// Pushes every pixel away from the average of its neighbours.
+    Sharpen,
+}
+
+/// Premultiplied RGBA in `0.0..=255.0`, so transparent pixels don't bleed
+/// their color into their neighbours.
+type Rgba = [f32; 4];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RetouchState {
+    kind: RetouchKind,
+    layer_id: Option<usize>,
+    /// Tip radius and strength, read when the stroke starts.
+    radius: f32,
+    strength: f32,
+    last: Option<(f32, f32)>,
+    /// Path length left before the next dab.
+    until_next_dab: f32,
+    /// Smudge only: colors picked up by the tip, one per pixel of its
+    /// bounding square. `None` where the tip was off the canvas.
+    carried: Vec<Option<Rgba>>,
+    /// Index into `pixels` of every touched pixel.
+    #[serde(skip)]
+    index: HashMap<(u32, u32), usize>,
+    pixels: Vec<PixelDiff>,
+}
+
+impl RetouchState {
+    fn new(kind: RetouchKind) -> Self {
+        Self {
+            kind,
+            layer_id: None,
+            radius: 0.0,
+            strength: 0.0,
+            last: None,
+            until_next_dab: 0.0,
+            carried: Vec::new(),
+            index: HashMap::new(),
+            pixels: Vec::new(),
+        }
+    }
+
+    pub fn smudge() -> Self {
+        Self::new(RetouchKind::Smudge)
+    }
+
+    pub fn blur() -> Self {
+        Self::new(RetouchKind::Blur)
+    }
+
+    pub fn sharpen() -> Self {
+        Self::new(RetouchKind::Sharpen)
+    }
+
+    pub fn kind(&self) -> RetouchKind {
+        self.kind
+    }
+
+    pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if e.button() != 0 || self.layer_id.is_some() {
+            return;
+        }
+        let Some(layer_id) = ctx.selected_layer else { return };
+        if !layer_is_editable(ctx.project, layer_id) {
+            return;
+        }
+
+        self.reset();
+        self.layer_id = Some(layer_id);
+        self.radius = (ctx.workspace_state.brush_size.get_untracked() * RADIUS_PER_SIZE).max(0.5);
+        self.strength = ctx.workspace_state.retouch_strength(self.kind).get_untracked().clamp(0.0, 1.0);
+        self.apply_at(e, ctx);
+    }
+
+    pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.layer_id.is_none() {
+            return;
+        }
+        self.apply_at(e, ctx);
+    }
+
+    pub fn on_pointer_up(&mut self, _e: &PointerEvent, ctx: &ToolContext) {
+        let Some(layer_id) = self.layer_id else { return };
+
+        if !self.pixels.is_empty() {
+            ctx.project.update(|project| {
+                project.record(StrokeDiff {
+                    layer_id,
+                    pixels: std::mem::take(&mut self.pixels),
+                });
+            });
+        }
+        self.reset();
+    }
+
+    /// Abandons the stroke, putting back the pixels it changed.
+    pub fn cancel(&mut self, project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
+        if let Some(layer_id) = self.layer_id {
+            let pixels = std::mem::take(&mut self.pixels);
+            workspace_state.mark_dirty(&pixels);
+            project.with_untracked(|project| StrokeDiff { layer_id, pixels }.restore(project));
+        }
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.layer_id = None;
+        self.last = None;
+        self.until_next_dab = 0.0;
+        self.carried.clear();
+        self.index.clear();
+        self.pixels.clear();
+    }
+
+    pub fn cursor(&self) -> &'static str {
+        "crosshair"
+    }
+
+    fn apply_at(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        let Some(layer_id) = self.layer_id else { return };
+        let point = screen_to_canvas_f(
+            ctx.canvas,
+            e.client_x() as f64,
+            e.client_y() as f64,
+            ctx.zoom,
+            ctx.pan_x,
+            ctx.pan_y,
+        );
+
+        ctx.project.with_untracked(|project| {
+            project.layers.update(|layers| {
+                let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return };
+                let touched = self.stroke_to(&mut layer.canvas, point);
+                ctx.workspace_state.mark_dirty(&touched);
+            });
+        });
+    }
+
+    /// Continues the stroke to `point` with dabs at regular spacing. Returns
+    /// the pixels changed by this call.
+    fn stroke_to(&mut self, canvas: &mut Canvas, point: (f32, f32)) -> Vec<PixelDiff> {
+        let mut touched = Vec::new();
+        let spacing = (self.radius * SPACING).max(0.5);
+
+        let Some(last) = self.last else {
+            self.dab(canvas, point, &mut touched);
+            self.last = Some(point);
+            self.until_next_dab = spacing;
+            return touched;
+        };
+
+        let (dx, dy) = (point.0 - last.0, point.1 - last.1);
+        let length = dx.hypot(dy);
+        let mut travelled = self.until_next_dab;
+
+        while travelled <= length {
+            let t = travelled / length;
+            self.dab(canvas, (last.0 + dx * t, last.1 + dy * t), &mut touched);
+            travelled += spacing;
+        }
+
+        self.until_next_dab = travelled - length;
+        self.last = Some(point);
+        touched
+    }
+
+    fn dab(&mut self, canvas: &mut Canvas, center: (f32, f32), touched: &mut Vec<PixelDiff>) {
+        // The tip's bounding square, anchored on the pixel under its center
+        let reach = self.radius.ceil() as i32;
+        let side = (reach * 2 + 1) as usize;
+        let (ox, oy) = (center.0.floor() as i32 - reach, center.1.floor() as i32 - reach);
+
+        // Every pixel reads the canvas as it was before this dab
+        let read = |x: i32, y: i32| {
+            if x < 0 || y < 0 {
+                return None;
+            }
+            canvas.get_rgba(x as u32, y as u32).map(premultiply)
+        };
+
+        let mut updates = Vec::new();
+        let mut carried = std::mem::take(&mut self.carried);
+        let picking_up = carried.is_empty();
+        if picking_up && self.kind == RetouchKind::Smudge {
+            carried = (0..side * side)
+                .map(|i| read(ox + (i % side) as i32, oy + (i / side) as i32))
+                .collect();
+        }
+
+        for j in 0..side {
+            for i in 0..side {
+                let (x, y) = (ox + i as i32, oy + j as i32);
+                let Some(current) = read(x, y) else { continue };
+                let distance = (x as f32 + 0.5 - center.0).hypot(y as f32 + 0.5 - center.1);
+                let amount = tip_coverage(distance, self.radius, HARDNESS) * self.strength;
+                if amount <= 0.0 {
+                    continue;
+                }
+
+                let result = match self.kind {
+                    RetouchKind::Smudge => {
+                        // The first dab only picks the colors up
+                        if picking_up {
+                            continue;
+                        }
+                        let slot = &mut carried[j * side + i];
+                        let result = match *slot {
+                            Some(paint) => mix(current, paint, amount),
+                            None => current,
+                        };
+                        // What the tip leaves behind is what it carries on
+                        *slot = Some(result);
+                        result
+                    }
+                    RetouchKind::Blur => mix(current, neighbourhood_mean(&read, x, y), amount),
+                    RetouchKind::Sharpen => {
+                        let mean = neighbourhood_mean(&read, x, y);
+                        // Premultiplied channels can't exceed alpha
+                        let sharpen = |c: usize| (current[c] + (current[c] - mean[c]) * amount).clamp(0.0, current[3]);
+                        [sharpen(0), sharpen(1), sharpen(2), current[3]]
+                    }
+                };
+                updates.push((x as u32, y as u32, unpremultiply(result)));
+            }
+        }
+        self.carried = carried;
+
+        for (x, y, rgba) in updates {
+            self.set_pixel(canvas, x, y, rgba, touched);
+        }
+    }
+
+    /// Writes one pixel, remembering its color from before the stroke.
+    fn set_pixel(&mut self, canvas: &mut Canvas, x: u32, y: u32, rgba: [u8; 4], touched: &mut Vec<PixelDiff>) {
+        let index = match self.index.get(&(x, y)) {
+            Some(index) => *index,
+            None => {
+                let Ok(before) = canvas.get_pixel(x, y) else { return };
+                self.pixels.push(PixelDiff { before, after: before });
+                self.index.insert((x, y), self.pixels.len() - 1);
+                self.pixels.len() - 1
+            }
+        };
+
+        let diff = &mut self.pixels[index];
+        let color = Color::from_rgba8(rgba);
+        if diff.after.color == color {
+            return;
+        }
+        diff.after = Pixel::new(x, y, color);
+        let _ = canvas.set_pixel(diff.after);
+        touched.push(diff.clone());
+    }
+}
+
+/// Average of the 3×3 block around `(x, y)`, ignoring pixels off the canvas.
+fn neighbourhood_mean(read: &impl Fn(i32, i32) -> Option<Rgba>, x: i32, y: i32) -> Rgba {
+    let mut sum = [0.0; 4];
+    let mut count = 0.0;
+    for ny in y - 1..=y + 1 {
+        for nx in x - 1..=x + 1 {
+            let Some(rgba) = read(nx, ny) else { continue };
+            for (total, channel) in sum.iter_mut().zip(rgba) {
+                *total += channel;
+            }
+            count += 1.0;
+        }
+    }
+    sum.map(|channel| channel / count)
+}
+
+fn mix(a: Rgba, b: Rgba, t: f32) -> Rgba {
+    let t = t.clamp(0.0, 1.0);
+    [0, 1, 2, 3].map(|c| a[c] + (b[c] - a[c]) * t)
+}
+
+fn premultiply([r, g, b, a]: [u8; 4]) -> Rgba {
+    let alpha = a as f32 / 255.0;
+    [r as f32 * alpha, g as f32 * alpha, b as f32 * alpha, a as f32]
+}
+
+fn unpremultiply([r, g, b, a]: Rgba) -> [u8; 4] {
+    if a <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let alpha = a / 255.0;
+    let channel = |value: f32| (value / alpha).round().clamp(0.0, 255.0) as u8;
+    [channel(r), channel(g), channel(b), a.round().clamp(0.0, 255.0) as u8]
+}
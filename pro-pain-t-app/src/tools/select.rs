@@ -1,9 +1,12 @@
 use crate::structs::history::{PixelDiff, StrokeDiff};
 use crate::structs::{color::Color, layer::Layer, project::Project};
+use crate::state::workspace_state::WorkspaceState;
 use crate::tools::context::ToolContext;
+use crate::tools::tools::Tool;
 use crate::tools::geometry::screen_to_canvas;
+use crate::tools::selection_mask::{combine_selections, selection_coverage, SelectionMask, SelectionOp};
 use crate::structs::pixel::Pixel;
-use leptos::prelude::{RwSignal, Set, Update, With};
+use leptos::prelude::{GetUntracked, RwSignal, Set, Update, With, WithUntracked};
 use serde::{Deserialize, Serialize};
 use web_sys::PointerEvent;
 
@@ -36,6 +39,20 @@ impl SelectionRect {
         x >= self.x && y >= self.y && x < self.x + self.w && y < self.y + self.h
     }
 
+    /// The whole document.
+    pub fn document(width: u32, height: u32) -> Self {
+        Self { x: 0, y: 0, w: width as i32, h: height as i32 }
+    }
+
+    /// The area inside both rects, empty if they don't overlap.
+    pub fn intersection(&self, other: &SelectionRect) -> Self {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w).min(other.x + other.w);
+        let y1 = (self.y + self.h).min(other.y + other.h);
+        Self { x: x0, y: y0, w: x1 - x0, h: y1 - y0 }
+    }
+
     pub fn from_points(a: (i32, i32), b: (i32, i32)) -> Self {
         let x0 = a.0.min(b.0);
         let y0 = a.1.min(b.1);
@@ -57,14 +74,38 @@ pub struct SelectionBuffer {
     pub pixels: Vec<Color>,
 }
 
+impl SelectionBuffer {
+    /// Builds a buffer from tightly packed row-major RGBA bytes.
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Self {
+        let pixels = rgba
+            .chunks_exact(4)
+            .map(|px| Color::from_rgba8([px[0], px[1], px[2], px[3]]))
+            .collect();
+        Self { width, height, pixels }
+    }
+
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|color| color.to_rgba8()).collect()
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SelectionState {
     pub layer_id: usize,
     pub rect: SelectionRect,
+    /// Shape inside `rect`, `None` selects the whole rect.
+    pub mask: Option<SelectionMask>,
     pub buffer: Option<SelectionBuffer>,
     pub original_pixels: Vec<PixelDiff>,
 }
 
+impl SelectionState {
+    /// How much of the document pixel (`x`, `y`) is selected, `0..=255`.
+    pub fn coverage(&self, x: i32, y: i32) -> u8 {
+        selection_coverage(self.rect, self.mask.as_ref(), x, y)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 enum SelectMode {
     Idle,
@@ -77,12 +118,22 @@ enum SelectMode {
     },
 }
 
-#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectShape {
+    Rectangle,
+    Ellipse,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SelectState {
     pointer_id: Option<i32>,
     mode: SelectMode,
     hover_handle: Option<ResizeHandle>,
     hover_inside: bool,
+    shape: SelectShape,
+    op: SelectionOp,
+    /// Selection the shape being drawn is combined with.
+    base: Option<(SelectionRect, Option<SelectionMask>)>,
 }
 
 impl Default for SelectState {
@@ -92,11 +143,25 @@ impl Default for SelectState {
             mode: SelectMode::Idle,
             hover_handle: None,
             hover_inside: false,
+            shape: SelectShape::Rectangle,
+            op: SelectionOp::Replace,
+            base: None,
         }
     }
 }
 
 impl SelectState {
+    pub fn ellipse() -> Self {
+        Self {
+            shape: SelectShape::Ellipse,
+            ..Self::default()
+        }
+    }
+
+    pub fn shape(&self) -> SelectShape {
+        self.shape
+    }
+
     pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
         if self.pointer_id.is_some() {
             return;
@@ -106,7 +171,7 @@ impl SelectState {
             return;
         };
 
-        if !layer_is_editable(ctx, layer_id) {
+        if !layer_is_editable(ctx.project, layer_id) {
             return;
         }
 
@@ -132,23 +197,31 @@ impl SelectState {
             ctx.workspace_state.selection.set(None);
         }
 
+        // Shift / Alt combine the new shape with the selection instead of
+        // grabbing it
+        let op = SelectionOp::from_modifiers(e.shift_key(), e.alt_key());
+
         let mut existing_rect: Option<SelectionRect> = None;
+        let mut existing_mask: Option<SelectionMask> = None;
         let mut existing_has_buffer = false;
+        let mut existing_covers = false;
         ctx.workspace_state.selection.with(|selection| {
             if let Some(sel) = selection {
                 if sel.layer_id == layer_id {
                     existing_rect = Some(sel.rect);
+                    existing_mask = sel.mask.clone();
                     existing_has_buffer = sel.buffer.is_some();
+                    existing_covers = sel.coverage(x, y) > 0;
                 }
             }
         });
 
         if let Some(rect) = existing_rect {
             let handle = handle_at(rect, x, y, ctx.zoom);
-            if handle.is_some() || rect.contains(x, y) {
+            if op == SelectionOp::Replace && (handle.is_some() || existing_covers) {
                 self.pointer_id = Some(e.pointer_id());
                 if !existing_has_buffer {
-                    let (buffer, diffs) = cut_buffer(ctx, layer_id, rect);
+                    let (buffer, diffs) = cut_buffer(ctx, layer_id, rect, existing_mask.as_ref());
                     ctx.workspace_state.selection.update(|sel| {
                         if let Some(sel) = sel.as_mut() {
                             sel.buffer = Some(buffer);
@@ -182,10 +255,20 @@ impl SelectState {
             });
         }
 
-        let rect = SelectionRect::from_points((x, y), (x, y));
+        self.op = op;
+        self.base = if op == SelectionOp::Replace {
+            None
+        } else {
+            ctx.workspace_state.selection.with_untracked(|selection| {
+                selection.as_ref().map(|sel| (sel.rect, sel.mask.clone()))
+            })
+        };
+
+        let (rect, mask) = self.created_selection((x, y), (x, y), document_rect(ctx));
         ctx.workspace_state.selection.set(Some(SelectionState {
             layer_id,
             rect,
+            mask,
             buffer: None,
             original_pixels: Vec::new(),
         }));
@@ -194,6 +277,30 @@ impl SelectState {
         self.mode = SelectMode::Creating { start: (x, y) };
     }
 
+    /// The shape dragged from `start` to `end` and cut to the `document`,
+    /// combined with `base`. An empty rect when nothing ends up selected.
+    fn created_selection(
+        &self,
+        start: (i32, i32),
+        end: (i32, i32),
+        document: SelectionRect,
+    ) -> (SelectionRect, Option<SelectionMask>) {
+        let rect = SelectionRect::from_points(start, end);
+        let shape = match self.shape {
+            SelectShape::Rectangle => Some(rect.intersection(&document)).filter(|rect| !rect.is_empty()).map(|rect| (rect, None)),
+            SelectShape::Ellipse => SelectionMask::from_ellipse(rect, document).map(|(rect, mask)| (rect, Some(mask))),
+        };
+        let base = self.base.as_ref().map(|(rect, mask)| (*rect, mask.as_ref()));
+
+        let combined = match shape {
+            Some((shape_rect, shape_mask)) => combine_selections(base, (shape_rect, shape_mask.as_ref()), self.op),
+            // An empty shape leaves unions and subtractions unchanged
+            None if matches!(self.op, SelectionOp::Union | SelectionOp::Subtract) => self.base.clone(),
+            None => None,
+        };
+        combined.unwrap_or((SelectionRect { x: start.0, y: start.1, w: 0, h: 0 }, None))
+    }
+
     pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
         let (x, y) = screen_to_canvas(
             ctx.canvas,
@@ -209,10 +316,11 @@ impl SelectState {
                 if self.pointer_id != Some(e.pointer_id()) {
                     return;
                 }
-                let rect = SelectionRect::from_points(start, (x, y));
+                let (rect, mask) = self.created_selection(start, (x, y), document_rect(ctx));
                 ctx.workspace_state.selection.update(|sel| {
                     if let Some(sel) = sel.as_mut() {
                         sel.rect = rect;
+                        sel.mask = mask;
                     }
                 });
             }
@@ -295,11 +403,13 @@ impl SelectState {
 
         self.pointer_id = None;
         self.mode = SelectMode::Idle;
+        self.base = None;
     }
 
     pub fn cancel(&mut self) {
         self.pointer_id = None;
         self.mode = SelectMode::Idle;
+        self.base = None;
     }
 
     pub fn cursor(&self) -> &'static str {
@@ -324,7 +434,7 @@ impl SelectState {
         ctx.workspace_state.selection.with(|selection| {
             if let Some(sel) = selection {
                 handle = handle_at(sel.rect, x, y, ctx.zoom);
-                inside = sel.rect.contains(x, y);
+                inside = sel.coverage(x, y) > 0;
             }
         });
         self.hover_handle = handle;
@@ -332,16 +442,23 @@ impl SelectState {
     }
 }
 
-fn layer_is_editable(ctx: &ToolContext, layer_id: usize) -> bool {
-    let mut editable = false;
-    ctx.project.with(|project| {
-        project.layers.with(|layers| {
-            if let Some(layer) = layers.iter().find(|l| l.id == layer_id) {
-                editable = !layer.is_locked && layer.is_visible;
-            }
-        });
-    });
-    editable
+/// Whether tools may change the layer's pixels: it exists, isn't locked and
+/// is visible.
+pub fn layer_is_editable(project: &RwSignal<Project>, layer_id: usize) -> bool {
+    project.with_untracked(|project| {
+        project.layers.with_untracked(|layers| {
+            layers
+                .iter()
+                .any(|l| l.id == layer_id && !l.is_locked && l.is_visible)
+        })
+    })
+}
+
+/// The whole document, which new selections are cut to.
+pub fn document_rect(ctx: &ToolContext) -> SelectionRect {
+    ctx.project.with_untracked(|project| {
+        SelectionRect::document(project.width.get_untracked(), project.height.get_untracked())
+    })
 }
 
 fn handle_at(rect: SelectionRect, x: i32, y: i32, zoom: f32) -> Option<ResizeHandle> {
@@ -399,7 +516,12 @@ fn cursor_for_handle(handle: ResizeHandle) -> &'static str {
     }
 }
 
-fn cut_buffer(ctx: &ToolContext, layer_id: usize, rect: SelectionRect) -> (SelectionBuffer, Vec<PixelDiff>) {
+fn cut_buffer(
+    ctx: &ToolContext,
+    layer_id: usize,
+    rect: SelectionRect,
+    mask: Option<&SelectionMask>,
+) -> (SelectionBuffer, Vec<PixelDiff>) {
     let mut buffer = SelectionBuffer {
         width: rect.w.max(1) as u32,
         height: rect.h.max(1) as u32,
@@ -414,16 +536,17 @@ fn cut_buffer(ctx: &ToolContext, layer_id: usize, rect: SelectionRect) -> (Selec
                 return;
             };
 
-            buffer = extract_buffer_from_layer(layer, rect);
+            buffer = extract_buffer_from_layer(layer, rect, mask);
 
-            clear_rect(layer, rect, &mut diffs);
+            clear_rect(layer, rect, mask, &mut diffs);
         });
     });
 
     (buffer, diffs)
 }
 
-fn extract_buffer_from_layer(layer: &mut Layer, rect: SelectionRect) -> SelectionBuffer {
+/// Pixels of `rect`, with their alpha scaled by the mask coverage.
+fn extract_buffer_from_layer(layer: &Layer, rect: SelectionRect, mask: Option<&SelectionMask>) -> SelectionBuffer {
     let width = rect.w.max(1) as u32;
     let height = rect.h.max(1) as u32;
     let mut pixels = Vec::with_capacity((width * height) as usize);
@@ -440,9 +563,13 @@ fn extract_buffer_from_layer(layer: &mut Layer, rect: SelectionRect) -> Selectio
                 pixels.push(transparent_color());
                 continue;
             }
-            let idx = (py as u32 * layer.canvas.width + px as u32) as usize;
-            let color = layer.canvas.content[idx].color;
-            pixels.push(color);
+            let coverage = selection_coverage(rect, mask, px, py);
+            let color = layer
+                .canvas
+                .get_rgba(px as u32, py as u32)
+                .map(Color::from_rgba8)
+                .map(|color| Color { alpha: color.alpha * coverage as f32 / 255.0, ..color });
+            pixels.push(color.unwrap_or_else(transparent_color));
         }
     }
 
@@ -456,36 +583,224 @@ fn extract_buffer_from_layer(layer: &mut Layer, rect: SelectionRect) -> Selectio
 pub fn commit_selection(project: &RwSignal<Project>, selection: &SelectionState) {
     let Some(buffer) = selection.buffer.as_ref() else { return; };
     let rect = selection.rect;
+    let mask = selection.mask.as_ref();
     let layer_id = selection.layer_id;
     let sel = selection.clone();
 
     project.update(|project| {
+        let mut diffs = Vec::new();
+
         project.layers.update(|layers| {
             let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else {
                 return;
             };
 
-            let mut diffs = Vec::new();
+            // Lifting comes first, so where the moved pixels overlap their old
+            // place the recorded `before` is the original color
+            diffs.extend(sel.original_pixels.clone());
 
-            clear_rect(layer, rect, &mut diffs);
+            apply_buffer(layer, rect, buffer, mask, &mut diffs);
+        });
 
-            apply_buffer(layer, rect, buffer, &mut diffs);
+        if !diffs.is_empty() {
+            project.record(StrokeDiff {
+                layer_id,
+                pixels: diffs,
+            });
+        }
+    });
+}
 
-            diffs.extend(sel.original_pixels.clone());
+/// Pixels for the clipboard: the floating selection, the selected area or,
+/// without a selection, the whole selected layer.
+pub fn copy_selection(project: &RwSignal<Project>, workspace_state: &WorkspaceState) -> Option<SelectionBuffer> {
+    let floating = workspace_state.selection.with_untracked(|selection| {
+        selection
+            .as_ref()
+            .map(|sel| (sel.layer_id, sel.rect, sel.mask.clone(), sel.buffer.clone()))
+    });
 
-            if !diffs.is_empty() {
-                project.history.add(StrokeDiff {
-                    layer_id,
-                    pixels: diffs,
-                });
+    let (layer_id, rect, mask) = match floating {
+        Some((_, _, _, Some(buffer))) => return Some(buffer),
+        Some((layer_id, rect, mask, None)) => (layer_id, rect, mask),
+        None => {
+            let layer_id = workspace_state.selected_layer_id.get_untracked()?;
+            let rect = project.with_untracked(|project| SelectionRect {
+                x: 0,
+                y: 0,
+                w: project.width.get_untracked() as i32,
+                h: project.height.get_untracked() as i32,
+            });
+            (layer_id, rect, None)
+        }
+    };
+
+    project.with_untracked(|project| {
+        project.layers.with_untracked(|layers| {
+            layers
+                .iter()
+                .find(|l| l.id == layer_id)
+                .map(|layer| extract_buffer_from_layer(layer, rect, mask.as_ref()))
+        })
+    })
+}
+
+/// Copies the selection and removes its pixels from the layer as one undo step.
+/// Does nothing without a selection.
+pub fn cut_selection(project: &RwSignal<Project>, workspace_state: &WorkspaceState) -> Option<SelectionBuffer> {
+    if workspace_state.selection.with_untracked(|selection| selection.is_none()) {
+        return None;
+    }
+    let buffer = copy_selection(project, workspace_state)?;
+
+    delete_selection(project, workspace_state);
+    workspace_state.selection.set(None);
+
+    Some(buffer)
+}
+
+/// Removes the selected pixels from the layer as one undo step, the outline
+/// stays selected. Does nothing without a selection or on a locked layer.
+pub fn delete_selection(project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
+    let Some(selection) = workspace_state.selection.get_untracked() else { return };
+    if !layer_is_editable(project, selection.layer_id) {
+        return;
+    }
+
+    project.update(|project| {
+        // A floating selection has already been lifted off the layer, only the
+        // clearing is left to record
+        let mut diffs = selection.original_pixels.clone();
+
+        if selection.buffer.is_none() {
+            project.layers.update(|layers| {
+                if let Some(layer) = layers.iter_mut().find(|l| l.id == selection.layer_id) {
+                    clear_rect(layer, selection.rect, selection.mask.as_ref(), &mut diffs);
+                }
+            });
+        }
+
+        if !diffs.is_empty() {
+            project.record(StrokeDiff {
+                layer_id: selection.layer_id,
+                pixels: diffs,
+            });
+        }
+    });
+    workspace_state.selection.update(|selection| {
+        if let Some(sel) = selection.as_mut() {
+            sel.buffer = None;
+            sel.original_pixels.clear();
+        }
+    });
+}
+
+/// Paints `color` over the selected pixels as one undo step, partially
+/// covered pixels are blended. Does nothing without a selection.
+pub fn fill_selection(project: &RwSignal<Project>, workspace_state: &WorkspaceState, color: Color) {
+    workspace_state.settle_selection(project);
+    let Some((layer_id, rect, mask)) = workspace_state.selection.with_untracked(|selection| {
+        selection.as_ref().map(|sel| (sel.layer_id, sel.rect, sel.mask.clone()))
+    }) else {
+        return;
+    };
+    if !layer_is_editable(project, layer_id) {
+        return;
+    }
+
+    let fill = SelectionBuffer {
+        width: rect.w.max(1) as u32,
+        height: rect.h.max(1) as u32,
+        pixels: vec![color; (rect.w.max(1) * rect.h.max(1)) as usize],
+    };
+
+    project.update(|project| {
+        let mut diffs = Vec::new();
+
+        project.layers.update(|layers| {
+            if let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) {
+                apply_buffer(layer, rect, &fill, mask.as_ref(), &mut diffs);
             }
         });
+
+        if !diffs.is_empty() {
+            workspace_state.mark_dirty(&diffs);
+            project.record(StrokeDiff {
+                layer_id,
+                pixels: diffs,
+            });
+        }
     });
 }
 
+/// Combines a shape drawn on `layer_id` with the current selection of that
+/// layer and makes the result the selection. `shape` is `None` when the shape
+/// covers no pixel.
+pub fn combine_into_selection(
+    workspace_state: &WorkspaceState,
+    layer_id: usize,
+    shape: Option<(SelectionRect, SelectionMask)>,
+    op: SelectionOp,
+) {
+    let base = workspace_state.selection.with_untracked(|selection| {
+        selection
+            .as_ref()
+            .filter(|sel| sel.layer_id == layer_id)
+            .map(|sel| (sel.rect, sel.mask.clone()))
+    });
+    let base_ref = base.as_ref().map(|(rect, mask)| (*rect, mask.as_ref()));
+
+    let combined = match &shape {
+        Some((rect, mask)) => combine_selections(base_ref, (*rect, Some(mask)), op),
+        // An empty shape leaves unions and subtractions unchanged
+        None if matches!(op, SelectionOp::Union | SelectionOp::Subtract) => base,
+        None => None,
+    };
+
+    workspace_state.selection.set(combined.map(|(rect, mask)| SelectionState {
+        layer_id,
+        rect,
+        mask,
+        buffer: None,
+        original_pixels: Vec::new(),
+    }));
+}
+
+/// Places `buffer` as a floating selection centered on the document, ready to
+/// be moved with the select tool until `commit_selection`.
+pub fn paste_as_floating(
+    project: &RwSignal<Project>,
+    workspace_state: &WorkspaceState,
+    layer_id: usize,
+    buffer: SelectionBuffer,
+) {
+    workspace_state.set_tool(Tool::Select(SelectState::default()), project);
+
+    let (project_w, project_h) = project.with_untracked(|project| {
+        (project.width.get_untracked() as i32, project.height.get_untracked() as i32)
+    });
+    let rect = SelectionRect {
+        x: (project_w - buffer.width as i32) / 2,
+        y: (project_h - buffer.height as i32) / 2,
+        w: buffer.width as i32,
+        h: buffer.height as i32,
+    };
+
+    workspace_state.selected_layer_id.set(Some(layer_id));
+    workspace_state.selection.set(Some(SelectionState {
+        layer_id,
+        rect,
+        mask: None,
+        buffer: Some(buffer),
+        original_pixels: Vec::new(),
+    }));
+}
+
+/// Clears the selected pixels, partially covered ones lose part of their alpha.
 fn clear_rect(
     layer: &mut Layer,
     rect: SelectionRect,
+    mask: Option<&SelectionMask>,
     diffs: &mut Vec<PixelDiff>,
 ) {
     let width = rect.w.max(1) as u32;
@@ -500,27 +815,34 @@ fn clear_rect(
 
             let ux = px as u32;
             let uy = py as u32;
-            let idx = (uy * layer.canvas.width + ux) as usize;
 
-            let before = layer.canvas.content[idx].clone();
-            let after = Color { r:0,g:0,b:0, alpha:0.0 };
+            let coverage = selection_coverage(rect, mask, px, py);
+            if coverage == 0 { continue; }
+
+            let Ok(before) = layer.canvas.get_pixel(ux, uy) else { continue; };
+            let after = if coverage == 255 {
+                Color { r:0,g:0,b:0, alpha:0.0 }
+            } else {
+                Color { alpha: before.color.alpha * (1.0 - coverage as f32 / 255.0), ..before.color }
+            };
 
             if before.color == after { continue; }
 
-            diffs.push(PixelDiff {
-                before: before.clone().into(),
-                after: Pixel::new(ux, uy, after),
-            });
+            let after = Pixel::new(ux, uy, after);
+            diffs.push(PixelDiff { before, after });
 
-            layer.canvas.content[idx].color = after;
+            let _ = layer.canvas.set_pixel(after);
         }
     }
 }
 
+/// Writes the buffer over the selected pixels, blending where the mask only
+/// partially covers them.
 fn apply_buffer(
     layer: &mut Layer,
     rect: SelectionRect,
     buffer: &SelectionBuffer,
+    mask: Option<&SelectionMask>,
     diffs: &mut Vec<PixelDiff>,
 ) {
     let width = rect.w.max(1) as u32;
@@ -535,20 +857,25 @@ fn apply_buffer(
 
             let ux = px as u32;
             let uy = py as u32;
-            let idx = (uy * layer.canvas.width + ux) as usize;
             let src_idx = (y * buffer.width + x) as usize;
 
+            let coverage = selection_coverage(rect, mask, px, py);
+            if coverage == 0 { continue; }
+
             let new_color = buffer.pixels.get(src_idx).copied().unwrap_or_else(transparent_color);
-            let before = layer.canvas.content[idx].clone();
+            let Ok(before) = layer.canvas.get_pixel(ux, uy) else { continue; };
+            let new_color = if coverage == 255 {
+                new_color
+            } else {
+                before.color.lerp(new_color, coverage as f32 / 255.0)
+            };
 
             if before.color == new_color { continue; }
 
-            diffs.push(PixelDiff {
-                before: before.clone().into(),
-                after: Pixel::new(ux, uy, new_color),
-            });
+            let after = Pixel::new(ux, uy, new_color);
+            diffs.push(PixelDiff { before, after });
 
-            layer.canvas.content[idx].color = new_color;
+            let _ = layer.canvas.set_pixel(after);
         }
     }
 }
@@ -635,3 +962,45 @@ fn transparent_color() -> Color {
         alpha: 0.0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::history::History;
+
+    #[test]
+    fn undoing_an_overlapping_move_restores_the_original_pixels() {
+        let red = Color::new(255, 0, 0, 1.0);
+        let green = Color::new(0, 255, 0, 1.0);
+        let project = RwSignal::new(Project::new("Test".to_string(), 4, 1, Color::default_white()));
+        let rect = SelectionRect { x: 0, y: 0, w: 2, h: 1 };
+
+        // Lift a red and a green pixel off the layer like the select tool does
+        let mut original_pixels = Vec::new();
+        project.with_untracked(|project| {
+            project.layers.update(|layers| {
+                let _ = layers[0].canvas.set_pixel(Pixel::new(0, 0, red));
+                let _ = layers[0].canvas.set_pixel(Pixel::new(1, 0, green));
+                clear_rect(&mut layers[0], rect, None, &mut original_pixels);
+            });
+        });
+
+        // Dropped one pixel to the right, over half of where they came from
+        let selection = SelectionState {
+            layer_id: 0,
+            rect: SelectionRect { x: 1, ..rect },
+            mask: None,
+            buffer: Some(SelectionBuffer { width: 2, height: 1, pixels: vec![red, green] }),
+            original_pixels,
+        };
+        commit_selection(&project, &selection);
+        History::apply_undo(&project, &WorkspaceState::default());
+
+        let colors: Vec<Color> = project.with_untracked(|project| {
+            project.layers.with_untracked(|layers| {
+                (0..4).map(|x| layers[0].canvas.get_pixel(x, 0).unwrap().color).collect()
+            })
+        });
+        assert_eq!(colors, vec![red, green, Color::transparent(), Color::transparent()]);
+    }
+}
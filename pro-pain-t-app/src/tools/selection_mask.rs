@@ -0,0 +1,303 @@
+use crate::tools::select::SelectionRect;
+use serde::{Deserialize, Serialize};
+
+/// How a newly drawn shape is combined with the current selection.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum SelectionOp {
+    Replace,
+    Union,
+    Subtract,
+    Intersect,
+}
+
+impl SelectionOp {
+    /// Shift adds, Alt subtracts and both together intersect.
+    pub fn from_modifiers(shift: bool, alt: bool) -> Self {
+        match (shift, alt) {
+            (false, false) => SelectionOp::Replace,
+            (true, false) => SelectionOp::Union,
+            (false, true) => SelectionOp::Subtract,
+            (true, true) => SelectionOp::Intersect,
+        }
+    }
+}
+
+/// Per-pixel coverage of a selection, `0` is outside and `255` fully inside.
+///
+/// The mask is stored relative to its `SelectionRect` and stretched over it
+/// when sampled, so moving or resizing the rect moves or scales the mask too.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SelectionMask {
+    pub width: u32,
+    pub height: u32,
+    coverage: Vec<u8>,
+    /// Boundary between covered and uncovered pixels as `[x0, y0, x1, y1]`
+    /// segments in mask coordinates, drawn as marching ants.
+    outline: Vec<[u32; 4]>,
+}
+
+impl SelectionMask {
+    pub fn new(width: u32, height: u32, coverage: Vec<u8>) -> Self {
+        let outline = trace_outline(width, height, &coverage);
+        Self { width, height, coverage, outline }
+    }
+
+    /// Polygon through `points` (document coordinates, implicitly closed),
+    /// filled with the even-odd rule at pixel centers and cut to `bounds`.
+    /// `None` when it covers no pixel.
+    pub fn from_polygon(points: &[(f32, f32)], bounds: SelectionRect) -> Option<(SelectionRect, SelectionMask)> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        let min_x = points.iter().map(|p| p.0).fold(f32::INFINITY, f32::min).floor() as i32;
+        let min_y = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min).floor() as i32;
+        let max_x = points.iter().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+        let max_y = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+        let rect = SelectionRect { x: min_x, y: min_y, w: max_x - min_x + 1, h: max_y - min_y + 1 }.intersection(&bounds);
+        if rect.is_empty() {
+            return None;
+        }
+
+        let width = rect.w as u32;
+        let height = rect.h as u32;
+        let mut coverage = vec![0u8; (width * height) as usize];
+        let mut crossings = Vec::new();
+
+        for row in 0..height {
+            let sample_y = (rect.y + row as i32) as f32 + 0.5;
+            crossings.clear();
+            for i in 0..points.len() {
+                let (x0, y0) = points[i];
+                let (x1, y1) = points[(i + 1) % points.len()];
+                if (y0 <= sample_y) != (y1 <= sample_y) {
+                    crossings.push(x0 + (sample_y - y0) / (y1 - y0) * (x1 - x0));
+                }
+            }
+            crossings.sort_by(|a, b| a.total_cmp(b));
+
+            for pair in crossings.chunks_exact(2) {
+                // Pixels whose centers lie between the two crossings
+                let start = ((pair[0] - 0.5).ceil() as i32 - rect.x).max(0);
+                let end = ((pair[1] - 0.5).floor() as i32 - rect.x).min(rect.w - 1);
+                for col in start..=end {
+                    coverage[(row * width) as usize + col as usize] = 255;
+                }
+            }
+        }
+
+        Self::new(width, height, coverage).trimmed(rect)
+    }
+
+    /// Ellipse inscribed in `rect`, cut to `bounds`.
+    pub fn from_ellipse(rect: SelectionRect, bounds: SelectionRect) -> Option<(SelectionRect, SelectionMask)> {
+        let area = rect.intersection(&bounds);
+        if rect.is_empty() || area.is_empty() {
+            return None;
+        }
+
+        let width = area.w as u32;
+        let height = area.h as u32;
+        let rx = rect.w as f32 / 2.0;
+        let ry = rect.h as f32 / 2.0;
+        // Offset of the visible area inside the ellipse's rect
+        let (ox, oy) = ((area.x - rect.x) as f32, (area.y - rect.y) as f32);
+        let mut coverage = vec![0u8; (width * height) as usize];
+
+        for row in 0..height {
+            for col in 0..width {
+                let dx = (ox + col as f32 + 0.5 - rx) / rx;
+                let dy = (oy + row as f32 + 0.5 - ry) / ry;
+                if dx * dx + dy * dy <= 1.0 {
+                    coverage[(row * width + col) as usize] = 255;
+                }
+            }
+        }
+
+        Self::new(width, height, coverage).trimmed(area)
+    }
+
+    /// Mask from a `rect.w * rect.h` coverage map, shrunk to the covered
+    /// pixels. `None` if nothing is covered.
+    pub fn from_coverage(rect: SelectionRect, coverage: Vec<u8>) -> Option<(SelectionRect, SelectionMask)> {
+        if rect.is_empty() || coverage.len() != (rect.w * rect.h) as usize {
+            return None;
+        }
+        Self::new(rect.w as u32, rect.h as u32, coverage).trimmed(rect)
+    }
+
+    /// Coverage of the document pixel (`x`, `y`) with the mask stretched over `rect`.
+    pub fn coverage_at(&self, rect: SelectionRect, x: i32, y: i32) -> u8 {
+        if !rect.contains(x, y) || self.width == 0 || self.height == 0 {
+            return 0;
+        }
+        let col = ((x - rect.x) as u64 * self.width as u64 / rect.w as u64) as u32;
+        let row = ((y - rect.y) as u64 * self.height as u64 / rect.h as u64) as u32;
+        self.coverage[(row * self.width + col) as usize]
+    }
+
+    pub fn outline(&self) -> &[[u32; 4]] {
+        &self.outline
+    }
+
+    /// Shrinks the mask to its covered pixels, `None` if nothing is covered.
+    fn trimmed(self, rect: SelectionRect) -> Option<(SelectionRect, SelectionMask)> {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.coverage[(row * self.width + col) as usize] > 0 {
+                    min_x = min_x.min(col);
+                    min_y = min_y.min(row);
+                    max_x = max_x.max(col);
+                    max_y = max_y.max(row);
+                }
+            }
+        }
+        if min_x > max_x || min_y > max_y {
+            return None;
+        }
+        if min_x == 0 && min_y == 0 && max_x == self.width - 1 && max_y == self.height - 1 {
+            return Some((rect, self));
+        }
+
+        let width = max_x - min_x + 1;
+        let height = max_y - min_y + 1;
+        let mut coverage = Vec::with_capacity((width * height) as usize);
+        for row in min_y..=max_y {
+            let start = (row * self.width + min_x) as usize;
+            coverage.extend_from_slice(&self.coverage[start..start + width as usize]);
+        }
+
+        let rect = SelectionRect {
+            x: rect.x + min_x as i32,
+            y: rect.y + min_y as i32,
+            w: width as i32,
+            h: height as i32,
+        };
+        Some((rect, Self::new(width, height, coverage)))
+    }
+}
+
+/// Coverage of a selection that may or may not have a mask.
+pub fn selection_coverage(rect: SelectionRect, mask: Option<&SelectionMask>, x: i32, y: i32) -> u8 {
+    match mask {
+        Some(mask) => mask.coverage_at(rect, x, y),
+        None if rect.contains(x, y) => 255,
+        None => 0,
+    }
+}
+
+/// Combines the current selection (`base`) with a new `shape`. Returns the
+/// bounds and mask of the result, `None` when nothing stays selected.
+pub fn combine_selections(
+    base: Option<(SelectionRect, Option<&SelectionMask>)>,
+    shape: (SelectionRect, Option<&SelectionMask>),
+    op: SelectionOp,
+) -> Option<(SelectionRect, Option<SelectionMask>)> {
+    let (shape_rect, shape_mask) = shape;
+    let Some((base_rect, base_mask)) = base.filter(|_| op != SelectionOp::Replace) else {
+        return (!shape_rect.is_empty()).then(|| (shape_rect, shape_mask.cloned()));
+    };
+
+    let bounds = match op {
+        SelectionOp::Union => SelectionRect::from_points(
+            (base_rect.x.min(shape_rect.x), base_rect.y.min(shape_rect.y)),
+            (
+                (base_rect.x + base_rect.w).max(shape_rect.x + shape_rect.w) - 1,
+                (base_rect.y + base_rect.h).max(shape_rect.y + shape_rect.h) - 1,
+            ),
+        ),
+        SelectionOp::Subtract | SelectionOp::Replace => base_rect,
+        SelectionOp::Intersect => base_rect.intersection(&shape_rect),
+    };
+    if bounds.is_empty() {
+        return None;
+    }
+
+    let mut coverage = Vec::with_capacity((bounds.w * bounds.h) as usize);
+    for y in bounds.y..bounds.y + bounds.h {
+        for x in bounds.x..bounds.x + bounds.w {
+            let a = selection_coverage(base_rect, base_mask, x, y);
+            let b = selection_coverage(shape_rect, shape_mask, x, y);
+            coverage.push(match op {
+                SelectionOp::Union => a.max(b),
+                SelectionOp::Subtract => a.min(255 - b),
+                SelectionOp::Intersect => a.min(b),
+                SelectionOp::Replace => b,
+            });
+        }
+    }
+
+    SelectionMask::new(bounds.w as u32, bounds.h as u32, coverage)
+        .trimmed(bounds)
+        .map(|(rect, mask)| (rect, Some(mask)))
+}
+
+/// Edges between covered and uncovered pixels, neighbouring edges on the
+/// same line merged into one segment.
+fn trace_outline(width: u32, height: u32, coverage: &[u8]) -> Vec<[u32; 4]> {
+    let covered = |col: i64, row: i64| {
+        col >= 0
+            && row >= 0
+            && col < width as i64
+            && row < height as i64
+            && coverage[(row as u32 * width + col as u32) as usize] > 0
+    };
+    let mut segments = Vec::new();
+
+    // Horizontal edges above each row (and below the last one)
+    for row in 0..=height as i64 {
+        let mut run_start = None;
+        for col in 0..=width as i64 {
+            let is_edge = col < width as i64 && covered(col, row - 1) != covered(col, row);
+            match (is_edge, run_start) {
+                (true, None) => run_start = Some(col),
+                (false, Some(start)) => {
+                    segments.push([start as u32, row as u32, col as u32, row as u32]);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Vertical edges left of each column (and right of the last one)
+    for col in 0..=width as i64 {
+        let mut run_start = None;
+        for row in 0..=height as i64 {
+            let is_edge = row < height as i64 && covered(col - 1, row) != covered(col, row);
+            match (is_edge, run_start) {
+                (true, None) => run_start = Some(row),
+                (false, Some(start)) => {
+                    segments.push([col as u32, start as u32, col as u32, row as u32]);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_reaching_far_off_the_document_are_cut_to_it() {
+        let document = SelectionRect::document(10, 10);
+        let huge = [(-1.0e6, -1.0e6), (1.0e6, -1.0e6), (1.0e6, 1.0e6), (-1.0e6, 1.0e6)];
+
+        let (rect, mask) = SelectionMask::from_polygon(&huge, document).unwrap();
+        assert_eq!(rect, document);
+        assert_eq!((mask.width, mask.height), (10, 10));
+
+        let (rect, _) = SelectionMask::from_ellipse(SelectionRect { x: -1_000_000, y: 0, w: 2_000_010, h: 10 }, document).unwrap();
+        assert_eq!(rect, document);
+
+        let outside = [(20.0, 20.0), (30.0, 20.0), (30.0, 30.0)];
+        assert!(SelectionMask::from_polygon(&outside, document).is_none());
+        assert!(SelectionMask::from_ellipse(SelectionRect { x: 20, y: 20, w: 5, h: 5 }, document).is_none());
+    }
+}
@@ -0,0 +1,277 @@
+//! Rectangle, ellipse and regular polygon tools.
+use crate::structs::canvas::Canvas;
+use crate::structs::color::Color;
+use crate::structs::history::{PixelDiff, StrokeDiff};
+use crate::structs::pixel::Pixel;
+use crate::tools::context::ToolContext;
+use crate::tools::geometry::{draw_line, screen_to_canvas_f};
+use crate::tools::select::{layer_is_editable, SelectionRect};
+use crate::tools::selection_mask::SelectionMask;
+use leptos::prelude::{GetUntracked, Update, WithUntracked};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, TAU};
+use web_sys::PointerEvent;
+
+/// Segments used to approximate an ellipse.
+const ELLIPSE_SEGMENTS: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum ShapeKind {
+    Rectangle,
+    Ellipse,
+    Polygon,
+}
+
+/// Which parts of a shape are painted. With both, the outline takes the
+/// current color and the inside the secondary one.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum ShapeStyle {
+    Outline,
+    Fill,
+    Both,
+}
+
+impl ShapeStyle {
+    pub const ALL: [ShapeStyle; 3] = [ShapeStyle::Outline, ShapeStyle::Fill, ShapeStyle::Both];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShapeStyle::Outline => "Outline",
+            ShapeStyle::Fill => "Fill",
+            ShapeStyle::Both => "Outline and fill",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShapeState {
+    kind: ShapeKind,
+    pointer_id: Option<i32>,
+    layer_id: Option<usize>,
+    /// Corners of the dragged box in document coordinates.
+    start: Option<(f32, f32)>,
+    end: Option<(f32, f32)>,
+    /// Polygon sides, read when the drag starts.
+    sides: u32,
+}
+
+impl ShapeState {
+    fn new(kind: ShapeKind) -> Self {
+        Self {
+            kind,
+            pointer_id: None,
+            layer_id: None,
+            start: None,
+            end: None,
+            sides: 5,
+        }
+    }
+
+    pub fn rectangle() -> Self {
+        Self::new(ShapeKind::Rectangle)
+    }
+
+    pub fn ellipse() -> Self {
+        Self::new(ShapeKind::Ellipse)
+    }
+
+    pub fn polygon() -> Self {
+        Self::new(ShapeKind::Polygon)
+    }
+
+    pub fn kind(&self) -> ShapeKind {
+        self.kind
+    }
+
+    pub fn on_pointer_down(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if e.button() != 0 || self.pointer_id.is_some() {
+            return;
+        }
+        let Some(layer_id) = ctx.selected_layer else { return };
+        if !layer_is_editable(ctx.project, layer_id) {
+            return;
+        }
+
+        let point = pointer_position(e, ctx);
+        self.pointer_id = Some(e.pointer_id());
+        self.layer_id = Some(layer_id);
+        self.start = Some(point);
+        self.end = Some(point);
+        self.sides = ctx.workspace_state.polygon_sides.get_untracked().max(3);
+    }
+
+    pub fn on_pointer_move(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        let Some(start) = self.start else { return };
+
+        // Shift turns the box into a square
+        let point = pointer_position(e, ctx);
+        self.end = Some(if e.shift_key() { square_corner(start, point) } else { point });
+    }
+
+    pub fn on_pointer_up(&mut self, e: &PointerEvent, ctx: &ToolContext) {
+        if self.pointer_id != Some(e.pointer_id()) {
+            return;
+        }
+        if let (Some(layer_id), Some(vertices)) = (self.layer_id, self.vertices()) {
+            draw_shape(ctx, layer_id, &vertices);
+        }
+        self.cancel();
+    }
+
+    pub fn cancel(&mut self) {
+        self.pointer_id = None;
+        self.layer_id = None;
+        self.start = None;
+        self.end = None;
+    }
+
+    pub fn cursor(&self) -> &'static str {
+        "crosshair"
+    }
+
+    /// Outline of the shape being dragged, closed.
+    pub fn preview_path(&self) -> Option<Vec<(f32, f32)>> {
+        let mut path = self.vertices()?;
+        path.push(path[0]);
+        Some(path)
+    }
+
+    /// Corners of the shape fitted into the dragged box.
+    fn vertices(&self) -> Option<Vec<(f32, f32)>> {
+        let (start, end) = (self.start?, self.end?);
+        let (x0, x1) = (start.0.min(end.0), start.0.max(end.0));
+        let (y0, y1) = (start.1.min(end.1), start.1.max(end.1));
+        let center = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+        let (rx, ry) = ((x1 - x0) / 2.0, (y1 - y0) / 2.0);
+
+        // Points on the ellipse inscribed in the box, starting at the top
+        let around = |count: usize| {
+            (0..count)
+                .map(|i| {
+                    let angle = i as f32 * TAU / count as f32 - FRAC_PI_2;
+                    (center.0 + rx * angle.cos(), center.1 + ry * angle.sin())
+                })
+                .collect()
+        };
+
+        Some(match self.kind {
+            ShapeKind::Rectangle => vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+            ShapeKind::Ellipse => around(ELLIPSE_SEGMENTS),
+            ShapeKind::Polygon => around(self.sides as usize),
+        })
+    }
+}
+
+fn pointer_position(e: &PointerEvent, ctx: &ToolContext) -> (f32, f32) {
+    screen_to_canvas_f(
+        ctx.canvas,
+        e.client_x() as f64,
+        e.client_y() as f64,
+        ctx.zoom,
+        ctx.pan_x,
+        ctx.pan_y,
+    )
+}
+
+/// Moves `end` so the box from `start` is square, keeping the drag direction.
+fn square_corner(start: (f32, f32), end: (f32, f32)) -> (f32, f32) {
+    let dx = end.0 - start.0;
+    let dy = end.1 - start.1;
+    let side = dx.abs().max(dy.abs());
+    (start.0 + side.copysign(dx), start.1 + side.copysign(dy))
+}
+
+/// Paints the polygon through `vertices` as one undo step, in the style and
+/// stroke width of the workspace.
+fn draw_shape(ctx: &ToolContext, layer_id: usize, vertices: &[(f32, f32)]) {
+    let style = ctx.workspace_state.shape_style.get_untracked();
+    let width = ctx.workspace_state.brush_size.get_untracked().max(0.1);
+    let current = ctx.project.with_untracked(|project| project.current_color.get_untracked());
+    let (outline, fill) = match style {
+        ShapeStyle::Outline => (Some(current), None),
+        ShapeStyle::Fill => (None, Some(current)),
+        ShapeStyle::Both => (Some(current), Some(ctx.workspace_state.secondary_color.get_untracked())),
+    };
+
+    let mut diffs = Vec::new();
+
+    ctx.project.update(|project| {
+        project.layers.update(|layers| {
+            let Some(layer) = layers.iter_mut().find(|l| l.id == layer_id) else { return };
+            let canvas = &mut layer.canvas;
+
+            if let Some(color) = fill {
+                fill_polygon(canvas, vertices, color, &mut diffs);
+            }
+            if let Some(color) = outline {
+                stroke_polygon(canvas, vertices, width, color, &mut diffs);
+            }
+
+            ctx.workspace_state.mark_dirty(&diffs);
+        });
+
+        if !diffs.is_empty() {
+            project.record(StrokeDiff {
+                layer_id,
+                pixels: std::mem::take(&mut diffs),
+            });
+        }
+    });
+}
+
+/// Paints every pixel whose center lies inside the polygon.
+pub fn fill_polygon(canvas: &mut Canvas, vertices: &[(f32, f32)], color: Color, diffs: &mut Vec<PixelDiff>) {
+    let bounds = SelectionRect::document(canvas.width, canvas.height);
+    let Some((rect, mask)) = SelectionMask::from_polygon(vertices, bounds) else { return };
+
+    for y in rect.y..rect.y + rect.h {
+        for x in rect.x..rect.x + rect.w {
+            if mask.coverage_at(rect, x, y) > 0 {
+                set_pixel(canvas, x, y, color, diffs);
+            }
+        }
+    }
+}
+
+/// Closed outline of `width` pixels centered on the polygon's edges.
+fn stroke_polygon(canvas: &mut Canvas, vertices: &[(f32, f32)], width: f32, color: Color, diffs: &mut Vec<PixelDiff>) {
+    let radius = width / 2.0;
+    let mut plot_pixel = |px: i32, py: i32| {
+        for dy in -(radius as i32)..=(radius as i32) {
+            for dx in -(radius as i32)..=(radius as i32) {
+                let dist = ((dx as f32).powi(2) + (dy as f32).powi(2)).sqrt();
+                if dist > radius { continue; }
+                set_pixel(canvas, px + dx, py + dy, color, diffs);
+            }
+        }
+    };
+
+    for (i, start) in vertices.iter().enumerate() {
+        let end = vertices[(i + 1) % vertices.len()];
+        draw_line(
+            start.0.floor() as i32,
+            start.1.floor() as i32,
+            end.0.floor() as i32,
+            end.1.floor() as i32,
+            &mut plot_pixel,
+        );
+    }
+}
+
+/// Sets one pixel, recording the change unless it already had `color`.
+pub fn set_pixel(canvas: &mut Canvas, x: i32, y: i32, color: Color, diffs: &mut Vec<PixelDiff>) {
+    if x < 0 || y < 0 {
+        return;
+    }
+    let Ok(before) = canvas.get_pixel(x as u32, y as u32) else { return };
+    if before.color == color {
+        return;
+    }
+
+    let after = Pixel::new(x as u32, y as u32, color);
+    diffs.push(PixelDiff { before, after });
+    let _ = canvas.set_pixel(after);
+}
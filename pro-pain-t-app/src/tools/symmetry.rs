@@ -0,0 +1,137 @@
+//! Mirror painting: the paint tools repeat every dab at the positions given
+//! by the workspace's symmetry.
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum SymmetryMode {
+    Off,
+    /// Mirrored left to right across a vertical axis.
+    Horizontal,
+    /// Mirrored top to bottom across a horizontal axis.
+    Vertical,
+    Both,
+    /// Rotated copies evenly spread around the center.
+    Radial,
+}
+
+impl SymmetryMode {
+    pub const ALL: [SymmetryMode; 5] = [
+        SymmetryMode::Off,
+        SymmetryMode::Horizontal,
+        SymmetryMode::Vertical,
+        SymmetryMode::Both,
+        SymmetryMode::Radial,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SymmetryMode::Off => "No symmetry",
+            SymmetryMode::Horizontal => "Horizontal",
+            SymmetryMode::Vertical => "Vertical",
+            SymmetryMode::Both => "Both axes",
+            SymmetryMode::Radial => "Radial",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    /// Center of the axes as a fraction of the document size, so it stays in
+    /// place when the canvas is resized.
+    pub center: (f32, f32),
+    /// Copies painted in radial mode.
+    pub segments: u32,
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Self {
+            mode: SymmetryMode::Off,
+            center: (0.5, 0.5),
+            segments: 6,
+        }
+    }
+}
+
+/// One mirrored or rotated copy, a linear map around the symmetry center.
+#[derive(Clone, Copy, Debug)]
+pub struct SymmetryCopy {
+    center: (f32, f32),
+    /// Row-major 2×2 matrix.
+    matrix: [f32; 4],
+}
+
+impl SymmetryCopy {
+    pub fn apply(self, (x, y): (f32, f32)) -> (f32, f32) {
+        let [a, b, c, d] = self.matrix;
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        (self.center.0 + a * dx + b * dy, self.center.1 + c * dx + d * dy)
+    }
+}
+
+impl Symmetry {
+    pub fn is_active(&self) -> bool {
+        self.mode != SymmetryMode::Off
+    }
+
+    /// Center in document pixels.
+    pub fn center_in(&self, width: u32, height: u32) -> (f32, f32) {
+        (self.center.0 * width as f32, self.center.1 * height as f32)
+    }
+
+    /// Every position a dab is painted at, the untouched original first.
+    pub fn copies(&self, width: u32, height: u32) -> Vec<SymmetryCopy> {
+        let center = self.center_in(width, height);
+        let copy = |matrix| SymmetryCopy { center, matrix };
+
+        match self.mode {
+            SymmetryMode::Off => vec![copy([1.0, 0.0, 0.0, 1.0])],
+            SymmetryMode::Horizontal => vec![copy([1.0, 0.0, 0.0, 1.0]), copy([-1.0, 0.0, 0.0, 1.0])],
+            SymmetryMode::Vertical => vec![copy([1.0, 0.0, 0.0, 1.0]), copy([1.0, 0.0, 0.0, -1.0])],
+            SymmetryMode::Both => vec![
+                copy([1.0, 0.0, 0.0, 1.0]),
+                copy([-1.0, 0.0, 0.0, 1.0]),
+                copy([1.0, 0.0, 0.0, -1.0]),
+                copy([-1.0, 0.0, 0.0, -1.0]),
+            ],
+            SymmetryMode::Radial => {
+                let segments = self.segments.max(2);
+                (0..segments)
+                    .map(|i| {
+                        let (sin, cos) = (i as f32 * TAU / segments as f32).sin_cos();
+                        copy([cos, -sin, sin, cos])
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Axis lines in document coordinates, for the overlay. Radial mode shows
+    /// one ray per segment, the first pointing up.
+    pub fn axes(&self, width: u32, height: u32) -> Vec<((f32, f32), (f32, f32))> {
+        let (cx, cy) = self.center_in(width, height);
+        let (w, h) = (width as f32, height as f32);
+        let vertical = ((cx, 0.0), (cx, h));
+        let horizontal = ((0.0, cy), (w, cy));
+
+        match self.mode {
+            SymmetryMode::Off => Vec::new(),
+            SymmetryMode::Horizontal => vec![vertical],
+            SymmetryMode::Vertical => vec![horizontal],
+            SymmetryMode::Both => vec![vertical, horizontal],
+            SymmetryMode::Radial => {
+                let segments = self.segments.max(2);
+                // Long enough to leave the document from any center inside it
+                let length = w.hypot(h);
+                (0..segments)
+                    .map(|i| {
+                        let angle = i as f32 * TAU / segments as f32 - FRAC_PI_2;
+                        ((cx, cy), (cx + angle.cos() * length, cy + angle.sin() * length))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
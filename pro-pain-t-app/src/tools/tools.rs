@@ -1,7 +1,10 @@
+use leptos::prelude::RwSignal;
 use serde::{Deserialize, Serialize};
-use web_sys::PointerEvent;
+use web_sys::{KeyboardEvent, PointerEvent};
 
-use crate::tools::{brush::BrushState, bucket::BucketState, context::ToolContext, eraser::EraserState, eyedropper::EyedropperState, line::LineState, pan::PanState, pen::PenState, select::SelectState};
+use crate::state::workspace_state::WorkspaceState;
+use crate::structs::project::Project;
+use crate::tools::{brush::BrushState, bucket::BucketState, context::ToolContext, eraser::EraserState, eyedropper::EyedropperState, gradient::GradientState, lasso::LassoState, line::LineState, magic_wand::MagicWandState, pan::PanState, pen::PenState, retouch::RetouchState, select::SelectState, shape::ShapeState};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum Tool {
@@ -13,6 +16,11 @@ pub enum Tool {
     Eraser(EraserState),
     Brush(BrushState),
     Line(LineState),
+    Lasso(LassoState),
+    MagicWand(MagicWandState),
+    Gradient(GradientState),
+    Shape(ShapeState),
+    Retouch(RetouchState),
 }
 
 impl Tool {
@@ -30,6 +38,11 @@ impl Tool {
             Tool::Eraser(state) => state.on_pointer_down(e, ctx),
             Tool::Brush(state) => state.on_pointer_down(e, ctx),
             Tool::Line(state) => state.on_pointer_down(e, ctx),
+            Tool::Lasso(state) => state.on_pointer_down(e, ctx),
+            Tool::MagicWand(state) => state.on_pointer_down(e, ctx),
+            Tool::Gradient(state) => state.on_pointer_down(e, ctx),
+            Tool::Shape(state) => state.on_pointer_down(e, ctx),
+            Tool::Retouch(state) => state.on_pointer_down(e, ctx),
         }
     }
 
@@ -43,6 +56,11 @@ impl Tool {
             Tool::Eraser(state) => state.on_pointer_move(e, ctx),
             Tool::Brush(state) => state.on_pointer_move(e, ctx),
             Tool::Line(state) => state.on_pointer_move(e, ctx),
+            Tool::Lasso(state) => state.on_pointer_move(e, ctx),
+            Tool::MagicWand(state) => state.on_pointer_move(e, ctx),
+            Tool::Gradient(state) => state.on_pointer_move(e, ctx),
+            Tool::Shape(state) => state.on_pointer_move(e, ctx),
+            Tool::Retouch(state) => state.on_pointer_move(e, ctx),
         }
     }
 
@@ -56,19 +74,31 @@ impl Tool {
             Tool::Eraser(state) => state.on_pointer_up(e, ctx),
             Tool::Brush(state) => state.on_pointer_up(e, ctx),
             Tool::Line(state) => state.on_pointer_up(e, ctx),
+            Tool::Lasso(state) => state.on_pointer_up(e, ctx),
+            Tool::MagicWand(state) => state.on_pointer_up(e, ctx),
+            Tool::Gradient(state) => state.on_pointer_up(e, ctx),
+            Tool::Shape(state) => state.on_pointer_up(e, ctx),
+            Tool::Retouch(state) => state.on_pointer_up(e, ctx),
         }
     }
 
-    pub fn on_pointer_cancel(&mut self) {
+    /// Abandons the gesture in progress. Paint tools put back the pixels
+    /// their unfinished stroke changed.
+    pub fn on_pointer_cancel(&mut self, project: &RwSignal<Project>, workspace_state: &WorkspaceState) {
         match self {
-            Tool::Pen(state) => state.cancel(),
+            Tool::Pen(state) => state.cancel(project, workspace_state),
             Tool::Pan(state) => state.cancel(),
             Tool::Select(state) => state.cancel(),
             Tool::Bucket(state) => state.cancel(),
             Tool::EyeDropper(state) => state.cancel(),
-            Tool::Eraser(state) => state.cancel(),
-            Tool::Brush(state) => state.cancel(),
+            Tool::Eraser(state) => state.cancel(project, workspace_state),
+            Tool::Brush(state) => state.cancel(project, workspace_state),
             Tool::Line(state) => state.cancel(),
+            Tool::Lasso(state) => state.cancel(),
+            Tool::MagicWand(state) => state.cancel(),
+            Tool::Gradient(state) => state.cancel(),
+            Tool::Shape(state) => state.cancel(),
+            Tool::Retouch(state) => state.cancel(project, workspace_state),
         }
     }
 
@@ -82,6 +112,32 @@ impl Tool {
             Tool::Eraser(state) => state.cursor(),
             Tool::Brush(state) => state.cursor(),
             Tool::Line(state) => state.cursor(),
+            Tool::Lasso(state) => state.cursor(),
+            Tool::MagicWand(state) => state.cursor(),
+            Tool::Gradient(state) => state.cursor(),
+            Tool::Shape(state) => state.cursor(),
+            Tool::Retouch(state) => state.cursor(),
+        }
+    }
+
+    /// Keys the tool reacts to while it has the canvas, e.g. Enter to finish a
+    /// polygon. Returns `true` when the key was used.
+    pub fn on_key_down(&mut self, e: &KeyboardEvent, ctx: &ToolContext) -> bool {
+        match self {
+            Tool::Lasso(state) => state.on_key_down(e, ctx),
+            Tool::Line(state) => state.on_key_down(e, ctx),
+            _ => false,
+        }
+    }
+
+    /// Outlines the tool is drawing, in document coordinates, shown over the canvas.
+    pub fn preview_paths(&self) -> Vec<Vec<(f32, f32)>> {
+        match self {
+            Tool::Line(state) => state.preview_paths(),
+            Tool::Lasso(state) => state.preview_path().into_iter().collect(),
+            Tool::Gradient(state) => state.preview_path().into_iter().collect(),
+            Tool::Shape(state) => state.preview_path().into_iter().collect(),
+            _ => Vec::new(),
         }
     }
 }
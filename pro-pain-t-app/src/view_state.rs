@@ -6,6 +6,8 @@ pub struct ProjectViewState {
     pub pan_x: RwSignal<f32>,
     pub pan_y: RwSignal<f32>,
     pub did_center_view: RwSignal<bool>,
+    /// CSS size of the canvas element, kept up to date by `CanvasArea`.
+    pub viewport_size: StoredValue<(f32, f32)>,
 }
 
 impl ProjectViewState {
@@ -15,6 +17,9 @@ impl ProjectViewState {
     pub const MAX_ZOOM_FACTOR: f32 = 32.0;
     pub const ZOOM_STEP_PERCENT_POINTS: f32 = 10.0;
     pub const ZOOM_EPSILON_FACTOR: f32 = 0.000_01;
+    /// Wheel zoom is `exp(-delta * sensitivity)`, so every scrolled pixel
+    /// scales by the same ratio no matter the current zoom.
+    pub const WHEEL_ZOOM_SENSITIVITY: f32 = 0.002;
 
     pub fn new() -> Self {
         Self {
@@ -22,9 +27,24 @@ impl ProjectViewState {
             pan_x: RwSignal::new(0.0),
             pan_y: RwSignal::new(0.0),
             did_center_view: RwSignal::new(false),
+            viewport_size: StoredValue::new((0.0, 0.0)),
         }
     }
 
+    /// Pan that centers a `project_w`×`project_h` document at `zoom`.
+    fn centered_pan(
+        viewport_w_css: f32,
+        viewport_h_css: f32,
+        project_w: u32,
+        project_h: u32,
+        zoom: f32,
+    ) -> (f32, f32) {
+        (
+            (viewport_w_css - (project_w as f32 * zoom)) / 2.0,
+            (viewport_h_css - (project_h as f32 * zoom)) / 2.0,
+        )
+    }
+
     pub fn ensure_centered_once(
         &self,
         viewport_w_css: f32,
@@ -37,8 +57,8 @@ impl ProjectViewState {
             return None;
         }
 
-        let target_pan_x = (viewport_w_css - (project_w as f32 * zoom)) / 2.0;
-        let target_pan_y = (viewport_h_css - (project_h as f32 * zoom)) / 2.0;
+        let (target_pan_x, target_pan_y) =
+            Self::centered_pan(viewport_w_css, viewport_h_css, project_w, project_h, zoom);
 
         self.pan_x.set(target_pan_x);
         self.pan_y.set(target_pan_y);
@@ -46,6 +66,55 @@ impl ProjectViewState {
         Some((target_pan_x, target_pan_y))
     }
 
+    /// Zooms so the whole document is visible and centers it.
+    pub fn fit_to_window(&self, project_w: u32, project_h: u32) {
+        self.zoom_to_viewport(project_w, project_h, f32::min);
+    }
+
+    /// Zooms so the document covers the whole viewport and centers it.
+    pub fn fill_window(&self, project_w: u32, project_h: u32) {
+        self.zoom_to_viewport(project_w, project_h, f32::max);
+    }
+
+    fn zoom_to_viewport(&self, project_w: u32, project_h: u32, pick: fn(f32, f32) -> f32) {
+        let (viewport_w_css, viewport_h_css) = self.viewport_size.get_value();
+        if project_w == 0 || project_h == 0 || viewport_w_css <= 0.0 || viewport_h_css <= 0.0 {
+            return;
+        }
+
+        let zoom = pick(viewport_w_css / project_w as f32, viewport_h_css / project_h as f32)
+            .clamp(Self::MIN_ZOOM_FACTOR, Self::MAX_ZOOM_FACTOR);
+        let (pan_x, pan_y) =
+            Self::centered_pan(viewport_w_css, viewport_h_css, project_w, project_h, zoom);
+
+        self.zoom_factor.set(zoom);
+        self.pan_x.set(pan_x);
+        self.pan_y.set(pan_y);
+        self.did_center_view.set(true);
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the document point under
+    /// (`anchor_x`, `anchor_y`) in place. The anchor is in CSS pixels relative
+    /// to the canvas element.
+    pub fn zoom_at(&self, factor: f32, anchor_x: f32, anchor_y: f32) {
+        if !factor.is_finite() || factor <= 0.0 || !anchor_x.is_finite() || !anchor_y.is_finite() {
+            return;
+        }
+
+        let zoom = self.zoom_factor.get_untracked();
+        let new_zoom = (zoom * factor).clamp(Self::MIN_ZOOM_FACTOR, Self::MAX_ZOOM_FACTOR);
+        if (new_zoom - zoom).abs() <= Self::ZOOM_EPSILON_FACTOR {
+            return;
+        }
+
+        let doc_x = (anchor_x - self.pan_x.get_untracked()) / zoom;
+        let doc_y = (anchor_y - self.pan_y.get_untracked()) / zoom;
+
+        self.zoom_factor.set(new_zoom);
+        self.pan_x.set(anchor_x - doc_x * new_zoom);
+        self.pan_y.set(anchor_y - doc_y * new_zoom);
+    }
+
     pub fn pan_by(&self, dx: f32, dy: f32) {
         if !dx.is_finite() || !dy.is_finite() {
             return;
@@ -83,3 +152,59 @@ impl ProjectViewState {
         (self.zoom_factor.get() * 100.0).round().max(1.0) as u32
     }
 }
+
+/// Two finger pinch on touch screens, built from the pointer events of both
+/// fingers. Spreading the fingers zooms around their midpoint, moving them
+/// together pans.
+#[derive(Default)]
+pub struct PinchGesture {
+    pointers: Vec<(i32, f32, f32)>,
+}
+
+impl PinchGesture {
+    /// Tracks a touch pointer. Returns `true` once a second finger turns the
+    /// touches into a pinch.
+    pub fn pointer_down(&mut self, pointer_id: i32, x: f32, y: f32) -> bool {
+        if self.pointers.len() < 2 && !self.pointers.iter().any(|(id, _, _)| *id == pointer_id) {
+            self.pointers.push((pointer_id, x, y));
+        }
+        self.is_active()
+    }
+
+    /// Moves a tracked pointer and applies the resulting zoom and pan.
+    /// Returns `true` when the event belonged to the pinch.
+    pub fn pointer_move(&mut self, pointer_id: i32, x: f32, y: f32, view_state: &ProjectViewState) -> bool {
+        let Some(index) = self.pointers.iter().position(|(id, _, _)| *id == pointer_id) else {
+            return false;
+        };
+        if !self.is_active() {
+            self.pointers[index] = (pointer_id, x, y);
+            return false;
+        }
+
+        let (distance_before, mid_x_before, mid_y_before) = self.span();
+        self.pointers[index] = (pointer_id, x, y);
+        let (distance, mid_x, mid_y) = self.span();
+
+        view_state.pan_by(mid_x - mid_x_before, mid_y - mid_y_before);
+        if distance_before > 0.0 {
+            view_state.zoom_at(distance / distance_before, mid_x, mid_y);
+        }
+        true
+    }
+
+    pub fn pointer_up(&mut self, pointer_id: i32) {
+        self.pointers.retain(|(id, _, _)| *id != pointer_id);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.pointers.len() == 2
+    }
+
+    /// Distance between the fingers and their midpoint.
+    fn span(&self) -> (f32, f32, f32) {
+        let (_, x0, y0) = self.pointers[0];
+        let (_, x1, y1) = self.pointers[1];
+        ((x1 - x0).hypot(y1 - y0), (x0 + x1) / 2.0, (y0 + y1) / 2.0)
+    }
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use crate::dtos::image::ImageDto;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClipboardImageDto {
+    pub image: ImageDto,
+}
+
+impl ClipboardImageDto {
+    pub fn new(image: ImageDto) -> Self {
+        Self { image }
+    }
+}
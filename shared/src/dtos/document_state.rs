@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// What the backend needs to know about the open project to title the window
+/// and to guard against losing unsaved changes.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentStateDto {
+    pub title: String,
+    pub path: Option<String>,
+    pub is_dirty: bool,
+}
+
+impl DocumentStateDto {
+    pub fn new(title: String, path: Option<String>, is_dirty: bool) -> Self {
+        Self { title, path, is_dirty }
+    }
+}
@@ -1,14 +1,15 @@
 use serde::{Deserialize, Serialize};
+use crate::dtos::export_options::ExportOptionsDto;
 use crate::dtos::image::ImageDto;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ExportObjectDto {
-    pub path: String,
     pub image: ImageDto,
+    pub options: ExportOptionsDto,
 }
 
 impl ExportObjectDto {
-    pub fn new(path: String, image: ImageDto) -> Self {
-        Self { path, image }
+    pub fn new(image: ImageDto, options: ExportOptionsDto) -> Self {
+        Self { image, options }
     }
 }
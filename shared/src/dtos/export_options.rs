@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ExportFormat {
+    #[default]
+    Png,
+    Jpeg,
+    WebP,
+    Bmp,
+    Tiff,
+    Gif,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 6] = [
+        ExportFormat::Png,
+        ExportFormat::Jpeg,
+        ExportFormat::WebP,
+        ExportFormat::Bmp,
+        ExportFormat::Tiff,
+        ExportFormat::Gif,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "PNG",
+            ExportFormat::Jpeg => "JPEG",
+            ExportFormat::WebP => "WebP (lossless)",
+            ExportFormat::Bmp => "BMP",
+            ExportFormat::Tiff => "TIFF",
+            ExportFormat::Gif => "GIF",
+        }
+    }
+
+    /// File extensions, the first one is used for new files.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ExportFormat::Png => &["png"],
+            ExportFormat::Jpeg => &["jpg", "jpeg"],
+            ExportFormat::WebP => &["webp"],
+            ExportFormat::Bmp => &["bmp"],
+            ExportFormat::Tiff => &["tif", "tiff"],
+            ExportFormat::Gif => &["gif"],
+        }
+    }
+
+    /// Formats without an alpha channel are always flattened.
+    pub fn supports_alpha(self) -> bool {
+        !matches!(self, ExportFormat::Jpeg)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub const ALL: [PngCompression; 3] = [PngCompression::Fast, PngCompression::Default, PngCompression::Best];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PngCompression::Fast => "Fast",
+            PngCompression::Default => "Default",
+            PngCompression::Best => "Best",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExportOptionsDto {
+    pub format: ExportFormat,
+    pub jpeg_quality: u8, // 1 - 100
+    pub png_compression: PngCompression,
+    /// RGB color the image is flattened onto. Formats without alpha fall back
+    /// to white when this is `None`.
+    pub flatten_background: Option<[u8; 3]>,
+    pub scale: f32, // 1.0 keeps the document size
+}
+
+impl Default for ExportOptionsDto {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            jpeg_quality: 90,
+            png_compression: PngCompression::default(),
+            flatten_background: None,
+            scale: 1.0,
+        }
+    }
+}
@@ -1,11 +1,11 @@
-use image::RgbImage;
+use image::RgbaImage;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ImageDto {
     pub width: u32,
     pub height: u32,
-    pub raw_data: Vec<u8>,
+    pub raw_data: Vec<u8>, // Row-major RGBA
 }
 
 impl ImageDto {
@@ -13,7 +13,7 @@ impl ImageDto {
         Self { width, height, raw_data }
     }
 
-    pub fn from_image(image: RgbImage) -> Self {
+    pub fn from_image(image: RgbaImage) -> Self {
         Self {
             width: image.width(),
             height: image.height(),
@@ -3,4 +3,9 @@ pub mod project;
 pub mod path;
 pub mod save_object;
 pub mod export_object;
+pub mod export_options;
+pub mod document_state;
 pub mod error_message;
+pub mod open_image;
+pub mod clipboard_image;
+pub mod shortcuts;
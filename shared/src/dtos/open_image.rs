@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use crate::dtos::image::ImageDto;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OpenImageDto {
+    pub name: String, // File name without extension, used as the project name
+    pub image: ImageDto,
+}
+
+impl OpenImageDto {
+    pub fn new(name: String, image: ImageDto) -> Self {
+        Self { name, image }
+    }
+}
@@ -2,11 +2,12 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProjectDto {
+    pub path: String,
     pub data: Vec<u8>,
 }
 
 impl ProjectDto {
-    pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+    pub fn new(path: String, data: Vec<u8>) -> Self {
+        Self { path, data }
     }
 }
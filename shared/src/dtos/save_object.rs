@@ -3,11 +3,11 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SaveObjectDto {
     pub path: String,
-    pub project_serialized: String,
+    pub project_serialized: Vec<u8>,
 }
 
 impl SaveObjectDto {
-    pub fn new(path: String, project_serialized: String) -> Self {
+    pub fn new(path: String, project_serialized: Vec<u8>) -> Self {
         Self { path, project_serialized }
     }
 }
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::shortcuts::ShortcutAction;
+
+/// Effective chord of every action, as sent to the frontend.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutsDto {
+    pub bindings: BTreeMap<ShortcutAction, String>,
+}
+
+/// Rebinds `action`, `None` restores its default chord.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SetShortcutDto {
+    pub action: ShortcutAction,
+    pub chord: Option<String>,
+}
+
+impl SetShortcutDto {
+    pub fn new(action: ShortcutAction, chord: Option<String>) -> Self {
+        Self { action, chord }
+    }
+}
@@ -1,8 +1,17 @@
 pub const EVENT_MENU_NEW_PROJECT: &str = "menu-new-project";
 pub const EVENT_MENU_OPEN_PROJECT: &str = "menu-open-project";
+pub const EVENT_MENU_OPEN_IMAGE: &str = "menu-open-image";
 pub const EVENT_MENU_SAVE_PROJECT: &str = "menu-save-project";
 pub const EVENT_MENU_IMPORT_AS_LAYER: &str = "menu-import-as-layer";
 pub const EVENT_MENU_EXPORT_PROJECT: &str = "menu-export-project";
 pub const EVENT_MENU_UNDO: &str = "menu-undo";
 pub const EVENT_MENU_REDO: &str = "menu-redo";
+pub const EVENT_MENU_CUT: &str = "menu-cut";
+pub const EVENT_MENU_COPY: &str = "menu-copy";
+pub const EVENT_MENU_PASTE: &str = "menu-paste";
+pub const EVENT_MENU_PASTE_AS_LAYER: &str = "menu-paste-as-layer";
 pub const EVENT_MENU_CANVAS_SIZE: &str = "menu-canvas-size";
+pub const EVENT_MENU_FIT_TO_WINDOW: &str = "menu-fit-to-window";
+pub const EVENT_MENU_FILL_WINDOW: &str = "menu-fill-window";
+pub const EVENT_MENU_SHORTCUTS: &str = "menu-shortcuts";
+pub const EVENT_AUTOSAVE: &str = "autosave";
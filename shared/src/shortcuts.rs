@@ -0,0 +1,421 @@
+//! Keyboard shortcut registry shared by the menu accelerators in the backend
+//! and the key handler in the frontend.
+//!
+//! Chords are written the way Tauri accelerators are, e.g. `CmdOrCtrl+Shift+Z`
+//! or `]`, so the same string can be handed to a menu item unchanged.
+
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ShortcutAction {
+    ToolPen,
+    ToolBrush,
+    ToolLine,
+    ToolEraser,
+    ToolBucket,
+    ToolEyedropper,
+    ToolSelect,
+    ToolEllipseSelect,
+    ToolLasso,
+    ToolPolygonalLasso,
+    ToolMagicWand,
+    ToolGradient,
+    ToolRectangle,
+    ToolEllipse,
+    ToolPolygon,
+    ToolSmudge,
+    ToolBlur,
+    ToolSharpen,
+    ToolPan,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    PasteAsLayer,
+    DeleteSelection,
+    FillSelection,
+    Save,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    FitToWindow,
+    FillWindow,
+    BrushSizeUp,
+    BrushSizeDown,
+    SwapColors,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 36] = [
+        ShortcutAction::ToolPen,
+        ShortcutAction::ToolBrush,
+        ShortcutAction::ToolLine,
+        ShortcutAction::ToolEraser,
+        ShortcutAction::ToolBucket,
+        ShortcutAction::ToolEyedropper,
+        ShortcutAction::ToolSelect,
+        ShortcutAction::ToolEllipseSelect,
+        ShortcutAction::ToolLasso,
+        ShortcutAction::ToolPolygonalLasso,
+        ShortcutAction::ToolMagicWand,
+        ShortcutAction::ToolGradient,
+        ShortcutAction::ToolRectangle,
+        ShortcutAction::ToolEllipse,
+        ShortcutAction::ToolPolygon,
+        ShortcutAction::ToolSmudge,
+        ShortcutAction::ToolBlur,
+        ShortcutAction::ToolSharpen,
+        ShortcutAction::ToolPan,
+        ShortcutAction::Undo,
+        ShortcutAction::Redo,
+        ShortcutAction::Cut,
+        ShortcutAction::Copy,
+        ShortcutAction::Paste,
+        ShortcutAction::PasteAsLayer,
+        ShortcutAction::DeleteSelection,
+        ShortcutAction::FillSelection,
+        ShortcutAction::Save,
+        ShortcutAction::ZoomIn,
+        ShortcutAction::ZoomOut,
+        ShortcutAction::ZoomReset,
+        ShortcutAction::FitToWindow,
+        ShortcutAction::FillWindow,
+        ShortcutAction::BrushSizeUp,
+        ShortcutAction::BrushSizeDown,
+        ShortcutAction::SwapColors,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::ToolPen => "Pen tool",
+            ShortcutAction::ToolBrush => "Brush tool",
+            ShortcutAction::ToolLine => "Line tool",
+            ShortcutAction::ToolEraser => "Eraser tool",
+            ShortcutAction::ToolBucket => "Bucket tool",
+            ShortcutAction::ToolEyedropper => "Eyedropper tool",
+            ShortcutAction::ToolSelect => "Select tool",
+            ShortcutAction::ToolEllipseSelect => "Ellipse select tool",
+            ShortcutAction::ToolLasso => "Lasso tool",
+            ShortcutAction::ToolPolygonalLasso => "Polygonal lasso tool",
+            ShortcutAction::ToolMagicWand => "Magic wand tool",
+            ShortcutAction::ToolGradient => "Gradient tool",
+            ShortcutAction::ToolRectangle => "Rectangle tool",
+            ShortcutAction::ToolEllipse => "Ellipse tool",
+            ShortcutAction::ToolPolygon => "Polygon tool",
+            ShortcutAction::ToolSmudge => "Smudge tool",
+            ShortcutAction::ToolBlur => "Blur tool",
+            ShortcutAction::ToolSharpen => "Sharpen tool",
+            ShortcutAction::ToolPan => "Pan tool",
+            ShortcutAction::Undo => "Undo",
+            ShortcutAction::Redo => "Redo",
+            ShortcutAction::Cut => "Cut",
+            ShortcutAction::Copy => "Copy",
+            ShortcutAction::Paste => "Paste",
+            ShortcutAction::PasteAsLayer => "Paste as new layer",
+            ShortcutAction::DeleteSelection => "Delete selection",
+            ShortcutAction::FillSelection => "Fill selection",
+            ShortcutAction::Save => "Save project",
+            ShortcutAction::ZoomIn => "Zoom in",
+            ShortcutAction::ZoomOut => "Zoom out",
+            ShortcutAction::ZoomReset => "Reset zoom",
+            ShortcutAction::FitToWindow => "Fit to window",
+            ShortcutAction::FillWindow => "Fill window",
+            ShortcutAction::BrushSizeUp => "Increase brush size",
+            ShortcutAction::BrushSizeDown => "Decrease brush size",
+            ShortcutAction::SwapColors => "Swap colors",
+        }
+    }
+
+    pub fn default_chord(self) -> &'static str {
+        match self {
+            ShortcutAction::ToolPen => "P",
+            ShortcutAction::ToolBrush => "B",
+            ShortcutAction::ToolLine => "L",
+            ShortcutAction::ToolEraser => "E",
+            ShortcutAction::ToolBucket => "G",
+            ShortcutAction::ToolEyedropper => "I",
+            ShortcutAction::ToolSelect => "M",
+            ShortcutAction::ToolEllipseSelect => "Shift+M",
+            ShortcutAction::ToolLasso => "A",
+            ShortcutAction::ToolPolygonalLasso => "Shift+A",
+            ShortcutAction::ToolMagicWand => "W",
+            ShortcutAction::ToolGradient => "Shift+G",
+            ShortcutAction::ToolRectangle => "R",
+            ShortcutAction::ToolEllipse => "O",
+            ShortcutAction::ToolPolygon => "Y",
+            ShortcutAction::ToolSmudge => "S",
+            ShortcutAction::ToolBlur => "U",
+            ShortcutAction::ToolSharpen => "Shift+U",
+            ShortcutAction::ToolPan => "H",
+            ShortcutAction::Undo => "CmdOrCtrl+Z",
+            ShortcutAction::Redo => "CmdOrCtrl+Shift+Z",
+            ShortcutAction::Cut => "CmdOrCtrl+X",
+            ShortcutAction::Copy => "CmdOrCtrl+C",
+            ShortcutAction::Paste => "CmdOrCtrl+V",
+            ShortcutAction::PasteAsLayer => "CmdOrCtrl+Shift+V",
+            ShortcutAction::DeleteSelection => "DELETE",
+            ShortcutAction::FillSelection => "Alt+BACKSPACE",
+            ShortcutAction::Save => "CmdOrCtrl+S",
+            ShortcutAction::ZoomIn => "CmdOrCtrl+=",
+            ShortcutAction::ZoomOut => "CmdOrCtrl+-",
+            ShortcutAction::ZoomReset => "CmdOrCtrl+0",
+            ShortcutAction::FitToWindow => "CmdOrCtrl+9",
+            ShortcutAction::FillWindow => "CmdOrCtrl+Shift+9",
+            ShortcutAction::BrushSizeUp => "]",
+            ShortcutAction::BrushSizeDown => "[",
+            ShortcutAction::SwapColors => "X",
+        }
+    }
+
+    /// Id of the menu item that runs the action. These are triggered by the
+    /// menu accelerator, so the frontend must not handle them a second time.
+    pub fn menu_id(self) -> Option<&'static str> {
+        match self {
+            ShortcutAction::Undo => Some("undo"),
+            ShortcutAction::Redo => Some("redo"),
+            ShortcutAction::Cut => Some("cut"),
+            ShortcutAction::Copy => Some("copy"),
+            ShortcutAction::Paste => Some("paste"),
+            ShortcutAction::PasteAsLayer => Some("paste_as_layer"),
+            ShortcutAction::Save => Some("save_project"),
+            ShortcutAction::FitToWindow => Some("fit_to_window"),
+            ShortcutAction::FillWindow => Some("fill_window"),
+            _ => None,
+        }
+    }
+}
+
+/// A key plus modifiers. `cmd_or_ctrl` is Command on macOS and Control
+/// elsewhere, matching Tauri's `CmdOrCtrl`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chord {
+    pub cmd_or_ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: String,
+}
+
+impl Chord {
+    /// Parses `Modifier+...+Key`. Modifier names are case insensitive and the
+    /// usual aliases are accepted. The key must be one `key_from_code` produces.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut chord = Chord { cmd_or_ctrl: false, alt: false, shift: false, key: String::new() };
+        let parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let (key, modifiers) = parts.split_last()?;
+
+        for modifier in modifiers {
+            match modifier.to_ascii_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" | "cmd" | "command" | "ctrl" | "control" | "super" | "meta" => {
+                    chord.cmd_or_ctrl = true
+                }
+                "alt" | "option" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return None,
+            }
+        }
+
+        let key = key.to_ascii_uppercase();
+        if !is_known_key(&key) {
+            return None;
+        }
+        chord.key = key;
+        Some(chord)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cmd_or_ctrl {
+            write!(f, "CmdOrCtrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Key name for a DOM `KeyboardEvent.code`. Codes are layout independent, so
+/// `]` stays `]` no matter what Shift turns it into.
+pub fn key_from_code(code: &str) -> Option<String> {
+    if let Some(letter) = code.strip_prefix("Key") {
+        return Some(letter.to_string());
+    }
+    if let Some(digit) = code.strip_prefix("Digit") {
+        return Some(digit.to_string());
+    }
+    if code.starts_with('F') && code[1..].parse::<u8>().is_ok_and(|n| (1..=12).contains(&n)) {
+        return Some(code.to_string());
+    }
+    let key = match code {
+        "BracketLeft" => "[",
+        "BracketRight" => "]",
+        "Equal" => "=",
+        "Minus" => "-",
+        "Comma" => ",",
+        "Period" => ".",
+        "Slash" => "/",
+        "Backslash" => "\\",
+        "Semicolon" => ";",
+        "Quote" => "'",
+        "Backquote" => "`",
+        "Space" => "SPACE",
+        "Enter" => "ENTER",
+        "Tab" => "TAB",
+        "Backspace" => "BACKSPACE",
+        "Delete" => "DELETE",
+        _ => return None,
+    };
+    Some(key.to_string())
+}
+
+fn is_known_key(key: &str) -> bool {
+    let is_single = key.chars().count() == 1
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || "[]=-,./\\;'`".contains(c));
+    let is_function = key.starts_with('F') && key[1..].parse::<u8>().is_ok_and(|n| (1..=12).contains(&n));
+    is_single || is_function || matches!(key, "SPACE" | "ENTER" | "TAB" | "BACKSPACE" | "DELETE")
+}
+
+/// User chosen chords by action. Actions without an entry use their default.
+pub type ShortcutOverrides = BTreeMap<ShortcutAction, String>;
+
+/// Reads saved overrides, skipping actions this version doesn't know, so one
+/// stale entry doesn't make the whole settings file invalid.
+pub fn deserialize_overrides<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ShortcutOverrides, D::Error> {
+    let saved = BTreeMap::<String, String>::deserialize(deserializer)?;
+    Ok(saved
+        .into_iter()
+        .filter_map(|(action, chord)| {
+            let action = ShortcutAction::deserialize(StrDeserializer::<ValueError>::new(&action)).ok()?;
+            Some((action, chord))
+        })
+        .collect())
+}
+
+/// Effective chord of every action. Overrides that don't parse are ignored,
+/// and so are overrides that end up sharing a chord with another action:
+/// those actions keep their defaults.
+pub fn resolve_shortcuts(overrides: &ShortcutOverrides) -> BTreeMap<ShortcutAction, Chord> {
+    let mut ignored = BTreeSet::new();
+    loop {
+        let shortcuts: BTreeMap<ShortcutAction, Chord> = ShortcutAction::ALL
+            .into_iter()
+            .filter_map(|action| {
+                let chord = overrides
+                    .get(&action)
+                    .filter(|_| !ignored.contains(&action))
+                    .and_then(|text| Chord::parse(text))
+                    .or_else(|| Chord::parse(action.default_chord()))?;
+                Some((action, chord))
+            })
+            .collect();
+
+        // Dropping an override brings back a default, which another override
+        // may be using, so repeat until nothing clashes. Defaults never do.
+        let mut by_chord: BTreeMap<String, Vec<ShortcutAction>> = BTreeMap::new();
+        for (action, chord) in &shortcuts {
+            by_chord.entry(chord.to_string()).or_default().push(*action);
+        }
+        let clashing: Vec<ShortcutAction> = by_chord
+            .into_values()
+            .filter(|actions| actions.len() > 1)
+            .flatten()
+            .filter(|action| overrides.contains_key(action) && !ignored.contains(action))
+            .collect();
+        if clashing.is_empty() {
+            return shortcuts;
+        }
+        ignored.extend(clashing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_round_trip_through_display() {
+        for text in ["CmdOrCtrl+Shift+Z", "Alt+BACKSPACE", "]", "F12", "CmdOrCtrl+Alt+Shift+0"] {
+            assert_eq!(Chord::parse(text).unwrap().to_string(), text);
+        }
+        for action in ShortcutAction::ALL {
+            let chord = Chord::parse(action.default_chord()).unwrap();
+            assert_eq!(Chord::parse(&chord.to_string()), Some(chord));
+        }
+    }
+
+    #[test]
+    fn aliases_and_case_are_normalized() {
+        assert_eq!(Chord::parse("ctrl + shift + z").unwrap().to_string(), "CmdOrCtrl+Shift+Z");
+        assert_eq!(Chord::parse("Option+Command+delete").unwrap().to_string(), "CmdOrCtrl+Alt+DELETE");
+    }
+
+    #[test]
+    fn malformed_chords_are_rejected() {
+        for text in ["", "Ctrl+", "Hyper+Z", "Ctrl+Shift", "ZZ", "F13", "Ctrl+Escape", "+"] {
+            assert_eq!(Chord::parse(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn key_codes_map_to_parseable_keys() {
+        for code in ["KeyA", "Digit7", "F5", "BracketRight", "Backspace", "Space"] {
+            let key = key_from_code(code).unwrap();
+            assert!(Chord::parse(&key).is_some(), "{}", key);
+        }
+        assert_eq!(key_from_code("BracketLeft").as_deref(), Some("["));
+        assert_eq!(key_from_code("F13"), None);
+        assert_eq!(key_from_code("ShiftLeft"), None);
+    }
+
+    #[test]
+    fn overrides_replace_defaults() {
+        let overrides = ShortcutOverrides::from([
+            (ShortcutAction::Undo, "Ctrl+Y".to_string()),
+            (ShortcutAction::ToolPen, "not a chord".to_string()),
+        ]);
+        let shortcuts = resolve_shortcuts(&overrides);
+
+        assert_eq!(shortcuts.len(), ShortcutAction::ALL.len());
+        assert_eq!(shortcuts[&ShortcutAction::Undo].to_string(), "CmdOrCtrl+Y");
+        assert_eq!(shortcuts[&ShortcutAction::ToolPen].to_string(), "P");
+        assert_eq!(shortcuts[&ShortcutAction::Redo].to_string(), "CmdOrCtrl+Shift+Z");
+    }
+
+    #[test]
+    fn conflicting_overrides_fall_back_to_defaults() {
+        // Swapping two defaults is fine
+        let swapped = ShortcutOverrides::from([
+            (ShortcutAction::ToolPen, "B".to_string()),
+            (ShortcutAction::ToolBrush, "P".to_string()),
+        ]);
+        let shortcuts = resolve_shortcuts(&swapped);
+        assert_eq!(shortcuts[&ShortcutAction::ToolPen].to_string(), "B");
+        assert_eq!(shortcuts[&ShortcutAction::ToolBrush].to_string(), "P");
+
+        // Taking another action's chord doesn't
+        let taken = ShortcutOverrides::from([(ShortcutAction::ToolPen, "B".to_string())]);
+        let shortcuts = resolve_shortcuts(&taken);
+        assert_eq!(shortcuts[&ShortcutAction::ToolPen].to_string(), "P");
+        assert_eq!(shortcuts[&ShortcutAction::ToolBrush].to_string(), "B");
+
+        // Every chord ends up used once
+        let chords: BTreeSet<String> = shortcuts.values().map(Chord::to_string).collect();
+        assert_eq!(chords.len(), shortcuts.len());
+    }
+
+    #[test]
+    fn unknown_actions_in_saved_settings_are_skipped() {
+        let json = r#"{ "Undo": "Ctrl+Y", "ToolTeleport": "T" }"#;
+        let overrides = deserialize_overrides(&mut serde_json::Deserializer::from_str(json)).unwrap();
+
+        assert_eq!(overrides, ShortcutOverrides::from([(ShortcutAction::Undo, "Ctrl+Y".to_string())]));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::document_state::has_unsaved_changes;
+use pro_pain_t_shared::dtos::path::PathDto;
+use pro_pain_t_shared::events::events::EVENT_AUTOSAVE;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+const RECOVERY_DIR: &str = "recovery";
+
+/// Recovery files live in `<app data>/recovery`. Every session writes its own
+/// file, so anything already there at startup was left behind by a session
+/// that didn't exit cleanly.
+pub struct AutosaveState {
+    recovery_dir: PathBuf,
+    session_file: PathBuf,
+    leftovers: Mutex<Vec<PathBuf>>,
+}
+
+impl AutosaveState {
+    pub fn is_recovery_file(&self, path: &Path) -> bool {
+        path.starts_with(&self.recovery_dir)
+    }
+
+    /// Recovery files of earlier sessions, newest first. Returns them only once.
+    pub fn take_leftovers(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.leftovers.lock().unwrap())
+    }
+
+    pub fn remove_session_file(&self) {
+        let _ = fs::remove_file(&self.session_file);
+    }
+}
+
+/// Looks for leftovers and starts the timer. Every `interval` the frontend is
+/// asked to write the project into the session's recovery file, as long as
+/// there are unsaved changes. A zero `interval` disables autosave.
+pub fn start(app_handle: &AppHandle, interval: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    let recovery_dir = app_handle.path().app_data_dir()?.join(RECOVERY_DIR);
+    fs::create_dir_all(&recovery_dir)?;
+
+    let mut leftovers = fs::read_dir(&recovery_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ppp"))
+        .collect::<Vec<_>>();
+    leftovers.sort_by_key(|path| std::cmp::Reverse(fs::metadata(path).and_then(|m| m.modified()).ok()));
+
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let session_file = recovery_dir.join(format!("session-{}-{}.ppp", started, std::process::id()));
+
+    app_handle.manage(AutosaveState {
+        recovery_dir,
+        session_file: session_file.clone(),
+        leftovers: Mutex::new(leftovers),
+    });
+
+    if interval.is_zero() {
+        return Ok(());
+    }
+
+    let app_handle = app_handle.clone();
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if !has_unsaved_changes(&app_handle) {
+            continue;
+        }
+        if app_handle.emit(EVENT_AUTOSAVE, PathDto::new(session_file.display())).is_err() {
+            eprintln!("Failed to request autosave");
+        }
+    });
+
+    Ok(())
+}
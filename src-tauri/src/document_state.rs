@@ -0,0 +1,34 @@
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// The frontend's view of the open project, kept in sync through
+/// `update_document_state_command`.
+#[derive(Default)]
+pub struct DocumentState {
+    pub title: String,
+    pub path: Option<String>,
+    pub is_dirty: bool,
+}
+
+impl DocumentState {
+    /// File name when the project has been saved, project name otherwise.
+    /// Unsaved changes are marked with a leading `*`.
+    pub fn window_title(&self) -> String {
+        let name = self
+            .path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.title.clone());
+        format!("{}{} - Pro PainT", if self.is_dirty { "*" } else { "" }, name)
+    }
+}
+
+pub fn project_path(app_handle: &AppHandle) -> Option<String> {
+    app_handle.state::<Mutex<DocumentState>>().lock().unwrap().path.clone()
+}
+
+pub fn has_unsaved_changes(app_handle: &AppHandle) -> bool {
+    app_handle.state::<Mutex<DocumentState>>().lock().unwrap().is_dirty
+}
@@ -1,33 +1,169 @@
+use arboard::{Clipboard, ImageData};
+use std::borrow::Cow;
 use std::fs;
-use image::RgbaImage;
-use tauri::AppHandle;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Window};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+use pro_pain_t_shared::dtos::export_options::ExportOptionsDto;
 use pro_pain_t_shared::dtos::image::ImageDto;
-use crate::events::handlers::error_dialog;
+use pro_pain_t_shared::dtos::project::ProjectDto;
+use pro_pain_t_shared::dtos::shortcuts::ShortcutsDto;
+use pro_pain_t_shared::shortcuts::{Chord, ShortcutAction};
+use crate::autosave::AutosaveState;
+use crate::document_state::DocumentState;
+use crate::events::handlers::{error_dialog, export_project_handler};
+use crate::menus::refresh_menus;
+use crate::settings::Settings;
 /*
 !!!
     DON'T FORGET TO ADD THE COMMANDS TO `invoke_handler` IN MAIN.RS
 !!!
 */
 
+/// Used for regular saves as well as autosaves into the recovery directory.
+/// Errors of regular saves are shown to the user here and returned so the
+/// frontend knows the project is still unsaved. A successful regular save
+/// makes the session's recovery file obsolete.
 #[tauri::command(rename_all = "snake_case")]
-pub fn save_project_command(app_handle: AppHandle, path: String, project_serialized: String) {
-    if fs::write(path, project_serialized).is_err() {
-        error_dialog(&app_handle, "Failed to write project to file");
-        return;
+pub fn save_project_command(app_handle: AppHandle, path: String, project_serialized: Vec<u8>) -> Result<(), String> {
+    let autosave = app_handle.try_state::<AutosaveState>();
+    let is_autosave = autosave.as_ref().is_some_and(|autosave| autosave.is_recovery_file(Path::new(&path)));
+
+    if let Err(err) = fs::write(&path, project_serialized) {
+        let message = format!("Failed to write project to file: {}", err);
+        if is_autosave {
+            eprintln!("Autosave failed: {}", message);
+        } else {
+            error_dialog(&app_handle, &message);
+        }
+        return Err(message);
+    }
+
+    if let (Some(autosave), false) = (autosave, is_autosave) {
+        autosave.remove_session_file();
     }
+    Ok(())
 }
 
+/// Offers to restore the newest recovery file left behind by a session that
+/// didn't exit cleanly. All leftovers are removed afterwards either way.
 #[tauri::command(rename_all = "snake_case")]
-pub fn export_project_command(app_handle: AppHandle, path: String, image: ImageDto) {
-    let image = RgbaImage::from_raw(image.width, image.height, image.raw_data);
-    if image.is_none() {
-        error_dialog(&app_handle, "Failed to create image buffer");
-        return;
-    }
-    if image.unwrap().save(path).is_err() {
-        error_dialog(&app_handle, "Failed to save image");
-        return;
+pub async fn take_recovered_project_command(app_handle: AppHandle) -> Option<ProjectDto> {
+    let leftovers = app_handle.try_state::<AutosaveState>()?.take_leftovers();
+    let newest = leftovers.first()?.clone();
+
+    let restore = app_handle.dialog()
+        .message("Pro PainT didn't exit properly last time. Do you want to restore the autosaved project?")
+        .title("Recover project")
+        .buttons(MessageDialogButtons::OkCancelCustom("Restore".to_string(), "Discard".to_string()))
+        .blocking_show();
+
+    let data = if restore {
+        match fs::read(&newest) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                error_dialog(&app_handle, format!("Failed to read recovery file: {}", err));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    for file in &leftovers {
+        let _ = fs::remove_file(file);
     }
+
+    data.map(|data| ProjectDto::new(newest.display().to_string(), data))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_document_state_command(app_handle: AppHandle, window: Window, title: String, path: Option<String>, is_dirty: bool) {
+    let state = app_handle.state::<Mutex<DocumentState>>();
+    let mut state = state.lock().unwrap();
+    state.title = title;
+    state.path = path;
+    state.is_dirty = is_dirty;
+    let _ = window.set_title(&state.window_title());
+}
+
+/// Asks for the target file, filtered to the chosen format, then encodes the image.
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_project_command(app_handle: AppHandle, image: ImageDto, options: ExportOptionsDto) {
+    let extensions = options.format.extensions();
+    app_handle.dialog().file()
+        .add_filter(options.format.label(), extensions)
+        .set_file_name(format!("unnamed_export.{}", extensions[0]))
+        .set_can_create_directories(true)
+        .save_file({
+            let app_handle = app_handle.clone();
+            move |file_path| export_project_handler(&app_handle, file_path, image, options)
+        });
+}
+
+/// Puts the image on the OS clipboard, where other applications see it as PNG.
+#[tauri::command(rename_all = "snake_case")]
+pub fn write_clipboard_image_command(image: ImageDto) -> Result<(), String> {
+    let mut clipboard = Clipboard::new().map_err(|err| format!("Clipboard unavailable: {}", err))?;
+    clipboard
+        .set_image(ImageData {
+            width: image.width as usize,
+            height: image.height as usize,
+            bytes: Cow::Owned(image.raw_data),
+        })
+        .map_err(|err| format!("Failed to copy image to the clipboard: {}", err))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn read_clipboard_image_command() -> Result<ImageDto, String> {
+    let mut clipboard = Clipboard::new().map_err(|err| format!("Clipboard unavailable: {}", err))?;
+    let image = clipboard.get_image().map_err(|err| match err {
+        arboard::Error::ContentNotAvailable => "The clipboard doesn't contain an image".to_string(),
+        err => format!("Failed to read image from the clipboard: {}", err),
+    })?;
+    Ok(ImageDto::new(image.width as u32, image.height as u32, image.bytes.into_owned()))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_shortcuts_command(app_handle: AppHandle) -> ShortcutsDto {
+    app_handle.state::<Mutex<Settings>>().lock().unwrap().shortcuts_dto()
+}
+
+/// Stores the user's chord for `action` in the settings file and updates the
+/// menu accelerators. `None` restores the default. Chords already used by
+/// another action are rejected.
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_shortcut_command(app_handle: AppHandle, action: ShortcutAction, chord: Option<String>) -> Result<ShortcutsDto, String> {
+    let chord = chord.unwrap_or_else(|| action.default_chord().to_string());
+    let chord = Chord::parse(&chord)
+        .ok_or_else(|| format!("\"{}\" is not a valid shortcut", chord))?
+        .to_string();
+
+    let shortcuts = {
+        let state = app_handle.state::<Mutex<Settings>>();
+        let mut settings = state.lock().unwrap();
+
+        let taken_by = settings
+            .shortcuts_dto()
+            .bindings
+            .into_iter()
+            .find(|(other, bound)| *other != action && *bound == chord);
+        if let Some((other, _)) = taken_by {
+            return Err(format!("{} is already used by \"{}\"", chord, other.label()));
+        }
+
+        if Chord::parse(action.default_chord()).is_some_and(|default| default.to_string() == chord) {
+            settings.shortcuts.remove(&action);
+        } else {
+            settings.shortcuts.insert(action, chord);
+        }
+        settings.save(&app_handle)?;
+        settings.shortcuts_dto()
+    };
+
+    refresh_menus(&app_handle).map_err(|err| format!("Failed to update the menu: {}", err))?;
+    Ok(shortcuts)
 }
 
 #[tauri::command(rename_all = "snake_case")]
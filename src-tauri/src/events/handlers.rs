@@ -1,21 +1,37 @@
-use image::ImageReader;
+use crate::document_state::has_unsaved_changes;
+use crate::export::export_image;
+use image::{ImageReader, RgbaImage};
+use pro_pain_t_shared::dtos::export_options::ExportOptionsDto;
 use pro_pain_t_shared::dtos::image::ImageDto;
 use pro_pain_t_shared::dtos::path::PathDto;
 use pro_pain_t_shared::dtos::project::ProjectDto;
-use pro_pain_t_shared::events::events::{EVENT_MENU_EXPORT_PROJECT, EVENT_MENU_IMPORT_AS_LAYER, EVENT_MENU_OPEN_PROJECT, EVENT_MENU_SAVE_PROJECT};
+use pro_pain_t_shared::dtos::open_image::OpenImageDto;
+use pro_pain_t_shared::events::events::{EVENT_MENU_IMPORT_AS_LAYER, EVENT_MENU_OPEN_IMAGE, EVENT_MENU_OPEN_PROJECT, EVENT_MENU_SAVE_PROJECT};
 use std::fs;
+use std::path::Path;
 use tauri::{AppHandle, Emitter};
 use tauri_plugin_dialog::{DialogExt, FilePath, MessageDialogButtons};
 
-/// Returns `true` if the user pressed the OK button.
-pub fn project_overwrite_confirmation(app_handle: &AppHandle) -> bool {
+/// Returns `true` if there's nothing to lose or the user pressed the OK button.
+pub fn unsaved_changes_confirmation(app_handle: &AppHandle, message: &str) -> bool {
+    if !has_unsaved_changes(app_handle) {
+        return true;
+    }
     app_handle.dialog()
-        .message("This will overwrite the currently opened project. Do you want to continue?")
+        .message(message)
         .title("Warning")
         .buttons(MessageDialogButtons::OkCancelCustom("Yes".to_string(), "No".to_string()))
         .blocking_show()
 }
 
+/// Returns `true` if the current project may be replaced.
+pub fn project_overwrite_confirmation(app_handle: &AppHandle) -> bool {
+    unsaved_changes_confirmation(
+        app_handle,
+        "The currently opened project has unsaved changes that will be lost. Do you want to continue?",
+    )
+}
+
 pub fn error_dialog(app_handle: &AppHandle, message: impl ToString) {
     app_handle.dialog()
         .message(message.to_string())
@@ -24,6 +40,29 @@ pub fn error_dialog(app_handle: &AppHandle, message: impl ToString) {
         .blocking_show();
 }
 
+/// Extensions of the image formats that can be opened and imported.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp", "tif", "tiff", "gif"];
+
+fn read_image(app_handle: &AppHandle, path: &str) -> Option<RgbaImage> {
+    let image = ImageReader::open(path);
+    if image.is_err() {
+        error_dialog(app_handle, "Failed to read image file");
+        return None;
+    }
+    let image = image.unwrap().with_guessed_format();
+    if image.is_err() {
+        error_dialog(app_handle, "Failed to read image file");
+        return None;
+    }
+    match image.unwrap().decode() {
+        Ok(image) => Some(image.into_rgba8()),
+        Err(err) => {
+            error_dialog(app_handle, format!("Failed to decode image file: {}", err));
+            None
+        }
+    }
+}
+
 pub fn open_file_handler(app_handle: &AppHandle, file_path: Option<FilePath>) {
     if let Some(path) = file_path {
         let project_file_data = fs::read(path.to_string());
@@ -31,7 +70,7 @@ pub fn open_file_handler(app_handle: &AppHandle, file_path: Option<FilePath>) {
             error_dialog(app_handle, "Failed to read file");
             return;
         }
-        let project_dto = ProjectDto::new(project_file_data.unwrap());
+        let project_dto = ProjectDto::new(path.to_string(), project_file_data.unwrap());
         if app_handle.emit(EVENT_MENU_OPEN_PROJECT, project_dto).is_err() {
             error_dialog(app_handle, "Failed to emit menubar action");
             return;
@@ -50,19 +89,26 @@ pub fn save_project_handler(app_handle: &AppHandle, file_path: Option<FilePath>)
     }
 }
 
-pub fn import_as_layer_handler(app_handle: &AppHandle, file_path: Option<FilePath>) {
+pub fn open_image_handler(app_handle: &AppHandle, file_path: Option<FilePath>) {
     if let Some(path) = file_path {
-        let image = ImageReader::open(path.to_string());
-        if image.is_err() {
-            error_dialog(app_handle, "Failed to read image file");
-            return;
-        }
-        let image = image.unwrap().decode();
-        if image.is_err() {
-            error_dialog(app_handle, "Failed to decode image file");
+        let path = path.to_string();
+        let Some(image) = read_image(app_handle, &path) else { return };
+        let name = Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Unnamed project".to_string());
+        let payload = OpenImageDto::new(name, ImageDto::from_image(image));
+        if app_handle.emit(EVENT_MENU_OPEN_IMAGE, payload).is_err() {
+            error_dialog(app_handle, "Failed to emit menubar action");
             return;
         }
-        let image = image.unwrap().into_rgb8();
+        println!("emitted open_image");
+    }
+}
+
+pub fn import_as_layer_handler(app_handle: &AppHandle, file_path: Option<FilePath>) {
+    if let Some(path) = file_path {
+        let Some(image) = read_image(app_handle, &path.to_string()) else { return };
         let payload = ImageDto::from_image(image);
         if app_handle.emit(EVENT_MENU_IMPORT_AS_LAYER, payload).is_err() {
             error_dialog(app_handle, "Failed to emit menubar action");
@@ -72,12 +118,12 @@ pub fn import_as_layer_handler(app_handle: &AppHandle, file_path: Option<FilePat
     }
 }
 
-pub fn export_project_handler(app_handle: &AppHandle, file_path: Option<FilePath>) {
+pub fn export_project_handler(app_handle: &AppHandle, file_path: Option<FilePath>, image: ImageDto, options: ExportOptionsDto) {
     if let Some(path) = file_path {
-        if app_handle.emit(EVENT_MENU_EXPORT_PROJECT, PathDto::new(path)).is_err() {
-            error_dialog(app_handle, "Failed to emit menubar action");
+        if let Err(message) = export_image(image, &options, &path.to_string()) {
+            error_dialog(app_handle, message);
             return;
         }
-        println!("emitted export_project");
+        println!("exported project to {}", path);
     }
 }
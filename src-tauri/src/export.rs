@@ -0,0 +1,114 @@
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::gif::GifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::codecs::tiff::TiffEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{self, FilterType};
+use image::{ExtendedColorType, ImageEncoder, RgbaImage};
+use pro_pain_t_shared::dtos::export_options::{ExportFormat, ExportOptionsDto, PngCompression};
+use pro_pain_t_shared::dtos::image::ImageDto;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Largest image an export may produce, in pixels (1 GiB of RGBA).
+const MAX_EXPORT_PIXELS: u64 = 1 << 28;
+
+/// Scales, flattens and encodes `image` as described by `options` and writes it to `path`.
+pub fn export_image(image: ImageDto, options: &ExportOptionsDto, path: &str) -> Result<(), String> {
+    let image = RgbaImage::from_raw(image.width, image.height, image.raw_data)
+        .ok_or("Image data doesn't match its dimensions")?;
+    let mut image = scale(image, options.scale)?;
+
+    let background = match options.flatten_background {
+        Some(background) => Some(background),
+        None if !options.format.supports_alpha() => Some([255, 255, 255]),
+        None => None,
+    };
+    if let Some(background) = background {
+        flatten(&mut image, background);
+    }
+
+    let file = File::create(path).map_err(|err| format!("Failed to create {}: {}", path, err))?;
+    let mut writer = BufWriter::new(file);
+    let (width, height) = image.dimensions();
+    let label = options.format.label();
+    let encoder_error = |err: image::ImageError| format!("Failed to encode {}: {}", label, err);
+
+    match options.format {
+        ExportFormat::Png => {
+            let compression = match options.png_compression {
+                PngCompression::Fast => CompressionType::Fast,
+                PngCompression::Default => CompressionType::Default,
+                PngCompression::Best => CompressionType::Best,
+            };
+            PngEncoder::new_with_quality(&mut writer, compression, PngFilterType::Adaptive)
+                .write_image(image.as_raw(), width, height, ExtendedColorType::Rgba8)
+                .map_err(encoder_error)?;
+        }
+        ExportFormat::Jpeg => {
+            let rgb = image::DynamicImage::ImageRgba8(image).into_rgb8();
+            JpegEncoder::new_with_quality(&mut writer, options.jpeg_quality.clamp(1, 100))
+                .write_image(rgb.as_raw(), width, height, ExtendedColorType::Rgb8)
+                .map_err(encoder_error)?;
+        }
+        ExportFormat::WebP => {
+            WebPEncoder::new_lossless(&mut writer)
+                .write_image(image.as_raw(), width, height, ExtendedColorType::Rgba8)
+                .map_err(encoder_error)?;
+        }
+        ExportFormat::Bmp => {
+            BmpEncoder::new(&mut writer)
+                .write_image(image.as_raw(), width, height, ExtendedColorType::Rgba8)
+                .map_err(encoder_error)?;
+        }
+        ExportFormat::Tiff => {
+            TiffEncoder::new(&mut writer)
+                .write_image(image.as_raw(), width, height, ExtendedColorType::Rgba8)
+                .map_err(encoder_error)?;
+        }
+        ExportFormat::Gif => {
+            GifEncoder::new(&mut writer)
+                .encode(image.as_raw(), width, height, ExtendedColorType::Rgba8)
+                .map_err(encoder_error)?;
+        }
+    }
+
+    writer.flush().map_err(|err| format!("Failed to write {}: {}", path, err))
+}
+
+/// Upscaling keeps hard pixel edges, downscaling is smoothed.
+fn scale(image: RgbaImage, factor: f32) -> Result<RgbaImage, String> {
+    if !factor.is_finite() || factor <= 0.0 {
+        return Err(format!("Invalid export scale {}", factor));
+    }
+    if factor == 1.0 {
+        return Ok(image);
+    }
+
+    let width = (image.width() as f64 * factor as f64).round().max(1.0);
+    let height = (image.height() as f64 * factor as f64).round().max(1.0);
+    if width * height > MAX_EXPORT_PIXELS as f64 {
+        return Err(format!(
+            "Scaled to {}%, the image would be {} × {} pixels, which is too large to export",
+            (factor * 100.0).round(),
+            width,
+            height
+        ));
+    }
+    // Both fit in `u32` since their product is bounded
+    let (width, height) = (width as u32, height as u32);
+    let filter = if factor > 1.0 { FilterType::Nearest } else { FilterType::Lanczos3 };
+    Ok(imageops::resize(&image, width, height, filter))
+}
+
+fn flatten(image: &mut RgbaImage, background: [u8; 3]) {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in 0..3 {
+            let blended = pixel[channel] as u32 * alpha + background[channel] as u32 * (255 - alpha);
+            pixel[channel] = ((blended + 127) / 255) as u8;
+        }
+        pixel[3] = 255;
+    }
+}
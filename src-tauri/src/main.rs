@@ -1,20 +1,51 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod autosave;
+mod document_state;
 mod menus;
 mod events;
+mod export;
+mod settings;
 
 use crate::menus::setup_menus;
-use crate::events::commands::{error_dialog_command, export_project_command, save_project_command};
+use crate::document_state::DocumentState;
+use crate::autosave::AutosaveState;
+use crate::events::commands::{error_dialog_command, export_project_command, get_shortcuts_command, read_clipboard_image_command, save_project_command, set_shortcut_command, take_recovered_project_command, update_document_state_command, write_clipboard_image_command};
+use crate::events::handlers::unsaved_changes_confirmation;
+use crate::settings::Settings;
 use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Manager, RunEvent, WindowEvent};
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
+            app.manage(Mutex::new(DocumentState::default()));
+            let settings = Settings::load(app.handle());
+            if let Err(err) = autosave::start(app.handle(), Duration::from_secs(settings.autosave_interval_secs)) {
+                eprintln!("Autosave unavailable: {}", err);
+            }
+            app.manage(Mutex::new(settings));
             setup_menus(app)?;
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![save_project_command, export_project_command, error_dialog_command])
-        .run(tauri::generate_context!())
-        .expect("Error while running Pro PainT Tauri application");
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if !unsaved_changes_confirmation(window.app_handle(), "The project has unsaved changes. Quit anyway?") {
+                    api.prevent_close();
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![save_project_command, export_project_command, error_dialog_command, update_document_state_command, take_recovered_project_command, write_clipboard_image_command, read_clipboard_image_command, get_shortcuts_command, set_shortcut_command])
+        .build(tauri::generate_context!())
+        .expect("Error while running Pro PainT Tauri application")
+        .run(|app_handle, event| {
+            if let RunEvent::Exit = event {
+                if let Some(autosave) = app_handle.try_state::<AutosaveState>() {
+                    autosave.remove_session_file();
+                }
+            }
+        });
 }
@@ -1,36 +1,83 @@
-use crate::events::handlers::{error_dialog, export_project_handler, import_as_layer_handler, open_file_handler, project_overwrite_confirmation, save_project_handler};
-use pro_pain_t_shared::events::events::{EVENT_MENU_CANVAS_SIZE, EVENT_MENU_NEW_PROJECT, EVENT_MENU_REDO, EVENT_MENU_UNDO};
-use tauri::menu::{MenuBuilder, SubmenuBuilder};
-use tauri::{App, AppHandle, Emitter};
+use crate::events::handlers::{error_dialog, import_as_layer_handler, open_file_handler, open_image_handler, IMAGE_EXTENSIONS, project_overwrite_confirmation, save_project_handler, unsaved_changes_confirmation};
+use crate::document_state::project_path;
+use crate::settings::Settings;
+use pro_pain_t_shared::dtos::shortcuts::ShortcutsDto;
+use pro_pain_t_shared::dtos::path::PathDto;
+use pro_pain_t_shared::events::events::{EVENT_MENU_CANVAS_SIZE, EVENT_MENU_COPY, EVENT_MENU_CUT, EVENT_MENU_EXPORT_PROJECT, EVENT_MENU_FILL_WINDOW, EVENT_MENU_FIT_TO_WINDOW, EVENT_MENU_NEW_PROJECT, EVENT_MENU_PASTE, EVENT_MENU_PASTE_AS_LAYER, EVENT_MENU_REDO, EVENT_MENU_SAVE_PROJECT, EVENT_MENU_SHORTCUTS, EVENT_MENU_UNDO};
+use pro_pain_t_shared::shortcuts::ShortcutAction;
+use tauri::menu::{Menu, MenuBuilder, MenuItem, MenuItemBuilder, SubmenuBuilder};
+use tauri::{App, AppHandle, Emitter, Manager, Wry};
+use std::path::Path;
+use std::sync::Mutex;
 use tauri_plugin_dialog::DialogExt;
 
-pub fn setup_menus(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+/// Builds the menu bar with the accelerators from the settings.
+fn build_menu<M: Manager<Wry>>(app: &M) -> tauri::Result<Menu<Wry>> {
+    let shortcuts = app.state::<Mutex<Settings>>().lock().unwrap().shortcuts_dto();
+
     let dummy_menu = SubmenuBuilder::new(app, "Pro PainT").build()?;
 
     let file_menu = SubmenuBuilder::new(app, "File")
         .text("new_project", "New project")
         .text("open_project", "Open project")
-        .text("save_project", "Save project")
+        .text("open_image", "Open image...")
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::Save, "Save project")?)
+        .text("save_project_as", "Save project as...")
         .separator()
         .text("import_as_layer", "Import image as Layer")
         .separator()
-        .text("export_project", "Export project")
+        .text("export_project", "Export project...")
         .separator()
         .text("quit", "Quit")
         .build()?;
 
     let edit_menu = SubmenuBuilder::new(app, "Edit")
-        .text("undo", "Undo")
-        .text("redo", "Redo")
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::Undo, "Undo")?)
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::Redo, "Redo")?)
+        .separator()
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::Cut, "Cut")?)
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::Copy, "Copy")?)
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::Paste, "Paste")?)
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::PasteAsLayer, "Paste as new layer")?)
         .separator()
         .text("canvas_size", "Canvas size...")
+        .separator()
+        .text("shortcuts", "Keyboard shortcuts...")
         .build()?;
 
-    let menu = MenuBuilder::new(app)
-        .items(&[&dummy_menu, &file_menu, &edit_menu])
+    let view_menu = SubmenuBuilder::new(app, "View")
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::FitToWindow, "Fit to window")?)
+        .item(&shortcut_item(app, &shortcuts, ShortcutAction::FillWindow, "Fill window")?)
         .build()?;
 
-    app.set_menu(menu)?;
+    MenuBuilder::new(app)
+        .items(&[&dummy_menu, &file_menu, &edit_menu, &view_menu])
+        .build()
+}
+
+/// Menu item for an action that has one, with the action's accelerator.
+fn shortcut_item<M: Manager<Wry>>(
+    manager: &M,
+    shortcuts: &ShortcutsDto,
+    action: ShortcutAction,
+    label: &str,
+) -> tauri::Result<MenuItem<Wry>> {
+    let id = action.menu_id().expect("Shortcut action has no menu item");
+    let mut builder = MenuItemBuilder::with_id(id, label);
+    if let Some(chord) = shortcuts.bindings.get(&action) {
+        builder = builder.accelerator(chord);
+    }
+    builder.build(manager)
+}
+
+/// Rebuilds the menu bar after the shortcuts changed.
+pub fn refresh_menus(app_handle: &AppHandle) -> tauri::Result<()> {
+    app_handle.set_menu(build_menu(app_handle)?)?;
+    Ok(())
+}
+
+pub fn setup_menus(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    app.set_menu(build_menu(app)?)?;
 
     app.on_menu_event(move |handle: &AppHandle, event| {
         let app_handle = handle.clone();
@@ -56,29 +103,50 @@ pub fn setup_menus(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
                     .pick_file(move |file_path| open_file_handler(&app_handle, file_path));
             }
 
-            "save_project" => {
+            "open_image" => {
+                if !project_overwrite_confirmation(&app_handle) {
+                    return;
+                }
                 app_handle.dialog().file()
-                    .add_filter("ProPainTProject", &["ppp"])
-                    .set_file_name("unnamed.ppp")
-                    .set_can_create_directories(true)
-                    .save_file(move |file_path| save_project_handler(&app_handle, file_path));
+                    .add_filter("Images", IMAGE_EXTENSIONS)
+                    .pick_file(move |file_path| open_image_handler(&app_handle, file_path));
+            }
+
+            "save_project" => {
+                // Projects that have a file are saved in place without asking
+                let Some(path) = project_path(&app_handle) else {
+                    save_project_as(app_handle);
+                    return;
+                };
+                if app_handle.emit(EVENT_MENU_SAVE_PROJECT, PathDto::new(path)).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted save_project");
+            }
+
+            "save_project_as" => {
+                save_project_as(app_handle);
             }
 
             "import_as_layer" => {
                 app_handle.dialog().file()
-                    .add_filter("PNG or JPG images", &["png", "jpg", "jpeg"])
+                    .add_filter("Images", IMAGE_EXTENSIONS)
                     .pick_file(move |file_path| import_as_layer_handler(&app_handle, file_path));
             }
 
             "export_project" => {
-                app_handle.dialog().file()
-                    .add_filter("PNG or JPG images", &["png", "jpg", "jpeg"])
-                    .set_file_name("unnamed_export")
-                    .set_can_create_directories(true)
-                    .save_file(move |file_path| export_project_handler(&app_handle, file_path));
+                if app_handle.emit(EVENT_MENU_EXPORT_PROJECT, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted export_project");
             }
 
             "quit" => {
+                if !unsaved_changes_confirmation(&app_handle, "The project has unsaved changes. Quit anyway?") {
+                    return;
+                }
                 app_handle.exit(0);
             }
 
@@ -99,6 +167,38 @@ pub fn setup_menus(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
                 println!("emitted redo");
             }
 
+            "cut" => {
+                if app_handle.emit(EVENT_MENU_CUT, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted cut");
+            }
+
+            "copy" => {
+                if app_handle.emit(EVENT_MENU_COPY, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted copy");
+            }
+
+            "paste" => {
+                if app_handle.emit(EVENT_MENU_PASTE, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted paste");
+            }
+
+            "paste_as_layer" => {
+                if app_handle.emit(EVENT_MENU_PASTE_AS_LAYER, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted paste_as_layer");
+            }
+
             "canvas_size" => {
                 if app_handle.emit(EVENT_MENU_CANVAS_SIZE, ()).is_err() {
                     error_dialog(&app_handle, "Failed to emit menubar action");
@@ -107,6 +207,31 @@ pub fn setup_menus(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
                 println!("emitted canvas_size");
             }
 
+            "shortcuts" => {
+                if app_handle.emit(EVENT_MENU_SHORTCUTS, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted shortcuts");
+            }
+
+            // ===== View =====
+            "fit_to_window" => {
+                if app_handle.emit(EVENT_MENU_FIT_TO_WINDOW, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted fit_to_window");
+            }
+
+            "fill_window" => {
+                if app_handle.emit(EVENT_MENU_FILL_WINDOW, ()).is_err() {
+                    error_dialog(&app_handle, "Failed to emit menubar action");
+                    return;
+                }
+                println!("emitted fill_window");
+            }
+
             _ => {
                 println!("Unhandled menu event: {:?}", event.id());
             }
@@ -115,3 +240,14 @@ pub fn setup_menus(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+fn save_project_as(app_handle: AppHandle) {
+    let file_name = project_path(&app_handle)
+        .and_then(|path| Path::new(&path).file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "unnamed.ppp".to_string());
+    app_handle.dialog().file()
+        .add_filter("ProPainTProject", &["ppp"])
+        .set_file_name(file_name)
+        .set_can_create_directories(true)
+        .save_file(move |file_path| save_project_handler(&app_handle, file_path));
+}
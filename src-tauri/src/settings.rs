@@ -0,0 +1,61 @@
+use pro_pain_t_shared::dtos::shortcuts::ShortcutsDto;
+use pro_pain_t_shared::shortcuts::{deserialize_overrides, resolve_shortcuts, ShortcutOverrides};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// User preferences stored as JSON in the app config directory. Missing
+/// fields fall back to their defaults, so older files keep working.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Seconds between autosaves of unsaved changes, `0` disables autosave.
+    pub autosave_interval_secs: u64,
+    /// Chords the user picked instead of the defaults, see `ShortcutAction`.
+    #[serde(deserialize_with = "deserialize_overrides")]
+    pub shortcuts: ShortcutOverrides,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { autosave_interval_secs: 120, shortcuts: ShortcutOverrides::new() }
+    }
+}
+
+impl Settings {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let Ok(config_dir) = app_handle.path().app_config_dir() else {
+            return Self::default();
+        };
+        let Ok(data) = fs::read_to_string(config_dir.join(SETTINGS_FILE)) else {
+            return Self::default();
+        };
+        serde_json::from_str(&data).unwrap_or_else(|err| {
+            eprintln!("Ignoring invalid {}: {}", SETTINGS_FILE, err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, app_handle: &AppHandle) -> Result<(), String> {
+        let config_dir = app_handle
+            .path()
+            .app_config_dir()
+            .map_err(|err| format!("Config directory unavailable: {}", err))?;
+        fs::create_dir_all(&config_dir)
+            .map_err(|err| format!("Failed to create {}: {}", config_dir.display(), err))?;
+        let data = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(config_dir.join(SETTINGS_FILE), data)
+            .map_err(|err| format!("Failed to write {}: {}", SETTINGS_FILE, err))
+    }
+
+    pub fn shortcuts_dto(&self) -> ShortcutsDto {
+        ShortcutsDto {
+            bindings: resolve_shortcuts(&self.shortcuts)
+                .into_iter()
+                .map(|(action, chord)| (action, chord.to_string()))
+                .collect(),
+        }
+    }
+}